@@ -0,0 +1,6 @@
+-- The core engine records every compute/matrix call, including operations
+-- added after 006 (sparse, solve, pairwise, ...), so the operation list is
+-- no longer enumerated here.
+alter table public.compute_jobs drop constraint if exists compute_jobs_operation_check;
+create index if not exists idx_compute_jobs_user_time on public.compute_jobs(user_id, created_at desc);
+create index if not exists idx_compute_benchmarks_user_time on public.compute_benchmarks(user_id, created_at desc);
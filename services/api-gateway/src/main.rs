@@ -1,4 +1,6 @@
 #![allow(dead_code)]
+mod telemetry;
+
 use axum::{
     body::Body,
     extract::{Request, State},
@@ -14,14 +16,27 @@ use std::sync::Arc;
 use std::time::Instant;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
+use tracing::Instrument;
 
 struct AppState {
     core_url: String,
     jwt_secret: String,
     rate_limiters: DashMap<String, TokenBucket>,
+    /// Body cap per plan, in `PLANS` order.
+    max_body_bytes: [usize; 3],
     start_time: Instant,
 }
 
+/// Plan tiers the core engine knows; tokens without a (recognised) `plan`
+/// claim, and API-key callers, are Free.
+const PLANS: [&str; 3] = ["Free", "Pro", "Enterprise"];
+
+fn plan_index(c: Option<&Claims>) -> usize {
+    c.and_then(|c| c.plan.as_deref())
+        .and_then(|p| PLANS.iter().position(|n| n.eq_ignore_ascii_case(p.trim())))
+        .unwrap_or(0)
+}
+
 struct TokenBucket {
     tokens: f64,
     max_tokens: f64,
@@ -52,21 +67,18 @@ struct Err { error: String, #[serde(skip_serializing_if = "Option::is_none")] de
 struct LicenseInfo { license: String, source_code: String, notice: String }
 
 #[derive(Deserialize, Serialize, Clone)]
-struct Claims { sub: String, email: Option<String>, role: Option<String>, exp: usize }
+struct Claims { sub: String, email: Option<String>, role: Option<String>, plan: Option<String>, exp: usize }
 
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "api_gateway=info,tower_http=info".into()),
-        )
-        .init();
+    let tracer = telemetry::init();
     let env = |k: &str, d: &str| std::env::var(k).unwrap_or_else(|_| d.into());
     let state = Arc::new(AppState {
         core_url: env("CORE_ENGINE_URL", "http://core-engine:8081"),
         jwt_secret: env("JWT_SECRET", "dev-secret-change-me"),
         rate_limiters: DashMap::new(),
+        max_body_bytes: [("FREE", 16), ("PRO", 128), ("ENTERPRISE", 512)]
+            .map(|(p, mib)| env(&format!("GATEWAY_MAX_BODY_BYTES_{p}"), "").parse().unwrap_or(mib << 20)),
         start_time: Instant::now(),
     });
     let cors = CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any);
@@ -74,19 +86,20 @@ async fn main() {
         .route("/health", get(health))
         .route("/license", get(license_handler));
     let api = Router::new()
-        .route("/api/v1/{*p}", any(proxy_core))
+        .route("/api/v1/*p", any(proxy_core))
         .layer(middleware::from_fn_with_state(state.clone(), auth_mw))
         .layer(middleware::from_fn_with_state(state.clone(), rate_mw));
     let app = Router::new()
         .merge(public)
         .merge(api)
         .layer(cors)
-        .layer(TraceLayer::new_for_http())
+        .layer(TraceLayer::new_for_http().make_span_with(telemetry::request_span).on_response(telemetry::record_status))
         .with_state(state);
     let addr = std::env::var("GATEWAY_ADDR").unwrap_or_else(|_| "0.0.0.0:8080".into());
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
     tracing::info!("API Gateway on {addr}");
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app).with_graceful_shutdown(telemetry::shutdown_signal()).await.unwrap();
+    if let Some(t) = tracer { let _ = t.shutdown(); }
 }
 
 async fn health(State(s): State<Arc<AppState>>) -> Json<Health> {
@@ -127,7 +140,7 @@ async fn auth_mw(
         }
     }
     if api_key.is_some() {
-        req.extensions_mut().insert(Claims { sub: "api-key-user".into(), email: None, role: Some("api".into()), exp: usize::MAX });
+        req.extensions_mut().insert(Claims { sub: "api-key-user".into(), email: None, role: Some("api".into()), plan: None, exp: usize::MAX });
         return Ok(next.run(req).await);
     }
     Err((StatusCode::UNAUTHORIZED, Json(Err { error: "Auth required".into(), details: Some("Provide Bearer token or X-API-Key".into()) })))
@@ -145,13 +158,18 @@ async fn rate_mw(
     Ok(next.run(req).await)
 }
 
-async fn forward(url: &str, req: Request) -> Result<Response, (StatusCode, Json<Err>)> {
+async fn forward(url: &str, req: Request, max_body: usize) -> Result<Response, (StatusCode, Json<Err>)> {
     let client = reqwest::Client::new();
     let path = req.uri().path().to_owned();
     let q = req.uri().query().map(|q| format!("?{q}")).unwrap_or_default();
     let method = req.method().clone();
-    let hdrs = req.headers().clone();
-    let body = axum::body::to_bytes(req.into_body(), 50 * 1024 * 1024).await
+    let mut hdrs = req.headers().clone();
+    telemetry::inject(&mut hdrs);
+    let too_large = || (StatusCode::PAYLOAD_TOO_LARGE, Json(Err { error: "Request body too large".into(), details: Some(format!("limit is {max_body} bytes")) }));
+    let declared = hdrs.get("content-length").and_then(|v| v.to_str().ok()).and_then(|v| v.parse::<usize>().ok());
+    if declared.is_some_and(|n| n > max_body) { return Err(too_large()); }
+    // Chunked bodies have no length to check up front; they fail here instead.
+    let body = axum::body::to_bytes(req.into_body(), max_body).await
         .map_err(|e| (StatusCode::BAD_REQUEST, Json(Err { error: "Body read fail".into(), details: Some(e.to_string()) })))?;
     let mut r = client.request(method, format!("{url}{path}{q}"));
     for (k, v) in hdrs.iter() { if k != "host" { r = r.header(k, v); } }
@@ -167,8 +185,24 @@ async fn forward(url: &str, req: Request) -> Result<Response, (StatusCode, Json<
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(Err { error: "Build fail".into(), details: Some(e.to_string()) })))
 }
 
+/// Engine endpoints that act on the whole engine rather than the caller's data.
+const ADMIN_PATHS: &[&str] = &["/api/v1/simd/stats/reset"];
+
 async fn proxy_core(
-    State(s): State<Arc<AppState>>, req: Request,
+    State(s): State<Arc<AppState>>, mut req: Request,
 ) -> Result<Response, (StatusCode, Json<Err>)> {
-    forward(&s.core_url, req).await
+    // The core engine attributes history to X-User-Id and sizes its limits
+    // by X-Plan; only the verified token may set either.
+    let claims = req.extensions().get::<Claims>();
+    // Stats reset zeroes figures every tenant sees; only admins may do it.
+    if ADMIN_PATHS.contains(&req.uri().path()) && claims.and_then(|c| c.role.as_deref()) != Some("admin") {
+        return Err((StatusCode::FORBIDDEN, Json(Err { error: "Admin role required".into(), details: Some(req.uri().path().into()) })));
+    }
+    let sub = claims.map(|c| c.sub.clone());
+    let plan = plan_index(claims);
+    req.headers_mut().remove("x-user-id");
+    if let Some(v) = sub.and_then(|sub| sub.parse().ok()) { req.headers_mut().insert("x-user-id", v); }
+    req.headers_mut().insert("x-plan", axum::http::HeaderValue::from_static(PLANS[plan]));
+    let span = tracing::info_span!("forward", otel.name = %format!("{} {}", req.method(), req.uri().path()), otel.kind = "client");
+    forward(&s.core_url, req, s.max_body_bytes[plan]).instrument(span).await
 }
@@ -0,0 +1,77 @@
+// Logging plus optional OpenTelemetry tracing, as in the core engine: spans
+// go to an OTLP/HTTP collector when OTEL_EXPORTER_OTLP_ENDPOINT (or
+// OTEL_EXPORTER_OTLP_TRACES_ENDPOINT) is set. Request spans continue a
+// client's `traceparent`, and `inject` passes the current span on to the
+// core engine so both services land in one trace.
+use axum::http::{HeaderMap, Request, Response};
+use opentelemetry::{global, trace::TracerProvider as _};
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource};
+use std::time::Duration;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+const SERVICE: &str = "api-gateway";
+
+/// Installs the log subscriber and, if a collector is configured, the OTLP
+/// exporter. Keep the provider and `shutdown` it on exit to flush spans.
+pub fn init() -> Option<SdkTracerProvider> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let configured = ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"].iter().any(|k| std::env::var(k).is_ok_and(|v| !v.is_empty()));
+    let exporter = configured.then(|| opentelemetry_otlp::SpanExporter::builder().with_http().build());
+    let (provider, error) = match exporter {
+        Some(Ok(exporter)) => {
+            let service = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| SERVICE.into());
+            let provider = SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(Resource::builder().with_service_name(service).build())
+                .build();
+            (Some(provider), None)
+        }
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| "api_gateway=info,tower_http=info".into()))
+        .with(tracing_subscriber::fmt::layer())
+        .with(provider.as_ref().map(|p| tracing_opentelemetry::layer().with_tracer(p.tracer(SERVICE))))
+        .init();
+    if let Some(e) = error { tracing::warn!("OTLP exporter disabled: {e}"); }
+    provider
+}
+
+/// Resolves on Ctrl-C or SIGTERM.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    let term = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut s) => { s.recv().await; }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let term = std::future::pending::<()>();
+    tokio::select! { _ = tokio::signal::ctrl_c() => {}, _ = term => {} }
+}
+
+/// Server span for one request, parented on its `traceparent` if any.
+pub fn request_span<B>(req: &Request<B>) -> Span {
+    let (method, path) = (req.method(), req.uri().path());
+    let span = tracing::info_span!("request",
+        otel.name = %format!("{method} {path}"), otel.kind = "server",
+        http.request.method = %method, url.path = %path, http.response.status_code = tracing::field::Empty);
+    let _ = span.set_parent(global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(req.headers()))));
+    span
+}
+
+pub fn record_status<B>(res: &Response<B>, _latency: Duration, span: &Span) {
+    span.record("http.response.status_code", res.status().as_u16());
+}
+
+/// Writes the current span's `traceparent` (and `tracestate`) into `headers`,
+/// replacing the caller's. Leaves them untouched when tracing is off.
+pub fn inject(headers: &mut HeaderMap) {
+    let cx = Span::current().context();
+    global::get_text_map_propagator(|p| p.inject_context(&cx, &mut HeaderInjector(headers)));
+}
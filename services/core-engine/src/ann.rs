@@ -0,0 +1,567 @@
+// ── Approximate nearest-neighbour indexes ───────────────────
+// HNSW graph and IVF-PQ, both operating on slots of a `VectorStore`.
+// Slots are append-only; deletes are tombstones in the store (HNSW keeps
+// the node for connectivity) or list removals (IVF-PQ).
+use crate::simd;
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric { L2, Cosine, Dot }
+
+impl Metric {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "l2" | "euclidean" => Some(Metric::L2),
+            "cosine" => Some(Metric::Cosine),
+            "dot" | "inner_product" => Some(Metric::Dot),
+            _ => None,
+        }
+    }
+}
+
+// ── Vector store ────────────────────────────────────────────
+#[derive(Serialize, Deserialize)]
+pub struct VectorStore {
+    pub dim: usize,
+    pub metric: Metric,
+    pub ids: Vec<u64>,
+    pub data: Vec<f32>,
+    pub live: Vec<bool>,
+}
+
+impl VectorStore {
+    pub fn new(dim: usize, metric: Metric) -> Self {
+        Self { dim, metric, ids: Vec::new(), data: Vec::new(), live: Vec::new() }
+    }
+    pub fn len(&self) -> usize { self.ids.len() }
+    pub fn is_empty(&self) -> bool { self.ids.is_empty() }
+    pub fn live_count(&self) -> usize { self.live.iter().filter(|l| **l).count() }
+    #[inline]
+    pub fn vector(&self, slot: u32) -> &[f32] {
+        let s = slot as usize * self.dim;
+        &self.data[s..s + self.dim]
+    }
+    /// Cosine vectors are stored unit-length, so every metric reduces to an
+    /// L2 or dot kernel. Smaller is always closer.
+    #[inline]
+    pub fn distance(&self, q: &[f32], slot: u32) -> f32 {
+        let v = self.vector(slot);
+        match self.metric {
+            Metric::L2 => simd::l2_sq_f32(q, v),
+            Metric::Cosine => 1.0 - simd::dot_f32(q, v),
+            Metric::Dot => -simd::dot_f32(q, v),
+        }
+    }
+    #[inline]
+    pub fn slot_distance(&self, a: u32, b: u32) -> f32 {
+        self.distance(self.vector(a), b)
+    }
+    /// Applies the metric's input transform (unit-normalisation for cosine).
+    pub fn prepare(&self, v: &[f64]) -> Vec<f32> {
+        let mut out: Vec<f32> = v.iter().map(|x| *x as f32).collect();
+        if self.metric == Metric::Cosine {
+            let mag = simd::norm_f32(&out);
+            if mag > 1e-12 { out.iter_mut().for_each(|x| *x /= mag); }
+        }
+        out
+    }
+    pub fn push(&mut self, id: u64, v: &[f32]) -> u32 {
+        self.ids.push(id);
+        self.data.extend_from_slice(v);
+        self.live.push(true);
+        (self.ids.len() - 1) as u32
+    }
+    /// Exact top-k scan over live slots.
+    pub fn brute_force(&self, q: &[f32], k: usize) -> Vec<(f32, u32)> {
+        let k = k.min(self.len());
+        let mut heap: BinaryHeap<(Dist, u32)> = BinaryHeap::with_capacity(k + 1);
+        for slot in 0..self.len() as u32 {
+            if !self.live[slot as usize] { continue; }
+            push_bounded(&mut heap, k, self.distance(q, slot), slot);
+        }
+        into_sorted(heap)
+    }
+}
+
+// ── Ordering helpers ────────────────────────────────────────
+#[derive(Clone, Copy, PartialEq)]
+pub struct Dist(pub f32);
+impl Eq for Dist {}
+impl PartialOrd for Dist {
+    fn partial_cmp(&self, o: &Self) -> Option<Ordering> { Some(self.cmp(o)) }
+}
+impl Ord for Dist {
+    fn cmp(&self, o: &Self) -> Ordering { self.0.total_cmp(&o.0) }
+}
+
+/// Keeps the `k` smallest distances in a max-heap.
+fn push_bounded(heap: &mut BinaryHeap<(Dist, u32)>, k: usize, d: f32, slot: u32) {
+    if heap.len() < k {
+        heap.push((Dist(d), slot));
+    } else if let Some(top) = heap.peek() {
+        if d < top.0 .0 { heap.pop(); heap.push((Dist(d), slot)); }
+    }
+}
+
+fn into_sorted(heap: BinaryHeap<(Dist, u32)>) -> Vec<(f32, u32)> {
+    heap.into_sorted_vec().into_iter().map(|(d, s)| (d.0, s)).collect()
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Rng(u64);
+impl Rng {
+    pub fn new(seed: u64) -> Self { Self(seed ^ 0x9E37_79B9_7F4A_7C15) }
+    pub fn next_u64(&mut self) -> u64 {
+        // splitmix64
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    pub fn next_f64(&mut self) -> f64 { (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64 }
+    pub fn below(&mut self, n: usize) -> usize { (self.next_u64() % n.max(1) as u64) as usize }
+}
+
+// ── Index wrapper ───────────────────────────────────────────
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnnIndex { Hnsw(Hnsw), IvfPq(IvfPq) }
+
+impl AnnIndex {
+    pub fn kind(&self) -> &'static str {
+        match self { AnnIndex::Hnsw(_) => "hnsw", AnnIndex::IvfPq(_) => "ivf_pq" }
+    }
+    pub fn insert(&mut self, store: &VectorStore, slot: u32) {
+        match self { AnnIndex::Hnsw(h) => h.insert(store, slot), AnnIndex::IvfPq(p) => p.insert(store, slot) }
+    }
+    pub fn remove(&mut self, slot: u32) {
+        // HNSW relies on the store tombstone so the node still routes searches.
+        if let AnnIndex::IvfPq(p) = self { p.remove(slot) }
+    }
+    /// `rerank` > 1 pulls `k * rerank` PQ candidates and re-sorts them
+    /// with exact distances; HNSW already returns exact distances.
+    pub fn search(&self, store: &VectorStore, q: &[f32], k: usize, ef: usize, nprobe: usize, rerank: usize) -> Vec<(f32, u32)> {
+        match self {
+            AnnIndex::Hnsw(h) => h.search(store, q, k, ef),
+            AnnIndex::IvfPq(p) if rerank > 1 => {
+                let pool = k.saturating_mul(rerank).min(store.len());
+                let mut c: Vec<(f32, u32)> = p.search(store, q, pool, nprobe).into_iter()
+                    .map(|(_, s)| (store.distance(q, s), s)).collect();
+                c.sort_by(|a, b| a.0.total_cmp(&b.0));
+                c.truncate(k);
+                c
+            }
+            AnnIndex::IvfPq(p) => p.search(store, q, k, nprobe),
+        }
+    }
+    pub fn params(&self) -> serde_json::Value {
+        match self {
+            AnnIndex::Hnsw(h) => serde_json::json!({
+                "m": h.m, "ef_construction": h.ef_construction, "max_level": h.max_level,
+            }),
+            AnnIndex::IvfPq(p) => serde_json::json!({
+                "nlist": p.nlist, "m": p.m, "nbits": p.nbits, "ksub": p.ksub,
+            }),
+        }
+    }
+}
+
+// ── HNSW ────────────────────────────────────────────────────
+#[derive(Serialize, Deserialize)]
+pub struct Hnsw {
+    pub m: usize,
+    pub m0: usize,
+    pub ef_construction: usize,
+    ml: f64,
+    /// Per-slot adjacency, one list per layer the node lives on.
+    links: Vec<Vec<Vec<u32>>>,
+    entry: Option<u32>,
+    pub max_level: usize,
+    rng: Rng,
+}
+
+impl Hnsw {
+    pub fn new(m: usize, ef_construction: usize, seed: u64) -> Self {
+        let m = m.max(2);
+        Self {
+            m, m0: m * 2, ef_construction: ef_construction.max(m),
+            ml: 1.0 / (m as f64).ln(), links: Vec::new(), entry: None, max_level: 0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Stops early, with a partial graph, once the surrounding job or
+    /// request is cancelled.
+    pub fn build(store: &VectorStore, m: usize, ef_construction: usize, seed: u64) -> Self {
+        let mut h = Self::new(m, ef_construction, seed);
+        for slot in 0..store.len() as u32 {
+            if slot % 256 == 255 && crate::jobs::cancelled() { break; }
+            if store.live[slot as usize] { h.insert(store, slot); }
+        }
+        h
+    }
+
+    fn random_level(&mut self) -> usize {
+        let u = self.rng.next_f64().max(f64::MIN_POSITIVE);
+        ((-u.ln()) * self.ml).floor() as usize
+    }
+
+    pub fn insert(&mut self, store: &VectorStore, slot: u32) {
+        let level = self.random_level();
+        if self.links.len() <= slot as usize { self.links.resize(slot as usize + 1, Vec::new()); }
+        self.links[slot as usize] = vec![Vec::new(); level + 1];
+        let Some(mut ep) = self.entry else {
+            self.entry = Some(slot);
+            self.max_level = level;
+            return;
+        };
+        let q = store.vector(slot);
+        for layer in (level + 1..=self.max_level).rev() {
+            ep = self.greedy(store, q, ep, layer);
+        }
+        let mut eps = vec![(store.distance(q, ep), ep)];
+        for layer in (0..=level.min(self.max_level)).rev() {
+            let found = self.search_layer(store, q, &eps, self.ef_construction, layer, false);
+            let cap = if layer == 0 { self.m0 } else { self.m };
+            let chosen = select_neighbours(store, &found, self.m);
+            for &nb in &chosen {
+                let list = &mut self.links[nb as usize][layer];
+                list.push(slot);
+                if list.len() > cap {
+                    let mut cands: Vec<(f32, u32)> = list.iter()
+                        .map(|&o| (store.slot_distance(nb, o), o)).collect();
+                    cands.sort_by(|a, b| a.0.total_cmp(&b.0));
+                    self.links[nb as usize][layer] = select_neighbours(store, &cands, cap);
+                }
+            }
+            self.links[slot as usize][layer] = chosen;
+            eps = found;
+        }
+        if level > self.max_level {
+            self.max_level = level;
+            self.entry = Some(slot);
+        }
+    }
+
+    fn greedy(&self, store: &VectorStore, q: &[f32], mut ep: u32, layer: usize) -> u32 {
+        let mut best = store.distance(q, ep);
+        loop {
+            let mut moved = false;
+            for &nb in &self.links[ep as usize][layer] {
+                let d = store.distance(q, nb);
+                if d < best { best = d; ep = nb; moved = true; }
+            }
+            if !moved { return ep; }
+        }
+    }
+
+    /// Best-first beam search on one layer. With `live_only`, tombstoned
+    /// nodes are still expanded but never returned.
+    fn search_layer(
+        &self, store: &VectorStore, q: &[f32], eps: &[(f32, u32)], ef: usize, layer: usize, live_only: bool,
+    ) -> Vec<(f32, u32)> {
+        let mut visited: HashSet<u32> = HashSet::with_capacity(ef.saturating_mul(8).min(store.len()));
+        let mut candidates: BinaryHeap<Reverse<(Dist, u32)>> = BinaryHeap::new();
+        let mut results: BinaryHeap<(Dist, u32)> = BinaryHeap::new();
+        for &(d, s) in eps {
+            if visited.insert(s) {
+                candidates.push(Reverse((Dist(d), s)));
+                if !live_only || store.live[s as usize] { results.push((Dist(d), s)); }
+            }
+        }
+        while let Some(Reverse((Dist(d), s))) = candidates.pop() {
+            if results.len() >= ef && d > results.peek().map(|r| r.0 .0).unwrap_or(f32::INFINITY) { break; }
+            let Some(links) = self.links[s as usize].get(layer) else { continue };
+            for &nb in links {
+                if !visited.insert(nb) { continue; }
+                let dn = store.distance(q, nb);
+                let worst = results.peek().map(|r| r.0 .0).unwrap_or(f32::INFINITY);
+                if results.len() < ef || dn < worst {
+                    candidates.push(Reverse((Dist(dn), nb)));
+                    if !live_only || store.live[nb as usize] {
+                        results.push((Dist(dn), nb));
+                        if results.len() > ef { results.pop(); }
+                    }
+                }
+            }
+        }
+        into_sorted(results)
+    }
+
+    pub fn search(&self, store: &VectorStore, q: &[f32], k: usize, ef: usize) -> Vec<(f32, u32)> {
+        let Some(mut ep) = self.entry else { return Vec::new() };
+        for layer in (1..=self.max_level).rev() {
+            ep = self.greedy(store, q, ep, layer);
+        }
+        let mut found = self.search_layer(store, q, &[(store.distance(q, ep), ep)], ef.max(k), 0, true);
+        found.truncate(k);
+        found
+    }
+}
+
+/// Neighbour-selection heuristic (Malkov & Yashunin, alg. 4): keep a
+/// candidate only if it is closer to the base than to any kept neighbour.
+fn select_neighbours(store: &VectorStore, sorted: &[(f32, u32)], m: usize) -> Vec<u32> {
+    let mut kept: Vec<u32> = Vec::with_capacity(m);
+    for &(d, c) in sorted {
+        if kept.len() >= m { break; }
+        if kept.iter().all(|&r| store.slot_distance(c, r) > d) { kept.push(c); }
+    }
+    kept
+}
+
+// ── IVF-PQ ──────────────────────────────────────────────────
+#[derive(Serialize, Deserialize)]
+pub struct IvfPq {
+    pub nlist: usize,
+    pub m: usize,
+    pub nbits: u32,
+    pub ksub: usize,
+    dsub: usize,
+    /// nlist × dim coarse centroids.
+    centroids: Vec<f32>,
+    /// m × ksub × dsub residual codebooks.
+    codebooks: Vec<f32>,
+    lists: Vec<Vec<u32>>,
+    codes: Vec<Vec<u8>>,
+    /// Owning list per slot, `u32::MAX` when absent.
+    assignment: Vec<u32>,
+}
+
+impl IvfPq {
+    pub fn build(store: &VectorStore, nlist: usize, m: usize, nbits: u32, iterations: usize, seed: u64) -> Result<Self, String> {
+        let dim = store.dim;
+        if m == 0 || !dim.is_multiple_of(m) {
+            return Err(format!("m={m} must divide dim={dim}"));
+        }
+        if !(1..=8).contains(&nbits) {
+            return Err("nbits must be in 1..=8".into());
+        }
+        let live: Vec<u32> = (0..store.len() as u32).filter(|s| store.live[*s as usize]).collect();
+        if live.is_empty() {
+            return Err("collection has no vectors to train on".into());
+        }
+        let mut rng = Rng::new(seed);
+        let nlist = nlist.clamp(1, live.len());
+        let ksub = (1usize << nbits).min(live.len());
+        let dsub = dim / m;
+
+        // Coarse quantizer over (a sample of) the live vectors.
+        let sample: Vec<u32> = sample_slots(&live, 256 * nlist.max(ksub), &mut rng);
+        let train: Vec<f32> = sample.iter().flat_map(|s| store.vector(*s).iter().copied()).collect();
+        let centroids = kmeans(&train, dim, nlist, iterations, &mut rng);
+
+        // Residual codebooks, one k-means per subspace.
+        let mut residuals = vec![0.0f32; train.len()];
+        for (i, v) in train.chunks_exact(dim).enumerate() {
+            let c = nearest(&centroids, dim, v);
+            for d in 0..dim { residuals[i * dim + d] = v[d] - centroids[c * dim + d]; }
+        }
+        let mut codebooks = Vec::with_capacity(m * ksub * dsub);
+        for j in 0..m {
+            let sub: Vec<f32> = residuals.chunks_exact(dim)
+                .flat_map(|r| r[j * dsub..(j + 1) * dsub].iter().copied()).collect();
+            let mut cb = kmeans(&sub, dsub, ksub, iterations, &mut rng);
+            cb.resize(ksub * dsub, 0.0);
+            codebooks.extend(cb);
+        }
+
+        let mut idx = Self {
+            nlist, m, nbits, ksub, dsub, centroids, codebooks,
+            lists: vec![Vec::new(); nlist], codes: vec![Vec::new(); nlist], assignment: Vec::new(),
+        };
+        for s in live { idx.insert(store, s); }
+        Ok(idx)
+    }
+
+    pub fn insert(&mut self, store: &VectorStore, slot: u32) {
+        let dim = store.dim;
+        let v = store.vector(slot);
+        let list = nearest(&self.centroids, dim, v);
+        let c = &self.centroids[list * dim..(list + 1) * dim];
+        let residual: Vec<f32> = v.iter().zip(c).map(|(x, y)| x - y).collect();
+        for j in 0..self.m {
+            let book = &self.codebooks[j * self.ksub * self.dsub..(j + 1) * self.ksub * self.dsub];
+            let code = nearest(book, self.dsub, &residual[j * self.dsub..(j + 1) * self.dsub]);
+            self.codes[list].push(code as u8);
+        }
+        self.lists[list].push(slot);
+        if self.assignment.len() <= slot as usize { self.assignment.resize(slot as usize + 1, u32::MAX); }
+        self.assignment[slot as usize] = list as u32;
+    }
+
+    pub fn remove(&mut self, slot: u32) {
+        let Some(&list) = self.assignment.get(slot as usize) else { return };
+        if list == u32::MAX { return; }
+        let list = list as usize;
+        if let Some(pos) = self.lists[list].iter().position(|s| *s == slot) {
+            let last = self.lists[list].len() - 1;
+            self.lists[list].swap_remove(pos);
+            let codes = &mut self.codes[list];
+            for j in 0..self.m { codes[pos * self.m + j] = codes[last * self.m + j]; }
+            codes.truncate(last * self.m);
+        }
+        self.assignment[slot as usize] = u32::MAX;
+    }
+
+    /// Asymmetric distance computation: per probed list, a `m × ksub`
+    /// lookup table turns each code into `m` table reads.
+    pub fn search(&self, store: &VectorStore, q: &[f32], k: usize, nprobe: usize) -> Vec<(f32, u32)> {
+        let dim = store.dim;
+        let dot = store.metric == Metric::Dot;
+        let mut coarse: Vec<(f32, usize)> = (0..self.nlist).map(|l| {
+            let c = &self.centroids[l * dim..(l + 1) * dim];
+            (if dot { -simd::dot_f32(q, c) } else { simd::l2_sq_f32(q, c) }, l)
+        }).collect();
+        coarse.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut table = vec![0.0f32; self.m * self.ksub];
+        let k = k.min(store.len());
+        let mut heap: BinaryHeap<(Dist, u32)> = BinaryHeap::with_capacity(k + 1);
+        for &(cd, list) in coarse.iter().take(nprobe.max(1)) {
+            let c = &self.centroids[list * dim..(list + 1) * dim];
+            for j in 0..self.m {
+                let qs = &q[j * self.dsub..(j + 1) * self.dsub];
+                let cs = &c[j * self.dsub..(j + 1) * self.dsub];
+                let r: Vec<f32> = qs.iter().zip(cs).map(|(a, b)| a - b).collect();
+                for code in 0..self.ksub {
+                    let w = &self.codebooks[(j * self.ksub + code) * self.dsub..(j * self.ksub + code + 1) * self.dsub];
+                    table[j * self.ksub + code] = if dot { -simd::dot_f32(qs, w) } else { simd::l2_sq_f32(&r, w) };
+                }
+            }
+            let base = if dot { cd } else { 0.0 };
+            for (i, &slot) in self.lists[list].iter().enumerate() {
+                if !store.live[slot as usize] { continue; }
+                let code = &self.codes[list][i * self.m..(i + 1) * self.m];
+                let d = base + code.iter().enumerate().map(|(j, &c)| table[j * self.ksub + c as usize]).sum::<f32>();
+                push_bounded(&mut heap, k, d, slot);
+            }
+        }
+        let mut out = into_sorted(heap);
+        // Unit vectors: ||a-b||² = 2 - 2cos, report cosine distance.
+        if store.metric == Metric::Cosine { out.iter_mut().for_each(|r| r.0 *= 0.5); }
+        out
+    }
+}
+
+fn sample_slots(slots: &[u32], max: usize, rng: &mut Rng) -> Vec<u32> {
+    if slots.len() <= max { return slots.to_vec(); }
+    let mut v = slots.to_vec();
+    for i in 0..max {
+        let j = i + rng.below(v.len() - i);
+        v.swap(i, j);
+    }
+    v.truncate(max);
+    v
+}
+
+fn nearest(centroids: &[f32], dim: usize, v: &[f32]) -> usize {
+    centroids.chunks_exact(dim).enumerate()
+        .map(|(i, c)| (simd::l2_sq_f32(v, c), i))
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, i)| i).unwrap_or(0)
+}
+
+/// Lloyd's k-means with random-sample seeding; empty clusters are reseeded.
+pub fn kmeans(data: &[f32], dim: usize, k: usize, iterations: usize, rng: &mut Rng) -> Vec<f32> {
+    let n = data.len() / dim.max(1);
+    let k = k.min(n).max(1);
+    let seeds = sample_slots(&(0..n as u32).collect::<Vec<_>>(), k, rng);
+    let mut centroids: Vec<f32> = seeds.iter().flat_map(|s| data[*s as usize * dim..(*s as usize + 1) * dim].iter().copied()).collect();
+    let mut assign = vec![0usize; n];
+    for _ in 0..iterations.max(1) {
+        if crate::jobs::cancelled() { break; }
+        let mut changed = false;
+        for (i, v) in data.chunks_exact(dim).enumerate() {
+            let c = nearest(&centroids, dim, v);
+            if c != assign[i] { assign[i] = c; changed = true; }
+        }
+        let mut sums = vec![0.0f64; k * dim];
+        let mut counts = vec![0usize; k];
+        for (i, v) in data.chunks_exact(dim).enumerate() {
+            counts[assign[i]] += 1;
+            for d in 0..dim { sums[assign[i] * dim + d] += v[d] as f64; }
+        }
+        for c in 0..k {
+            if counts[c] == 0 {
+                let s = rng.below(n);
+                centroids[c * dim..(c + 1) * dim].copy_from_slice(&data[s * dim..(s + 1) * dim]);
+                continue;
+            }
+            for d in 0..dim { centroids[c * dim + d] = (sums[c * dim + d] / counts[c] as f64) as f32; }
+        }
+        if !changed { break; }
+    }
+    centroids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIM: usize = 16;
+
+    fn store(metric: Metric, n: usize, rng: &mut Rng) -> VectorStore {
+        let mut s = VectorStore::new(DIM, metric);
+        for id in 0..n as u64 {
+            let v: Vec<f64> = (0..DIM).map(|_| rng.next_f64() * 2.0 - 1.0).collect();
+            let v = s.prepare(&v);
+            s.push(id, &v);
+        }
+        s
+    }
+
+    /// Mean recall@k of `index` against brute force over random queries.
+    fn recall(s: &VectorStore, index: &AnnIndex, k: usize, ef: usize, nprobe: usize, rerank: usize, rng: &mut Rng) -> f64 {
+        let queries = 50;
+        let mut hits = 0;
+        for _ in 0..queries {
+            let q: Vec<f64> = (0..DIM).map(|_| rng.next_f64() * 2.0 - 1.0).collect();
+            let q = s.prepare(&q);
+            let truth: Vec<u32> = s.brute_force(&q, k).into_iter().map(|r| r.1).collect();
+            let got = index.search(s, &q, k, ef, nprobe, rerank);
+            assert_eq!(got.len(), k);
+            hits += got.iter().filter(|r| truth.contains(&r.1)).count();
+        }
+        hits as f64 / (queries * k) as f64
+    }
+
+    #[test]
+    fn hnsw_recall_matches_brute_force() {
+        let mut rng = Rng::new(7);
+        for metric in [Metric::L2, Metric::Cosine] {
+            let s = store(metric, 1000, &mut rng);
+            let index = AnnIndex::Hnsw(Hnsw::build(&s, 16, 100, 1));
+            let r = recall(&s, &index, 10, 100, 0, 0, &mut rng);
+            assert!(r >= 0.95, "hnsw recall@10 {r}");
+        }
+    }
+
+    #[test]
+    fn ivf_pq_recall_with_rerank() {
+        let mut rng = Rng::new(11);
+        let s = store(Metric::L2, 1000, &mut rng);
+        let Ok(pq) = IvfPq::build(&s, 16, 8, 8, 10, 3) else { panic!("ivf_pq build failed") };
+        let index = AnnIndex::IvfPq(pq);
+        // Probing every list isolates the PQ approximation from the coarse step.
+        let coarse = recall(&s, &index, 10, 0, 16, 0, &mut rng);
+        let reranked = recall(&s, &index, 10, 0, 16, 10, &mut rng);
+        assert!(coarse >= 0.8, "ivf_pq recall@10 {coarse}");
+        assert!(reranked >= 0.98, "ivf_pq recall@10 with rerank {reranked}");
+    }
+
+    #[test]
+    fn brute_force_is_sorted_and_capped() {
+        let mut rng = Rng::new(3);
+        let s = store(Metric::L2, 20, &mut rng);
+        let q = s.vector(4).to_vec();
+        let r = s.brute_force(&q, 100);
+        assert_eq!(r.len(), 20);
+        assert_eq!(r[0].1, 4);
+        assert!(r.windows(2).all(|w| w[0].0 <= w[1].0));
+    }
+}
@@ -0,0 +1,317 @@
+// ── Baselines ───────────────────────────────────────────────
+// Named benchmark runs kept to catch regressions between engine versions.
+// A baseline holds every timed sample of every variant plus the suite that
+// produced it, so it can be rerun unchanged after an upgrade. Baselines
+// belong to the caller (`X-User-Id`; anonymous callers share one namespace)
+// and are written to SIMD_BASELINE_DIR (default ./baselines) as
+// `<user>/<name>.json`, or `<name>.json` when anonymous, and cached in
+// memory. A comparison pairs cells by operation, size, dtype, threads
+// and variant and runs a two-sided Mann-Whitney U test on their
+// samples: a cell regressed when the difference is significant at `alpha`
+// and its median slowed by more than `threshold`.
+use crate::bench::{Axes, BenchmarkResult, Suite};
+use crate::history::User;
+use crate::{api_err, ApiError, AppState, BenchmarkRequest, BenchmarkResponse};
+use crate::telemetry::Json;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// Cached baselines keyed by owner and name.
+pub type Baselines = RwLock<HashMap<(Option<Uuid>, String), Arc<Baseline>>>;
+
+pub const DEFAULT_THRESHOLD: f64 = 0.05;
+pub const DEFAULT_ALPHA: f64 = 0.05;
+
+#[derive(Serialize, Deserialize)]
+pub struct Cell {
+    pub operation: String, pub size: usize, pub dtype: String, pub threads: usize, pub variant: String,
+    pub median_ns: f64,
+    pub samples: Vec<f64>,
+}
+
+/// Enough of the original request to rerun the same suite.
+#[derive(Serialize, Deserialize)]
+pub struct Spec {
+    #[serde(flatten)]
+    pub grid: Axes,
+    pub variants: Vec<String>,
+    pub iterations: usize,
+    pub warmup: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Baseline {
+    pub name: String,
+    pub engine_version: String,
+    pub backend: String,
+    pub created_at_ms: u64,
+    pub suite: Spec,
+    pub cells: Vec<Cell>,
+}
+
+impl Baseline {
+    pub fn new(name: &str, suite: &Suite, results: &[BenchmarkResult]) -> Baseline {
+        let cells = results.iter().flat_map(|r| r.variants.iter().map(move |v| Cell {
+            operation: r.operation.clone(), size: r.size, dtype: r.dtype.clone(), threads: r.threads,
+            variant: v.variant.clone(), median_ns: v.stats.median_ns, samples: v.samples.clone(),
+        })).collect();
+        Baseline {
+            name: name.into(),
+            engine_version: env!("CARGO_PKG_VERSION").into(),
+            backend: crate::simd::backend().into(),
+            created_at_ms: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0),
+            suite: Spec {
+                grid: suite.axes(),
+                variants: suite.variants.iter().map(|v| v.name.to_string()).collect(),
+                iterations: suite.iterations, warmup: suite.warmup,
+            },
+            cells,
+        }
+    }
+
+    /// The benchmark request that reruns this baseline's suite and compares
+    /// the result against it.
+    fn rerun(&self, threshold: Option<f64>, alpha: Option<f64>) -> BenchmarkRequest {
+        let grid = &self.suite.grid;
+        BenchmarkRequest {
+            size: None, iterations: Some(self.suite.iterations), warmup: Some(self.suite.warmup),
+            operations: Some(grid.operations.clone()), sizes: Some(grid.sizes.clone()), sweep: None,
+            dtypes: Some(grid.dtypes.clone()), threads: Some(grid.threads.clone()),
+            variants: Some(self.suite.variants.clone()), counters: None,
+            save_baseline: None, compare_to: Some(self.name.clone()), threshold, alpha,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BaselineInfo { name: String, engine_version: String, backend: String, created_at_ms: u64, cells: usize }
+
+#[derive(Serialize)]
+pub struct CellComparison {
+    pub operation: String, pub size: usize, pub dtype: String, pub threads: usize, pub variant: String,
+    pub baseline_median_ns: f64, pub current_median_ns: f64,
+    /// Relative change of the median; positive is slower.
+    pub change: f64,
+    /// Two-sided Mann-Whitney U p-value.
+    pub p_value: f64,
+    /// "regression", "improvement" or "unchanged".
+    pub verdict: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct Comparison {
+    pub baseline: String,
+    pub baseline_engine_version: String,
+    pub threshold: f64,
+    pub alpha: f64,
+    pub regressions: usize,
+    pub improvements: usize,
+    /// Cells of this run with no counterpart in the baseline.
+    pub unmatched: usize,
+    pub cells: Vec<CellComparison>,
+}
+
+#[derive(Deserialize, Default)]
+struct CompareRequest { threshold: Option<f64>, alpha: Option<f64> }
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/v1/simd/baselines", get(list_baselines))
+        .route("/api/v1/simd/baselines/:name", get(get_baseline).delete(drop_baseline))
+        .route("/api/v1/simd/baselines/:name/compare", post(compare_baseline))
+}
+
+// ── Statistics ──────────────────────────────────────────────
+/// Complementary error function (Numerical Recipes' erfcc, |error| < 1.2e-7).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23 + t * (1.000_023_68 + t * (0.374_091_96 + t * (0.096_784_18 + t * (-0.186_288_06
+        + t * (0.278_868_07 + t * (-1.135_203_98 + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let r = t * poly.exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+/// Two-sided Mann-Whitney U test of `a` against `b`, via the normal
+/// approximation with tie and continuity corrections (good from roughly
+/// eight samples a side).
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() || b.is_empty() { return 1.0; }
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+    let mut all: Vec<(f64, bool)> = a.iter().map(|&x| (x, true)).chain(b.iter().map(|&x| (x, false))).collect();
+    all.sort_by(|x, y| x.0.total_cmp(&y.0));
+    let (mut rank_sum, mut ties, mut i) = (0.0, 0.0, 0);
+    while i < all.len() {
+        let j = i + all[i..].iter().take_while(|x| x.0 == all[i].0).count();
+        let (rank, t) = ((i + j + 1) as f64 / 2.0, (j - i) as f64);
+        ties += t * t * t - t;
+        rank_sum += rank * all[i..j].iter().filter(|x| x.1).count() as f64;
+        i = j;
+    }
+    let u = rank_sum - n1 * (n1 + 1.0) / 2.0;
+    let n = n1 + n2;
+    let var = n1 * n2 / 12.0 * (n + 1.0 - ties / (n * (n - 1.0)));
+    if var <= 0.0 { return 1.0; }
+    let z = ((u - n1 * n2 / 2.0).abs() - 0.5).max(0.0) / var.sqrt();
+    erfc(z / std::f64::consts::SQRT_2).min(1.0)
+}
+
+pub fn compare(baseline: &Baseline, results: &[BenchmarkResult], threshold: f64, alpha: f64) -> Comparison {
+    let mut cells = Vec::new();
+    let mut unmatched = 0;
+    for r in results {
+        for v in &r.variants {
+            let Some(base) = baseline.cells.iter().find(|c| {
+                c.operation == r.operation && c.size == r.size && c.dtype == r.dtype && c.threads == r.threads && c.variant == v.variant
+            }) else { unmatched += 1; continue };
+            let current = v.stats.median_ns;
+            let change = if base.median_ns > 0.0 { current / base.median_ns - 1.0 } else { 0.0 };
+            let p_value = mann_whitney(&base.samples, &v.samples);
+            let verdict = match () {
+                _ if p_value >= alpha => "unchanged",
+                _ if change > threshold => "regression",
+                _ if change < -threshold => "improvement",
+                _ => "unchanged",
+            };
+            cells.push(CellComparison {
+                operation: r.operation.clone(), size: r.size, dtype: r.dtype.clone(), threads: r.threads,
+                variant: v.variant.clone(), baseline_median_ns: base.median_ns, current_median_ns: current,
+                change, p_value, verdict,
+            });
+        }
+    }
+    Comparison {
+        baseline: baseline.name.clone(), baseline_engine_version: baseline.engine_version.clone(), threshold, alpha,
+        regressions: cells.iter().filter(|c| c.verdict == "regression").count(),
+        improvements: cells.iter().filter(|c| c.verdict == "improvement").count(),
+        unmatched, cells,
+    }
+}
+
+// ── Store ───────────────────────────────────────────────────
+/// The directory holding `user`'s baselines.
+fn baseline_dir(user: User) -> PathBuf {
+    let root = PathBuf::from(std::env::var("SIMD_BASELINE_DIR").unwrap_or_else(|_| "./baselines".into()));
+    match user.0 {
+        Some(id) => root.join(id.to_string()),
+        None => root,
+    }
+}
+
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= 128
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && !name.starts_with('.')
+}
+
+pub fn lookup(s: &AppState, user: User, name: &str) -> Result<Arc<Baseline>, ApiError> {
+    let key = (user.0, name.to_string());
+    if let Some(b) = s.baselines.read().unwrap().get(&key) { return Ok(b.clone()); }
+    let not_found = || api_err(StatusCode::NOT_FOUND, "Baseline not found", Some(name.into()));
+    if !valid_name(name) { return Err(not_found()); }
+    let raw = std::fs::read(baseline_dir(user).join(format!("{name}.json"))).map_err(|_| not_found())?;
+    let b: Baseline = serde_json::from_slice(&raw)
+        .map_err(|e| api_err(StatusCode::UNPROCESSABLE_ENTITY, "Corrupt baseline", Some(e.to_string())))?;
+    let b = Arc::new(b);
+    s.baselines.write().unwrap().insert(key, b.clone());
+    Ok(b)
+}
+
+/// Writes (or replaces) one of `user`'s baselines on disk, then caches it.
+pub fn save(s: &AppState, user: User, b: Baseline) -> Result<(), ApiError> {
+    let io = |e: std::io::Error| api_err(StatusCode::INTERNAL_SERVER_ERROR, "Saving baseline failed", Some(e.to_string()));
+    let dir = baseline_dir(user);
+    std::fs::create_dir_all(&dir).map_err(io)?;
+    let written = serde_json::to_vec(&b)
+        .map_err(|e| api_err(StatusCode::INTERNAL_SERVER_ERROR, "Saving baseline failed", Some(e.to_string())))?;
+    let path = dir.join(format!("{}.json", b.name));
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, &written).map_err(io)?;
+    std::fs::rename(&tmp, &path).map_err(io)?;
+    s.baselines.write().unwrap().insert((user.0, b.name.clone()), Arc::new(b));
+    Ok(())
+}
+
+// ── Handlers ────────────────────────────────────────────────
+async fn list_baselines(State(s): State<Arc<AppState>>, user: User) -> Json<Vec<BaselineInfo>> {
+    let mut names: Vec<String> = s.baselines.read().unwrap().keys()
+        .filter(|(owner, _)| *owner == user.0).map(|(_, name)| name.clone()).collect();
+    if let Ok(entries) = std::fs::read_dir(baseline_dir(user)) {
+        names.extend(entries.flatten().filter_map(|e| e.file_name().to_str()?.strip_suffix(".json").map(String::from)));
+    }
+    names.sort();
+    names.dedup();
+    Json(names.iter().filter_map(|n| lookup(&s, user, n).ok()).map(|b| BaselineInfo {
+        name: b.name.clone(), engine_version: b.engine_version.clone(), backend: b.backend.clone(),
+        created_at_ms: b.created_at_ms, cells: b.cells.len(),
+    }).collect())
+}
+
+async fn get_baseline(State(s): State<Arc<AppState>>, user: User, Path(name): Path<String>) -> Result<Json<serde_json::Value>, ApiError> {
+    let b = lookup(&s, user, &name)?;
+    Ok(Json(serde_json::to_value(&*b).unwrap_or_default()))
+}
+
+async fn drop_baseline(State(s): State<Arc<AppState>>, user: User, Path(name): Path<String>) -> Result<StatusCode, ApiError> {
+    let cached = s.baselines.write().unwrap().remove(&(user.0, name.clone())).is_some();
+    let on_disk = valid_name(&name) && std::fs::remove_file(baseline_dir(user).join(format!("{name}.json"))).is_ok();
+    if cached || on_disk { Ok(StatusCode::NO_CONTENT) } else { Err(api_err(StatusCode::NOT_FOUND, "Baseline not found", Some(name))) }
+}
+
+/// Reruns the baseline's suite on this engine and compares against it.
+async fn compare_baseline(
+    State(s): State<Arc<AppState>>, user: User, limits: crate::limits::Limits, Path(name): Path<String>, req: Option<Json<CompareRequest>>,
+) -> Result<Json<BenchmarkResponse>, ApiError> {
+    let req = req.map(|Json(r)| r).unwrap_or_default();
+    let rerun = lookup(&s, user, &name)?.rerun(req.threshold, req.alpha);
+    crate::benchmark(State(s), user, limits, Json(rerun)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference values from the normal approximation with tie and
+    /// continuity corrections (as SciPy's `mannwhitneyu(method="asymptotic")`).
+    #[test]
+    fn mann_whitney_matches_reference_p_values() {
+        let cases: [(&[f64], &[f64], f64); 3] = [
+            (&[1.1, 2.3, 3.0, 4.8, 5.2, 6.7, 7.1, 8.4], &[3.5, 5.9, 7.7, 8.8, 9.3, 10.6, 11.2, 12.9], 0.018_129_008),
+            // Ties within and across the samples.
+            (&[1.0, 2.0, 2.0, 3.0, 3.0, 3.0, 4.0, 5.0, 6.0, 7.0], &[3.0, 4.0, 4.0, 5.0, 5.0, 6.0, 7.0, 8.0, 8.0, 9.0], 0.022_208_731),
+            // Completely separated.
+            (&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0], &[100.0, 101.0, 102.0, 103.0, 104.0, 105.0, 106.0, 107.0, 108.0, 109.0], 0.000_182_672),
+        ];
+        for (a, b, want) in cases {
+            let p = mann_whitney(a, b);
+            assert!((p - want).abs() < 1e-6, "p = {p}, expected {want}");
+            assert!((mann_whitney(b, a) - p).abs() < 1e-12, "not symmetric");
+        }
+    }
+
+    #[test]
+    fn mann_whitney_degenerate_inputs() {
+        let a = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        assert_eq!(mann_whitney(&a, &a), 1.0);
+        assert_eq!(mann_whitney(&[], &a), 1.0);
+        assert_eq!(mann_whitney(&[5.0; 8], &[5.0; 8]), 1.0);
+    }
+
+    #[test]
+    fn erfc_accuracy() {
+        // erfc(0) = 1, erfc(1) = 0.157299207..., erfc(-x) = 2 - erfc(x).
+        assert!((erfc(0.0) - 1.0).abs() < 1.2e-7);
+        assert!((erfc(1.0) - 0.157_299_207_050_285).abs() < 1.2e-7);
+        assert!((erfc(-1.0) - 1.842_700_792_949_715).abs() < 1.2e-7);
+        assert!(erfc(6.0) < 1e-15);
+    }
+}
@@ -0,0 +1,638 @@
+// ── Batched small matrices, vectors and quaternions ─────────
+// Arrays of 3x3/4x4 matrices, 3/4-vectors and quaternions stored as
+// structure-of-arrays: component `k` of item `i` lives at `data[k * n + i]`.
+// Kernels are written once over the `Lane` trait and instantiated for
+// `Wide` (W items per step, one SIMD lane per item) and plain `f64` for the
+// tail, so the SIMD runs across the batch rather than inside one matrix.
+use crate::cost::Cost;
+use crate::limits::Limits;
+use crate::{api_err, geometry, simd, ApiError, AppState};
+use crate::telemetry::Json;
+use axum::{extract::State, http::StatusCode, routing::post, Router};
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
+
+pub const W: usize = 8;
+
+pub trait Lane:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> + Neg<Output = Self>
+{
+    fn splat(v: f64) -> Self;
+    fn map(self, f: impl Fn(f64) -> f64) -> Self;
+    fn zip(self, o: Self, f: impl Fn(f64, f64) -> f64) -> Self;
+    fn sqrt(self) -> Self { self.map(f64::sqrt) }
+    fn min(self, o: Self) -> Self { self.zip(o, f64::min) }
+    fn max(self, o: Self) -> Self { self.zip(o, f64::max) }
+}
+
+impl Lane for f64 {
+    #[inline(always)] fn splat(v: f64) -> Self { v }
+    #[inline(always)] fn map(self, f: impl Fn(f64) -> f64) -> Self { f(self) }
+    #[inline(always)] fn zip(self, o: Self, f: impl Fn(f64, f64) -> f64) -> Self { f(self, o) }
+}
+
+/// `W` batch items processed in lockstep.
+#[derive(Clone, Copy)]
+pub struct Wide(pub [f64; W]);
+
+macro_rules! wide_op {
+    ($tr:ident, $f:ident, $op:tt) => {
+        impl $tr for Wide {
+            type Output = Wide;
+            #[inline(always)]
+            fn $f(self, o: Wide) -> Wide { Wide(std::array::from_fn(|l| self.0[l] $op o.0[l])) }
+        }
+    };
+}
+wide_op!(Add, add, +);
+wide_op!(Sub, sub, -);
+wide_op!(Mul, mul, *);
+wide_op!(Div, div, /);
+
+impl Neg for Wide {
+    type Output = Wide;
+    #[inline(always)]
+    fn neg(self) -> Wide { Wide(self.0.map(|v| -v)) }
+}
+
+impl Lane for Wide {
+    #[inline(always)] fn splat(v: f64) -> Self { Wide([v; W]) }
+    #[inline(always)] fn map(self, f: impl Fn(f64) -> f64) -> Self { Wide(self.0.map(f)) }
+    #[inline(always)] fn zip(self, o: Self, f: impl Fn(f64, f64) -> f64) -> Self { Wide(std::array::from_fn(|l| f(self.0[l], o.0[l]))) }
+}
+
+// ── SoA storage ─────────────────────────────────────────────
+#[derive(Clone, Copy, PartialEq)]
+pub enum Layout { Soa, Aos }
+
+impl Layout {
+    pub fn parse(s: Option<&str>) -> Result<Self, String> {
+        match s.unwrap_or("soa") {
+            "soa" => Ok(Layout::Soa),
+            "aos" => Ok(Layout::Aos),
+            other => Err(format!("unknown layout '{other}' (expected soa or aos)")),
+        }
+    }
+}
+
+pub struct Soa { pub comps: usize, pub n: usize, pub data: Vec<f64> }
+
+impl Soa {
+    /// `Soa` input is one array per component; `Aos` is one array per item.
+    pub fn parse(raw: Vec<Vec<f64>>, layout: Layout, what: &str) -> Result<Soa, String> {
+        let outer = raw.len();
+        let inner = raw.first().map(|r| r.len()).unwrap_or(0);
+        if raw.iter().any(|r| r.len() != inner) { return Err(format!("`{what}` has ragged rows")); }
+        let (comps, n) = if layout == Layout::Soa { (outer, inner) } else { (inner, outer) };
+        if comps == 0 || n == 0 { return Err(format!("`{what}` is empty")); }
+        let data = match layout {
+            Layout::Soa => raw.concat(),
+            Layout::Aos => (0..comps).flat_map(|k| raw.iter().map(move |item| item[k])).collect(),
+        };
+        Ok(Soa { comps, n, data })
+    }
+
+    /// A single-component batch (e.g. per-item interpolation factors).
+    pub fn column(v: Vec<f64>) -> Soa { Soa { comps: 1, n: v.len(), data: v } }
+
+    pub fn comp(&self, k: usize) -> &[f64] { &self.data[k * self.n..(k + 1) * self.n] }
+
+    /// Drops the trailing components (kernels may return side outputs).
+    pub fn truncate(mut self, comps: usize) -> Soa {
+        self.data.truncate(comps * self.n);
+        self.comps = comps;
+        self
+    }
+
+    /// Single-component results serialise flat; others follow `layout`.
+    pub fn to_json(&self, layout: Layout) -> serde_json::Value {
+        let arr = |it: &mut dyn Iterator<Item = f64>| serde_json::Value::Array(it.map(serde_json::Value::from).collect());
+        if self.comps == 1 { return arr(&mut self.data.iter().copied()); }
+        match layout {
+            Layout::Soa => serde_json::Value::Array((0..self.comps).map(|k| arr(&mut self.comp(k).iter().copied())).collect()),
+            Layout::Aos => serde_json::Value::Array((0..self.n).map(|i| arr(&mut (0..self.comps).map(|k| self.data[k * self.n + i]))).collect()),
+        }
+    }
+}
+
+/// Common batch length; size-1 inputs broadcast.
+pub fn batch_len(ins: &[&Soa]) -> Result<usize, String> {
+    let n = ins.iter().map(|s| s.n).max().unwrap_or(0);
+    if let Some(s) = ins.iter().find(|s| s.n != n && s.n != 1) {
+        return Err(format!("batch sizes {} and {n} do not match (only size 1 broadcasts)", s.n));
+    }
+    Ok(n)
+}
+
+/// Runs a kernel over the batch. The inputs' components are concatenated
+/// into the kernel's argument slice in order; the kernel returns `O`
+/// output components.
+pub fn run<const O: usize, FW, FS>(ins: &[&Soa], n: usize, (wide, narrow): (FW, FS)) -> Soa
+where
+    FW: Fn(&[Wide]) -> [Wide; O],
+    FS: Fn(&[f64]) -> [f64; O],
+{
+    let cols: Vec<&[f64]> = ins.iter().flat_map(|s| (0..s.comps).map(move |k| s.comp(k))).collect();
+    let mut data = vec![0.0; O * n];
+    simd::with_wide_lanes(|| {
+        let full = n - n % W;
+        let mut x = vec![Wide::splat(0.0); cols.len()];
+        for i0 in (0..full).step_by(W) {
+            for (xk, c) in x.iter_mut().zip(&cols) {
+                *xk = if c.len() == 1 { Wide::splat(c[0]) } else { Wide(c[i0..i0 + W].try_into().unwrap()) };
+            }
+            for (k, r) in wide(&x).iter().enumerate() { data[k * n + i0..k * n + i0 + W].copy_from_slice(&r.0); }
+        }
+        let mut xs = vec![0.0; cols.len()];
+        for i in full..n {
+            for (xk, c) in xs.iter_mut().zip(&cols) { *xk = if c.len() == 1 { c[0] } else { c[i] }; }
+            for (k, r) in narrow(&xs).iter().enumerate() { data[k * n + i] = *r; }
+        }
+    });
+    Soa { comps: O, n, data }
+}
+
+/// Instantiates a generic kernel for both lane types.
+macro_rules! kernel {
+    ($f:ident $(, $c:expr)*) => { ($f::<$crate::batch::Wide $(, $c)*>, $f::<f64 $(, $c)*>) };
+}
+pub(crate) use kernel;
+
+// ── Matrix kernels (row-major, S×S) ─────────────────────────
+#[inline(always)]
+fn mat_mul<T: Lane, const S: usize, const O: usize>(x: &[T]) -> [T; O] {
+    let (a, b) = x.split_at(O);
+    std::array::from_fn(|e| {
+        let (r, c) = (e / S, e % S);
+        (1..S).fold(a[r * S] * b[c], |acc, k| acc + a[r * S + k] * b[k * S + c])
+    })
+}
+
+#[inline(always)]
+fn transpose<T: Lane, const S: usize, const O: usize>(x: &[T]) -> [T; O] {
+    std::array::from_fn(|e| x[(e % S) * S + e / S])
+}
+
+/// Adjugate of the top-left 3x3 block of a row-major matrix with row stride `s`.
+#[inline(always)]
+fn adj3<T: Lane>(m: &[T], s: usize) -> [T; 9] {
+    let a = |r: usize, c: usize| m[r * s + c];
+    [
+        a(1, 1) * a(2, 2) - a(1, 2) * a(2, 1), a(0, 2) * a(2, 1) - a(0, 1) * a(2, 2), a(0, 1) * a(1, 2) - a(0, 2) * a(1, 1),
+        a(1, 2) * a(2, 0) - a(1, 0) * a(2, 2), a(0, 0) * a(2, 2) - a(0, 2) * a(2, 0), a(0, 2) * a(1, 0) - a(0, 0) * a(1, 2),
+        a(1, 0) * a(2, 1) - a(1, 1) * a(2, 0), a(0, 1) * a(2, 0) - a(0, 0) * a(2, 1), a(0, 0) * a(1, 1) - a(0, 1) * a(1, 0),
+    ]
+}
+
+#[inline(always)]
+fn det_from_adj3<T: Lane>(m: &[T], adj: &[T; 9]) -> T {
+    m[0] * adj[0] + m[1] * adj[3] + m[2] * adj[6]
+}
+
+/// Adjugate and determinant of a 4x4 via the 2x2 sub-determinants of the
+/// top and bottom row pairs.
+#[inline(always)]
+fn adj4<T: Lane>(m: &[T]) -> ([T; 16], T) {
+    let a = |r: usize, c: usize| m[r * 4 + c];
+    let s = [
+        a(0, 0) * a(1, 1) - a(1, 0) * a(0, 1), a(0, 0) * a(1, 2) - a(1, 0) * a(0, 2), a(0, 0) * a(1, 3) - a(1, 0) * a(0, 3),
+        a(0, 1) * a(1, 2) - a(1, 1) * a(0, 2), a(0, 1) * a(1, 3) - a(1, 1) * a(0, 3), a(0, 2) * a(1, 3) - a(1, 2) * a(0, 3),
+    ];
+    let c = [
+        a(2, 0) * a(3, 1) - a(3, 0) * a(2, 1), a(2, 0) * a(3, 2) - a(3, 0) * a(2, 2), a(2, 0) * a(3, 3) - a(3, 0) * a(2, 3),
+        a(2, 1) * a(3, 2) - a(3, 1) * a(2, 2), a(2, 1) * a(3, 3) - a(3, 1) * a(2, 3), a(2, 2) * a(3, 3) - a(3, 2) * a(2, 3),
+    ];
+    let det = s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+    let adj = [
+        a(1, 1) * c[5] - a(1, 2) * c[4] + a(1, 3) * c[3],
+        -a(0, 1) * c[5] + a(0, 2) * c[4] - a(0, 3) * c[3],
+        a(3, 1) * s[5] - a(3, 2) * s[4] + a(3, 3) * s[3],
+        -a(2, 1) * s[5] + a(2, 2) * s[4] - a(2, 3) * s[3],
+        -a(1, 0) * c[5] + a(1, 2) * c[2] - a(1, 3) * c[1],
+        a(0, 0) * c[5] - a(0, 2) * c[2] + a(0, 3) * c[1],
+        -a(3, 0) * s[5] + a(3, 2) * s[2] - a(3, 3) * s[1],
+        a(2, 0) * s[5] - a(2, 2) * s[2] + a(2, 3) * s[1],
+        a(1, 0) * c[4] - a(1, 1) * c[2] + a(1, 3) * c[0],
+        -a(0, 0) * c[4] + a(0, 1) * c[2] - a(0, 3) * c[0],
+        a(3, 0) * s[4] - a(3, 1) * s[2] + a(3, 3) * s[0],
+        -a(2, 0) * s[4] + a(2, 1) * s[2] - a(2, 3) * s[0],
+        -a(1, 0) * c[3] + a(1, 1) * c[1] - a(1, 2) * c[0],
+        a(0, 0) * c[3] - a(0, 1) * c[1] + a(0, 2) * c[0],
+        -a(3, 0) * s[3] + a(3, 1) * s[1] - a(3, 2) * s[0],
+        a(2, 0) * s[3] - a(2, 1) * s[1] + a(2, 2) * s[0],
+    ];
+    (adj, det)
+}
+
+#[inline(always)]
+fn det3<T: Lane>(x: &[T]) -> [T; 1] { [det_from_adj3(x, &adj3(x, 3))] }
+
+#[inline(always)]
+fn det4<T: Lane>(x: &[T]) -> [T; 1] { [adj4(x).1] }
+
+/// `1/det`, or 0 for singular matrices (which then invert to all zeros,
+/// like the single-matrix `inverse`).
+#[inline(always)]
+fn inv_det<T: Lane>(det: T) -> T {
+    det.map(|d| if d.abs() < SINGULAR_EPS { 0.0 } else { 1.0 / d })
+}
+
+pub const SINGULAR_EPS: f64 = 1e-15;
+
+/// Inverse followed by the determinant as a side output.
+#[inline(always)]
+fn inverse3<T: Lane>(x: &[T]) -> [T; 10] {
+    let adj = adj3(x, 3);
+    let det = det_from_adj3(x, &adj);
+    let inv = inv_det(det);
+    std::array::from_fn(|e| if e < 9 { adj[e] * inv } else { det })
+}
+
+#[inline(always)]
+fn inverse4<T: Lane>(x: &[T]) -> [T; 17] {
+    let (adj, det) = adj4(x);
+    let inv = inv_det(det);
+    std::array::from_fn(|e| if e < 16 { adj[e] * inv } else { det })
+}
+
+/// Top-left `V×V` block of an `S×S` matrix times a `V`-vector.
+#[inline(always)]
+fn mat_vec<T: Lane, const S: usize, const V: usize>(x: &[T]) -> [T; V] {
+    let (m, v) = x.split_at(S * S);
+    std::array::from_fn(|r| (1..V).fold(m[r * S] * v[0], |acc, c| acc + m[r * S + c] * v[c]))
+}
+
+/// 4x4 affine/projective transform of a 3-D point (w = 1), divided by the
+/// resulting w unless it is ~0.
+#[inline(always)]
+fn transform_point4<T: Lane>(x: &[T]) -> [T; 3] {
+    let (m, p) = x.split_at(16);
+    let row = |r: usize| m[r * 4] * p[0] + m[r * 4 + 1] * p[1] + m[r * 4 + 2] * p[2] + m[r * 4 + 3];
+    let w = row(3).map(|w| if w.abs() < SINGULAR_EPS { 1.0 } else { 1.0 / w });
+    [row(0) * w, row(1) * w, row(2) * w]
+}
+
+/// Normals transform by the inverse-transpose of the linear part; the
+/// cofactor matrix has the same direction (up to det's sign), so no division.
+#[inline(always)]
+fn transform_normal<T: Lane, const S: usize>(x: &[T]) -> [T; 3] {
+    let adj = adj3(x, S);
+    let det = det_from_adj3(x, &adj);
+    let nv = &x[S * S..];
+    let sign = det.map(|d| if d < 0.0 { -1.0 } else { 1.0 });
+    let r: [T; 3] = std::array::from_fn(|i| (adj[i] * nv[0] + adj[3 + i] * nv[1] + adj[6 + i] * nv[2]) * sign);
+    normalize(r)
+}
+
+#[inline(always)]
+pub fn normalize<T: Lane, const N: usize>(v: [T; N]) -> [T; N] {
+    let len = v.iter().skip(1).fold(v[0] * v[0], |acc, c| acc + *c * *c).sqrt();
+    let inv = len.map(|l| if l > SINGULAR_EPS { 1.0 / l } else { 0.0 });
+    v.map(|c| c * inv)
+}
+
+// ── Quaternion kernels ([x, y, z, w]) ───────────────────────
+#[inline(always)]
+fn quat_mul<T: Lane>(x: &[T]) -> [T; 4] {
+    let (ax, ay, az, aw) = (x[0], x[1], x[2], x[3]);
+    let (bx, by, bz, bw) = (x[4], x[5], x[6], x[7]);
+    [
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+        aw * bw - ax * bx - ay * by - az * bz,
+    ]
+}
+
+/// Shortest-path slerp; falls back to normalised lerp when the quaternions
+/// are nearly parallel.
+#[inline(always)]
+fn quat_slerp<T: Lane>(x: &[T]) -> [T; 4] {
+    let (a, b, t) = (&x[0..4], &x[4..8], x[8]);
+    let d = a[0] * b[0] + a[1] * b[1] + a[2] * b[2] + a[3] * b[3];
+    let sign = d.map(|d| if d < 0.0 { -1.0 } else { 1.0 });
+    let d = d * sign;
+    let wa = d.zip(t, |d, t| if d > 0.9995 { 1.0 - t } else { let th = d.acos(); ((1.0 - t) * th).sin() / th.sin() });
+    let wb = d.zip(t, |d, t| if d > 0.9995 { t } else { let th = d.acos(); (t * th).sin() / th.sin() }) * sign;
+    normalize(std::array::from_fn(|i| a[i] * wa + b[i] * wb))
+}
+
+/// Rotation matrix of a (not necessarily unit) quaternion, embedded in the
+/// top-left of an `S×S` matrix.
+#[inline(always)]
+fn quat_to_matrix<T: Lane, const S: usize, const O: usize>(x: &[T]) -> [T; O] {
+    let (qx, qy, qz, qw) = (x[0], x[1], x[2], x[3]);
+    let s = (qx * qx + qy * qy + qz * qz + qw * qw).map(|n| if n > SINGULAR_EPS { 2.0 / n } else { 0.0 });
+    let one = T::splat(1.0);
+    let r = [
+        one - s * (qy * qy + qz * qz), s * (qx * qy - qw * qz), s * (qx * qz + qw * qy),
+        s * (qx * qy + qw * qz), one - s * (qx * qx + qz * qz), s * (qy * qz - qw * qx),
+        s * (qx * qz - qw * qy), s * (qy * qz + qw * qx), one - s * (qx * qx + qy * qy),
+    ];
+    std::array::from_fn(|e| {
+        let (i, j) = (e / S, e % S);
+        if i < 3 && j < 3 { r[i * 3 + j] } else { T::splat(if i == j { 1.0 } else { 0.0 }) }
+    })
+}
+
+/// `v' = v + w·t + u × t` with `t = 2(u × v)`.
+#[inline(always)]
+fn quat_rotate<T: Lane>(x: &[T]) -> [T; 3] {
+    let (u, w, v) = (&x[0..3], x[3], &x[4..7]);
+    let cross = |a: [T; 3], b: [T; 3]| [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]];
+    let u = [u[0], u[1], u[2]];
+    let c = cross(u, [v[0], v[1], v[2]]);
+    let two = T::splat(2.0);
+    let t = [c[0] * two, c[1] * two, c[2] * two];
+    let ut = cross(u, t);
+    std::array::from_fn(|i| v[i] + w * t[i] + ut[i])
+}
+
+// ── Dispatch ────────────────────────────────────────────────
+pub const OPERATIONS: &[&str] = &[
+    "mat_mul", "transpose", "determinant", "inverse", "transform_points", "transform_vectors",
+    "transform_normals", "quat_mul", "quat_slerp", "quat_to_matrix", "quat_rotate",
+];
+
+pub struct Output { pub result: Soa, pub singular: Option<usize>, pub flops_per_item: usize }
+
+pub fn component_error(a: &Soa, b: Option<&Soa>) -> String {
+    match b {
+        Some(b) => format!("does not accept {}-component `a` with {}-component `b`", a.comps, b.comps),
+        None => format!("does not accept {}-component `a` (or needs `b`)", a.comps),
+    }
+}
+
+/// Returns `Ok(None)` for an unknown operation name.
+pub fn dispatch(op: &str, a: &Soa, b: Option<&Soa>, t: Option<&Soa>, size: usize) -> Result<Option<Output>, String> {
+    let need_b = || b.ok_or_else(|| "requires `b`".to_string());
+    let out = |result: Soa, flops_per_item: usize| Ok(Some(Output { result, singular: None, flops_per_item }));
+    match (op, a.comps, b.map(|b| b.comps)) {
+        ("mat_mul", 9, Some(9)) => { let b = need_b()?; out(run(&[a, b], batch_len(&[a, b])?, kernel!(mat_mul, 3, 9)), 45) }
+        ("mat_mul", 16, Some(16)) => { let b = need_b()?; out(run(&[a, b], batch_len(&[a, b])?, kernel!(mat_mul, 4, 16)), 112) }
+        ("transpose", 9, _) => out(run(&[a], a.n, kernel!(transpose, 3, 9)), 0),
+        ("transpose", 16, _) => out(run(&[a], a.n, kernel!(transpose, 4, 16)), 0),
+        ("determinant", 9, _) => out(run(&[a], a.n, kernel!(det3)), 14),
+        ("determinant", 16, _) => out(run(&[a], a.n, kernel!(det4)), 47),
+        ("inverse", 9 | 16, _) => {
+            let (full, comps, flops) = if a.comps == 9 {
+                (run(&[a], a.n, kernel!(inverse3)), 9, 32)
+            } else {
+                (run(&[a], a.n, kernel!(inverse4)), 16, 120)
+            };
+            let singular = full.comp(comps).iter().filter(|d| d.abs() < SINGULAR_EPS).count();
+            Ok(Some(Output { result: full.truncate(comps), singular: Some(singular), flops_per_item: flops }))
+        }
+        ("transform_points", 9, Some(3)) | ("transform_vectors", 9, Some(3)) => {
+            let b = need_b()?;
+            out(run(&[a, b], batch_len(&[a, b])?, kernel!(mat_vec, 3, 3)), 15)
+        }
+        ("transform_points", 16, Some(3)) => { let b = need_b()?; out(run(&[a, b], batch_len(&[a, b])?, kernel!(transform_point4)), 28) }
+        ("transform_points", 16, Some(4)) => { let b = need_b()?; out(run(&[a, b], batch_len(&[a, b])?, kernel!(mat_vec, 4, 4)), 28) }
+        ("transform_vectors", 16, Some(3)) => { let b = need_b()?; out(run(&[a, b], batch_len(&[a, b])?, kernel!(mat_vec, 4, 3)), 15) }
+        ("transform_normals", 9, Some(3)) => { let b = need_b()?; out(run(&[a, b], batch_len(&[a, b])?, kernel!(transform_normal, 3)), 48) }
+        ("transform_normals", 16, Some(3)) => { let b = need_b()?; out(run(&[a, b], batch_len(&[a, b])?, kernel!(transform_normal, 4)), 48) }
+        ("quat_mul", 4, Some(4)) => { let b = need_b()?; out(run(&[a, b], batch_len(&[a, b])?, kernel!(quat_mul)), 28) }
+        ("quat_rotate", 4, Some(3)) => { let b = need_b()?; out(run(&[a, b], batch_len(&[a, b])?, kernel!(quat_rotate)), 30) }
+        ("quat_slerp", 4, Some(4)) => {
+            let (b, t) = (need_b()?, t.ok_or("requires `t` or `scalar`")?);
+            out(run(&[a, b, t], batch_len(&[a, b, t])?, kernel!(quat_slerp)), 40)
+        }
+        ("quat_to_matrix", 4, _) => match size {
+            3 => out(run(&[a], a.n, kernel!(quat_to_matrix, 3, 9)), 25),
+            4 => out(run(&[a], a.n, kernel!(quat_to_matrix, 4, 16)), 25),
+            s => Err(format!("size must be 3 or 4, got {s}")),
+        },
+        _ if OPERATIONS.contains(&op) => Err(component_error(a, b)),
+        _ => geometry::dispatch(op, a, b),
+    }
+}
+
+// ── API ─────────────────────────────────────────────────────
+#[derive(Deserialize)]
+pub(crate) struct BatchRequest {
+    operation: String,
+    a: Vec<Vec<f64>>,
+    b: Option<Vec<Vec<f64>>>,
+    /// Per-item interpolation factors for `quat_slerp`.
+    t: Option<Vec<f64>>,
+    scalar: Option<f64>,
+    layout: Option<String>,
+    /// Output matrix size for `quat_to_matrix` (3 or 4).
+    size: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BatchResponse {
+    operation: String, layout: String, count: usize, components: usize, result: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    singular: Option<usize>,
+    elapsed_ns: u128, throughput_gflops: f64,
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/v1/simd/batch", post(batch))
+}
+
+pub(crate) async fn batch(State(s): State<Arc<AppState>>, limits: Limits, Json(req): Json<BatchRequest>) -> Result<Json<BatchResponse>, ApiError> {
+    let call = s.metrics.call("batch", &req.operation);
+    let t0 = Instant::now();
+    let bad = |e: String| api_err(StatusCode::BAD_REQUEST, "Invalid batch operation", Some(e));
+    let validate = tracing::info_span!("validate").entered();
+    let layout = Layout::parse(req.layout.as_deref()).map_err(bad)?;
+    let a = Soa::parse(req.a, layout, "a").map_err(bad)?;
+    let b = req.b.map(|b| Soa::parse(b, layout, "b")).transpose().map_err(bad)?;
+    limits.batch("batch size of a", a.n)?;
+    if let Some(b) = &b { limits.batch("batch size of b", b.n)?; }
+    if let Some(t) = &req.t { limits.batch("batch size of t", t.len())?; }
+    drop(validate);
+    let t = req.t.map(Soa::column).or_else(|| req.scalar.map(|v| Soa::column(vec![v])));
+    let out = tracing::info_span!("kernel", operation = %req.operation, items = a.n)
+        .in_scope(|| dispatch(&req.operation, &a, b.as_ref(), t.as_ref(), req.size.unwrap_or(3)))
+        .map_err(|e| bad(format!("{}: {e}", req.operation)))?
+        .ok_or_else(|| api_err(StatusCode::BAD_REQUEST, "Unknown batch operation", Some(req.operation.clone())))?;
+    let elapsed_ns = t0.elapsed().as_nanos();
+    let flops = (out.flops_per_item * out.result.n) as f64;
+    call.finish(true, a.n * a.comps, Cost { flops, bytes: 0.0 });
+    s.stats.total_matrix_ops.fetch_add(1, Ordering::Relaxed);
+    Ok(Json(BatchResponse {
+        operation: req.operation, layout: if layout == Layout::Soa { "soa" } else { "aos" }.into(),
+        count: out.result.n, components: out.result.comps, result: out.result.to_json(layout), singular: out.singular,
+        elapsed_ns, throughput_gflops: if elapsed_ns > 0 { flops / elapsed_ns as f64 } else { 0.0 },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Not a multiple of `W`, so the last items run through the `f64` tail.
+    const N: usize = W + 3;
+
+    /// Deterministic values in [-1, 1).
+    fn values(seed: u64, len: usize) -> Vec<f64> {
+        let mut s = seed;
+        (0..len).map(|_| {
+            s = s.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (s >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+        }).collect()
+    }
+
+    /// `N` diagonally dominant (so invertible) S×S matrices, one row per item.
+    fn matrices(seed: u64, s: usize) -> Vec<Vec<f64>> {
+        values(seed, N * s * s).chunks(s * s).map(|m| {
+            m.iter().enumerate().map(|(e, v)| if e / s == e % s { v + s as f64 } else { *v }).collect()
+        }).collect()
+    }
+
+    fn quats(seed: u64) -> Vec<Vec<f64>> {
+        values(seed, N * 4).chunks(4).map(|q| {
+            let len = q.iter().map(|c| c * c).sum::<f64>().sqrt();
+            q.iter().map(|c| c / len).collect()
+        }).collect()
+    }
+
+    /// Runs `op` on AoS items and returns the result one item per row.
+    fn run_aos(op: &str, a: &[Vec<f64>], b: Option<&[Vec<f64>]>, t: Option<Vec<f64>>) -> Output {
+        let soa = |m: &[Vec<f64>]| Soa::parse(m.to_vec(), Layout::Aos, "test").unwrap();
+        let (a, b, t) = (soa(a), b.map(soa), t.map(Soa::column));
+        dispatch(op, &a, b.as_ref(), t.as_ref(), 3).unwrap().unwrap()
+    }
+
+    fn item(r: &Soa, i: usize) -> Vec<f64> {
+        (0..r.comps).map(|k| r.data[k * r.n + i]).collect()
+    }
+
+    fn close(got: &[f64], want: &[f64], what: &str) {
+        assert_eq!(got.len(), want.len(), "{what}");
+        for (g, w) in got.iter().zip(want) {
+            assert!((g - w).abs() <= 1e-9 * (1.0 + w.abs()), "{what}: {got:?} vs {want:?}");
+        }
+    }
+
+    /// Laplace expansion along the first row.
+    fn det_ref(m: &[f64], s: usize) -> f64 {
+        if s == 1 { return m[0]; }
+        (0..s).map(|c| {
+            let minor: Vec<f64> = (1..s).flat_map(|r| (0..s).filter(move |&j| j != c).map(move |j| m[r * s + j])).collect();
+            let sign = if c % 2 == 0 { 1.0 } else { -1.0 };
+            sign * m[c] * det_ref(&minor, s - 1)
+        }).sum()
+    }
+
+    /// Gauss-Jordan with partial pivoting.
+    fn inverse_ref(m: &[f64], s: usize) -> Vec<f64> {
+        let mut a: Vec<Vec<f64>> = (0..s).map(|r| {
+            let mut row = m[r * s..(r + 1) * s].to_vec();
+            row.extend((0..s).map(|c| if c == r { 1.0 } else { 0.0 }));
+            row
+        }).collect();
+        for c in 0..s {
+            let p = (c..s).max_by(|&x, &y| a[x][c].abs().total_cmp(&a[y][c].abs())).unwrap();
+            a.swap(c, p);
+            let pivot = a[c][c];
+            a[c].iter_mut().for_each(|v| *v /= pivot);
+            for r in (0..s).filter(|&r| r != c) {
+                let f = a[r][c];
+                let pr = a[c].clone();
+                a[r].iter_mut().zip(&pr).for_each(|(v, p)| *v -= f * p);
+            }
+        }
+        a.iter().flat_map(|row| row[s..].to_vec()).collect()
+    }
+
+    /// Hamilton product as `(w₁w₂ − v₁·v₂, w₁v₂ + w₂v₁ + v₁ × v₂)`.
+    fn qmul_ref(a: &[f64], b: &[f64]) -> [f64; 4] {
+        let (av, aw, bv, bw) = (&a[..3], a[3], &b[..3], b[3]);
+        let cross = [av[1] * bv[2] - av[2] * bv[1], av[2] * bv[0] - av[0] * bv[2], av[0] * bv[1] - av[1] * bv[0]];
+        let dot = av[0] * bv[0] + av[1] * bv[1] + av[2] * bv[2];
+        let v: [f64; 3] = std::array::from_fn(|i| aw * bv[i] + bw * av[i] + cross[i]);
+        [v[0], v[1], v[2], aw * bw - dot]
+    }
+
+    fn conj(q: &[f64]) -> [f64; 4] {
+        [-q[0], -q[1], -q[2], q[3]]
+    }
+
+    #[test]
+    fn adjugates_match_cofactor_reference_in_every_lane() {
+        let (m3, m4) = (matrices(1, 3), matrices(2, 4));
+        let lanes = |m: &[Vec<f64>], k: usize| Wide(std::array::from_fn(|l| m[l][k]));
+        let w3: Vec<Wide> = (0..9).map(|k| lanes(&m3, k)).collect();
+        let w4: Vec<Wide> = (0..16).map(|k| lanes(&m4, k)).collect();
+        let (adj3w, (adj4w, det4w)) = (adj3(&w3, 3), adj4(&w4));
+        for l in 0..W {
+            // adj(M) = det(M) · M⁻¹
+            let want3: Vec<f64> = inverse_ref(&m3[l], 3).iter().map(|v| v * det_ref(&m3[l], 3)).collect();
+            close(&adj3w.map(|w| w.0[l]), &want3, &format!("adj3 lane {l}"));
+            let want4: Vec<f64> = inverse_ref(&m4[l], 4).iter().map(|v| v * det_ref(&m4[l], 4)).collect();
+            close(&adj4w.map(|w| w.0[l]), &want4, &format!("adj4 lane {l}"));
+            close(&[det4w.0[l]], &[det_ref(&m4[l], 4)], &format!("det4 lane {l}"));
+        }
+    }
+
+    #[test]
+    fn determinant_and_inverse_match_reference_including_tail() {
+        for s in [3, 4] {
+            let mut m = matrices(s as u64, s);
+            // Singular items in a SIMD lane and in the tail invert to zeros.
+            m[2] = vec![1.0; s * s];
+            m[N - 1] = vec![0.0; s * s];
+            let det = run_aos("determinant", &m, None, None).result;
+            let inv = run_aos("inverse", &m, None, None);
+            assert_eq!((det.n, inv.result.n, inv.singular), (N, N, Some(2)));
+            for (i, mi) in m.iter().enumerate() {
+                close(&item(&det, i), &[det_ref(mi, s)], &format!("det{s} item {i}"));
+                let want = if i == 2 || i == N - 1 { vec![0.0; s * s] } else { inverse_ref(mi, s) };
+                close(&item(&inv.result, i), &want, &format!("inverse{s} item {i}"));
+            }
+        }
+    }
+
+    #[test]
+    fn quaternion_ops_match_hamilton_reference_including_tail() {
+        let (a, b) = (quats(3), quats(4));
+        let v: Vec<Vec<f64>> = values(5, N * 3).chunks(3).map(|c| c.to_vec()).collect();
+        let mul = run_aos("quat_mul", &a, Some(&b), None).result;
+        let rot = run_aos("quat_rotate", &a, Some(&v), None).result;
+        let mat = run_aos("quat_to_matrix", &a, None, None).result;
+        for i in 0..N {
+            close(&item(&mul, i), &qmul_ref(&a[i], &b[i]), &format!("quat_mul item {i}"));
+            // q · (v, 0) · q*
+            let want = qmul_ref(&qmul_ref(&a[i], &[v[i][0], v[i][1], v[i][2], 0.0]), &conj(&a[i]));
+            close(&item(&rot, i), &want[..3], &format!("quat_rotate item {i}"));
+            let r = item(&mat, i);
+            let mv: Vec<f64> = (0..3).map(|row| (0..3).map(|c| r[row * 3 + c] * v[i][c]).sum()).collect();
+            close(&mv, &want[..3], &format!("quat_to_matrix item {i}"));
+        }
+    }
+
+    #[test]
+    fn slerp_matches_axis_angle_power_including_tail() {
+        let (a, b) = (quats(6), quats(7));
+        let t: Vec<f64> = values(8, N).iter().map(|v| (v + 1.0) / 2.0).collect();
+        let out = run_aos("quat_slerp", &a, Some(&b), Some(t.clone())).result;
+        for i in 0..N {
+            // slerp(a, b, t) = a · (a* · b)^t along the shorter arc.
+            let mut r = qmul_ref(&conj(&a[i]), &b[i]);
+            if r[3] < 0.0 { r = r.map(|c| -c); }
+            let half = r[3].clamp(-1.0, 1.0).acos();
+            let axis_len = (r[0] * r[0] + r[1] * r[1] + r[2] * r[2]).sqrt();
+            let (s, c) = (t[i] * half).sin_cos();
+            let rt = [r[0] / axis_len * s, r[1] / axis_len * s, r[2] / axis_len * s, c];
+            close(&item(&out, i), &qmul_ref(&a[i], &rt), &format!("quat_slerp item {i}"));
+        }
+        // A scalar `t` broadcasts; the endpoints return `a` and (sign-matched) `b`.
+        let start = run_aos("quat_slerp", &a, Some(&b), Some(vec![0.0])).result;
+        let end = run_aos("quat_slerp", &a, Some(&b), Some(vec![1.0])).result;
+        for i in 0..N {
+            close(&item(&start, i), &a[i], &format!("t=0 item {i}"));
+            let d: f64 = a[i].iter().zip(&b[i]).map(|(x, y)| x * y).sum();
+            let want: Vec<f64> = b[i].iter().map(|c| c * d.signum()).collect();
+            close(&item(&end, i), &want, &format!("t=1 item {i}"));
+        }
+    }
+}
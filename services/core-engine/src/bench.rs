@@ -0,0 +1,605 @@
+// ── Benchmark harness ───────────────────────────────────────
+// A suite is the cross product of operations × sizes × dtypes × thread
+// counts; every cell runs each requested kernel variant. Each run gets
+// untimed warmup rounds (page faults, cache fill, clock ramp-up) and is
+// then timed once per iteration. Inputs and outputs go through `black_box`
+// so the optimiser can neither hoist the work out of the loop nor delete it
+// as dead code.
+use crate::cost::{self, Cost};
+use crate::{perf, simd, BenchmarkRequest};
+use serde::{Deserialize, Serialize};
+use std::hint::black_box;
+use std::ops::{Add, Div, Mul};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Barrier;
+use std::time::Instant;
+
+#[derive(Clone, Default, Serialize)]
+pub struct Summary {
+    pub samples: usize,
+    pub min_ns: f64,
+    pub max_ns: f64,
+    pub mean_ns: f64,
+    pub median_ns: f64,
+    pub stddev_ns: f64,
+    pub p5_ns: f64,
+    pub p95_ns: f64,
+    /// Samples outside Tukey's fences (1.5 × IQR beyond the quartiles).
+    pub outliers_low: usize,
+    pub outliers_high: usize,
+}
+
+/// Times `work` over every part once per iteration after `warmup`
+/// untimed rounds, returning one duration (ns) per timed iteration. With
+/// more than one part, each part gets its own scoped thread and an
+/// iteration spans from a shared start barrier until the slowest part
+/// finishes. Stops early (with the samples so far) when the surrounding job or
+/// request is cancelled. `counters`, opened on this thread, run over exactly the
+/// timed iterations.
+pub fn sample_parts<P: Send>(warmup: usize, iterations: usize, counters: Option<&perf::Counters>, mut parts: Vec<P>,
+                             work: impl Fn(&mut P) -> f64 + Sync) -> Vec<f64> {
+    let mut samples = Vec::with_capacity(iterations);
+    let start = |i: usize| if i == warmup { if let Some(c) = counters { c.start() } };
+    if parts.len() <= 1 {
+        for i in 0..warmup + iterations {
+            if crate::jobs::cancelled() { break; }
+            start(i);
+            let t = Instant::now();
+            black_box(parts.iter_mut().map(&work).sum::<f64>());
+            if i >= warmup { samples.push(t.elapsed().as_nanos() as f64); }
+        }
+        if let Some(c) = counters { c.stop() }
+        return samples;
+    }
+    let (barrier, stop) = (Barrier::new(parts.len()), AtomicBool::new(false));
+    let (barrier, stop, work) = (&barrier, &stop, &work);
+    std::thread::scope(|scope| {
+        let mut parts = parts.into_iter();
+        let mut mine = parts.next().unwrap();
+        for mut part in parts {
+            scope.spawn(move || loop {
+                barrier.wait();
+                if stop.load(Ordering::Relaxed) { break; }
+                black_box(work(&mut part));
+                barrier.wait();
+            });
+        }
+        for i in 0..warmup + iterations {
+            if crate::jobs::cancelled() { break; }
+            start(i);
+            barrier.wait();
+            let t = Instant::now();
+            black_box(work(&mut mine));
+            barrier.wait();
+            if i >= warmup { samples.push(t.elapsed().as_nanos() as f64); }
+        }
+        if let Some(c) = counters { c.stop() }
+        stop.store(true, Ordering::Relaxed);
+        barrier.wait();
+    });
+    samples
+}
+
+/// Linear-interpolated percentile of sorted samples, `p` in 0..=1.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let Some(&last) = sorted.last() else { return 0.0 };
+    let rank = p * (sorted.len() - 1) as f64;
+    let (lo, frac) = (rank.floor() as usize, rank.fract());
+    match sorted.get(lo + 1) {
+        Some(&next) => sorted[lo] + (next - sorted[lo]) * frac,
+        None => last,
+    }
+}
+
+pub fn summarize(samples: &[f64]) -> Summary {
+    let n = samples.len();
+    if n == 0 { return Summary::default(); }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mean = sorted.iter().sum::<f64>() / n as f64;
+    let var = if n > 1 { sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64 } else { 0.0 };
+    let (q1, q3) = (percentile(&sorted, 0.25), percentile(&sorted, 0.75));
+    let (lo_fence, hi_fence) = (q1 - 1.5 * (q3 - q1), q3 + 1.5 * (q3 - q1));
+    Summary {
+        samples: n,
+        min_ns: sorted[0],
+        max_ns: sorted[n - 1],
+        mean_ns: mean,
+        median_ns: percentile(&sorted, 0.5),
+        stddev_ns: var.sqrt(),
+        p5_ns: percentile(&sorted, 0.05),
+        p95_ns: percentile(&sorted, 0.95),
+        outliers_low: sorted.iter().take_while(|&&x| x < lo_fence).count(),
+        outliers_high: sorted.iter().rev().take_while(|&&x| x > hi_fence).count(),
+    }
+}
+
+// ── Suites ──────────────────────────────────────────────────
+pub const VECTOR_OPERATIONS: &[&str] = &["add", "mul", "fma", "dot_product", "normalize"];
+/// Square `n × n` matrices with `n = ⌊√size⌋`, so a size sweep covers the
+/// same memory footprints as the vector operations.
+pub const MATRIX_OPERATIONS: &[&str] = &["gemm", "transpose"];
+pub const DTYPES: &[&str] = &["f64", "f32"];
+const MAX_THREADS: usize = 256;
+/// Sizes one sweep may expand to (2^0 through 2^31).
+pub const MAX_SWEEP_STEPS: usize = 32;
+
+/// Powers of two from `min` to `max` elements (inclusive).
+#[derive(Deserialize)]
+pub struct Sweep { pub min: usize, pub max: usize }
+
+pub struct Suite {
+    pub operations: Vec<String>,
+    pub sizes: Vec<usize>,
+    pub dtypes: Vec<String>,
+    pub threads: Vec<usize>,
+    pub variants: Vec<Variant>,
+    pub warmup: usize,
+    pub iterations: usize,
+    /// Read hardware counters around each run (when perf_event_open allows).
+    pub counters: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Axes { pub operations: Vec<String>, pub sizes: Vec<usize>, pub dtypes: Vec<String>, pub threads: Vec<usize> }
+
+/// One cell of the grid. Top-level timings are the widest variant's;
+/// `variants` has all of them.
+#[derive(Serialize)]
+pub struct BenchmarkResult {
+    pub operation: String, pub size: usize, pub dtype: String, pub threads: usize,
+    /// Per iteration, from the cost model.
+    pub flops: f64, pub bytes_processed: u64,
+    pub iterations: usize, pub warmup: usize,
+    pub variant: String,
+    /// Sum of the timed iterations; `per_op_ns` and throughput use the median.
+    pub total_ns: u128, pub per_op_ns: u128, pub throughput_gflops: f64, pub gbytes_per_sec: f64,
+    pub speedup_vs_scalar: Option<f64>,
+    pub stats: Summary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counters: Option<perf::Counts>,
+    pub variants: Vec<VariantResult>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct VariantResult {
+    pub variant: String, pub total_ns: u128, pub per_op_ns: u128, pub throughput_gflops: f64, pub gbytes_per_sec: f64,
+    pub speedup_vs_scalar: Option<f64>,
+    pub stats: Summary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counters: Option<perf::Counts>,
+    /// Kept for baselines; too bulky for the response.
+    #[serde(skip)]
+    pub samples: Vec<f64>,
+}
+
+/// Timed samples (ns) and the counters read around them.
+type Run = (Vec<f64>, Option<perf::Counts>);
+
+fn dedup<T: PartialEq + Clone>(v: &mut Vec<T>) {
+    let mut seen = Vec::with_capacity(v.len());
+    v.retain(|x| if seen.contains(x) { false } else { seen.push(x.clone()); true });
+}
+
+impl Suite {
+    pub fn from_request(req: &BenchmarkRequest) -> Result<Suite, String> {
+        let iterations = req.iterations.unwrap_or(100);
+        let mut operations = req.operations.clone()
+            .unwrap_or_else(|| VECTOR_OPERATIONS.iter().map(|s| s.to_string()).collect());
+        dedup(&mut operations);
+        if let Some(op) = operations.iter().find(|o| !VECTOR_OPERATIONS.contains(&o.as_str()) && !MATRIX_OPERATIONS.contains(&o.as_str())) {
+            return Err(format!("unknown operation '{op}' (expected one of {}, {})", VECTOR_OPERATIONS.join(", "), MATRIX_OPERATIONS.join(", ")));
+        }
+        let mut sizes = req.sizes.clone().unwrap_or_default();
+        if let Some(Sweep { min, max }) = req.sweep {
+            if min == 0 || min > max { return Err(format!("sweep needs 0 < min <= max, got {min}..{max}")); }
+            let (mut n, mut swept) = (min.checked_next_power_of_two(), false);
+            for step in 0.. {
+                let Some(size) = n.filter(|&s| s <= max) else { break };
+                if step == MAX_SWEEP_STEPS {
+                    return Err(format!("sweep {min}..{max} has more than {MAX_SWEEP_STEPS} sizes"));
+                }
+                sizes.push(size);
+                n = size.checked_mul(2);
+                swept = true;
+            }
+            if !swept { return Err(format!("sweep {min}..{max} contains no power of two")); }
+        }
+        if sizes.is_empty() { sizes.push(req.size.unwrap_or(10000)); }
+        dedup(&mut sizes);
+        let mut dtypes = req.dtypes.clone().unwrap_or_else(|| vec!["f64".into()]);
+        dedup(&mut dtypes);
+        if let Some(d) = dtypes.iter().find(|d| !DTYPES.contains(&d.as_str())) {
+            return Err(format!("unsupported dtype '{d}' (expected one of {})", DTYPES.join(", ")));
+        }
+        let mut threads = req.threads.clone().unwrap_or_else(|| vec![1]);
+        dedup(&mut threads);
+        if let Some(t) = threads.iter().find(|&&t| t == 0 || t > MAX_THREADS) {
+            return Err(format!("thread count {t} out of range 1..={MAX_THREADS}"));
+        }
+        let available = variants();
+        let variants = match &req.variants {
+            None => available,
+            Some(names) => {
+                if let Some(n) = names.iter().find(|n| !available.iter().any(|v| v.name == n.as_str())) {
+                    let names: Vec<_> = available.iter().map(|v| v.name).collect();
+                    return Err(format!("variant '{n}' is not available on this CPU (available: {})", names.join(", ")));
+                }
+                available.into_iter().filter(|v| names.iter().any(|n| n == v.name)).collect()
+            }
+        };
+        Ok(Suite {
+            operations, sizes, dtypes, threads, variants, iterations,
+            warmup: req.warmup.unwrap_or(iterations.div_ceil(10)),
+            counters: req.counters.unwrap_or(true),
+        })
+    }
+
+    pub fn axes(&self) -> Axes {
+        Axes { operations: self.operations.clone(), sizes: self.sizes.clone(), dtypes: self.dtypes.clone(), threads: self.threads.clone() }
+    }
+
+    /// Work over the whole run, per warmup and timed iteration: `size`
+    /// elements for each vector cell and variant, and for matrix operations
+    /// (run once per f64 cell) their FLOPs, since gemm grows as size^1.5.
+    pub fn work(&self) -> u128 {
+        let reps = (self.threads.len() * (self.warmup + self.iterations)) as u128;
+        let vector_runs = (self.dtypes.len() * self.variants.len()) as u128;
+        let matrix_runs = self.dtypes.iter().filter(|d| *d != "f32").count() as u128;
+        self.sizes.iter().map(|&size| self.operations.iter().map(|op| {
+            if MATRIX_OPERATIONS.contains(&op.as_str()) {
+                matrix_runs * (cost::kernel(op, size, 8).flops as u128).max(size as u128)
+            } else {
+                vector_runs * size as u128
+            }
+        }).sum::<u128>() * reps).sum()
+    }
+
+    /// `sample_parts` plus this run's counters, per timed iteration over
+    /// `elements`.
+    fn measure<P: Send>(&self, elements: usize, parts: Vec<P>, work: impl Fn(&mut P) -> f64 + Sync) -> Run {
+        let counters = if self.counters { perf::Counters::open().ok() } else { None };
+        let samples = sample_parts(self.warmup, self.iterations, counters.as_ref(), parts, work);
+        let counts = counters.and_then(|c| c.read(samples.len(), elements));
+        (samples, counts)
+    }
+
+    /// Runs every cell, in dtype → size → operation → threads order.
+    pub fn run(&self) -> Vec<BenchmarkResult> {
+        let blocks = (self.dtypes.len() * self.sizes.len()) as f64;
+        let mut out = Vec::new();
+        for (di, dtype) in self.dtypes.iter().enumerate() {
+            for (si, &size) in self.sizes.iter().enumerate() {
+                if crate::jobs::cancelled() { return out; }
+                match dtype.as_str() {
+                    "f32" => self.run_vector::<f32>(size, &mut out),
+                    _ => {
+                        self.run_vector::<f64>(size, &mut out);
+                        self.run_matrix(size, &mut out);
+                    }
+                }
+                crate::jobs::report_progress((di * self.sizes.len() + si + 1) as f64 / blocks);
+            }
+        }
+        out
+    }
+
+    fn run_vector<T: Elem>(&self, size: usize, out: &mut Vec<BenchmarkResult>) {
+        let a: Vec<T> = (0..size).map(|i| T::from_f64(i as f64 * 0.001)).collect();
+        let b: Vec<T> = (0..size).map(|i| T::from_f64(i as f64 * 0.002 + 1.0)).collect();
+        let mut o = vec![T::default(); size];
+        for op in self.operations.iter().filter(|o| VECTOR_OPERATIONS.contains(&o.as_str())) {
+            let cost = cost::kernel(op, size, std::mem::size_of::<T>());
+            for &threads in &self.threads {
+                let chunk = size.div_ceil(threads).max(1);
+                let mut timings = Vec::with_capacity(self.variants.len());
+                for variant in &self.variants {
+                    if crate::jobs::cancelled() { return; }
+                    let Some(kernel) = variant.kernel::<T>(op) else { continue };
+                    let parts: Vec<_> = a.chunks(chunk).zip(b.chunks(chunk)).zip(o.chunks_mut(chunk)).collect();
+                    let run = self.measure(size, parts, |((a, b), o)| {
+                        let r = kernel(black_box(a), black_box(b), o);
+                        black_box(o);
+                        r
+                    });
+                    timings.push((variant.name.to_string(), run));
+                }
+                self.push(out, op, size, T::NAME, threads, cost, timings);
+            }
+        }
+    }
+
+    fn run_matrix(&self, size: usize, out: &mut Vec<BenchmarkResult>) {
+        let n = size.isqrt();
+        if n == 0 { return; }
+        let a: Vec<f64> = (0..n * n).map(|i| (i % 97) as f64 * 0.01).collect();
+        let b: Vec<f64> = (0..n * n).map(|i| (i % 89) as f64 * 0.01 - 0.4).collect();
+        let mut c = vec![0.0f64; n * n];
+        for op in self.operations.iter().filter(|o| MATRIX_OPERATIONS.contains(&o.as_str())) {
+            for &threads in &self.threads {
+                if crate::jobs::cancelled() { return; }
+                let rows = n.div_ceil(threads) * n;
+                let run = match op.as_str() {
+                    "gemm" => {
+                        let parts: Vec<_> = a.chunks(rows).zip(c.chunks_mut(rows)).collect();
+                        self.measure(n * n, parts, |(ar, cr)| {
+                            simd::gemm_f64(ar.len() / n, n, n, black_box(ar), black_box(&b), cr);
+                            0.0
+                        })
+                    }
+                    _ => {
+                        let parts: Vec<_> = c.chunks_mut(rows).enumerate().collect();
+                        self.measure(n * n, parts, |(idx, cr)| {
+                            let a = black_box(&a);
+                            for (r, row) in cr.chunks_mut(n).enumerate() {
+                                let j = *idx * rows / n + r;
+                                for (i, v) in row.iter_mut().enumerate() { *v = a[i * n + j]; }
+                            }
+                            0.0
+                        })
+                    }
+                };
+                self.push(out, op, n * n, "f64", threads, cost::kernel(op, n * n, 8), vec![("dispatch".into(), run)]);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push(&self, out: &mut Vec<BenchmarkResult>, op: &str, size: usize, dtype: &str, threads: usize, cost: Cost,
+            timings: Vec<(String, Run)>) {
+        let mut variants: Vec<VariantResult> = Vec::with_capacity(timings.len());
+        for (variant, (samples, counters)) in timings {
+            let stats = summarize(&samples);
+            let scalar_ns = variants.first().filter(|v| v.variant == "scalar").map(|v| v.stats.median_ns)
+                .or((variant == "scalar").then_some(stats.median_ns));
+            let per = |x: f64| if stats.median_ns > 0.0 { x / stats.median_ns } else { 0.0 };
+            variants.push(VariantResult {
+                total_ns: samples.iter().sum::<f64>() as u128,
+                per_op_ns: stats.median_ns as u128,
+                throughput_gflops: per(cost.flops),
+                gbytes_per_sec: per(cost.bytes),
+                speedup_vs_scalar: scalar_ns.map(per),
+                variant, stats, counters, samples,
+            });
+        }
+        let Some(best) = variants.last().cloned() else { return };
+        out.push(BenchmarkResult {
+            operation: op.into(), size, dtype: dtype.into(), threads,
+            flops: cost.flops, bytes_processed: cost.bytes as u64,
+            iterations: best.stats.samples, warmup: self.warmup,
+            variant: best.variant, total_ns: best.total_ns, per_op_ns: best.per_op_ns,
+            throughput_gflops: best.throughput_gflops, gbytes_per_sec: best.gbytes_per_sec,
+            speedup_vs_scalar: best.speedup_vs_scalar,
+            stats: best.stats, counters: best.counters, variants,
+        });
+    }
+}
+
+// ── Kernel variants ─────────────────────────────────────────
+// The vector operations compiled once per instruction set. The generic
+// bodies are `#[inline(always)]`, so each `#[target_feature]` wrapper
+// auto-vectorises them at that width (SSE2 and NEON are the baselines of
+// their targets). The scalar variant reads its inputs with volatile loads,
+// which LLVM cannot merge into vector loads.
+pub type Kernel<T> = fn(&[T], &[T], &mut [T]) -> f64;
+
+pub trait Elem: Copy + Default + Send + Sync + Add<Output = Self> + Mul<Output = Self> + Div<Output = Self> {
+    const NAME: &'static str;
+    fn from_f64(v: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn mul_add(self, a: Self, b: Self) -> Self;
+    fn sqrt(self) -> Self;
+}
+
+macro_rules! elem {
+    ($t:ty) => {
+        impl Elem for $t {
+            const NAME: &'static str = stringify!($t);
+            fn from_f64(v: f64) -> Self { v as $t }
+            fn to_f64(self) -> f64 { self as f64 }
+            #[inline(always)]
+            fn mul_add(self, a: Self, b: Self) -> Self { <$t>::mul_add(self, a, b) }
+            #[inline(always)]
+            fn sqrt(self) -> Self { <$t>::sqrt(self) }
+        }
+    };
+}
+elem!(f64);
+elem!(f32);
+
+pub struct Variant { pub name: &'static str }
+
+impl Variant {
+    pub fn kernel<T: Elem>(&self, op: &str) -> Option<Kernel<T>> {
+        match self.name {
+            "scalar" => scalar::kernel(op),
+            #[cfg(target_arch = "x86_64")]
+            "avx2" => avx2::kernel(op),
+            #[cfg(target_arch = "x86_64")]
+            "avx512" => avx512::kernel(op),
+            _ => baseline::kernel(op),
+        }
+    }
+}
+
+/// Variants runnable on this CPU, scalar first and widest last.
+pub fn variants() -> Vec<Variant> {
+    let mut v = vec![Variant { name: "scalar" }];
+    #[cfg(target_arch = "x86_64")]
+    {
+        v.push(Variant { name: "sse2" });
+        if simd::has_avx2_fma() {
+            v.push(Variant { name: "avx2" });
+        }
+        if is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("fma") {
+            v.push(Variant { name: "avx512" });
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    v.push(Variant { name: "neon" });
+    v
+}
+
+mod lanes {
+    use super::Elem;
+    const L: usize = 16;
+
+    #[inline(always)]
+    pub fn zip<T: Elem>(a: &[T], b: &[T], out: &mut [T], f: impl Fn(T, T) -> T) -> f64 {
+        for ((o, x), y) in out.iter_mut().zip(a).zip(b) { *o = f(*x, *y); }
+        0.0
+    }
+
+    /// Independent accumulators so the reduction can vectorise.
+    #[inline(always)]
+    pub fn dot<T: Elem>(a: &[T], b: &[T]) -> T {
+        let n = a.len().min(b.len());
+        let (ca, cb) = (a[..n].chunks_exact(L), b[..n].chunks_exact(L));
+        let tail = ca.remainder().iter().zip(cb.remainder()).fold(T::default(), |s, (x, y)| s + *x * *y);
+        let mut acc = [T::default(); L];
+        for (x, y) in ca.zip(cb) {
+            for l in 0..L { acc[l] = acc[l] + x[l] * y[l]; }
+        }
+        acc.iter().fold(tail, |s, x| s + *x)
+    }
+
+    #[inline(always)]
+    pub fn normalize<T: Elem>(a: &[T], out: &mut [T]) -> f64 {
+        let mag = dot(a, a).sqrt();
+        for (o, x) in out.iter_mut().zip(a) { *o = *x / mag; }
+        mag.to_f64()
+    }
+}
+
+macro_rules! isa_variant {
+    ($name:ident $(, $feature:literal)?) => {
+        mod $name {
+            use super::{lanes, Elem, Kernel};
+            $(#[target_feature(enable = $feature)])?
+            unsafe fn add<T: Elem>(a: &[T], b: &[T], o: &mut [T]) -> f64 { lanes::zip(a, b, o, |x, y| x + y) }
+            $(#[target_feature(enable = $feature)])?
+            unsafe fn mul<T: Elem>(a: &[T], b: &[T], o: &mut [T]) -> f64 { lanes::zip(a, b, o, |x, y| x * y) }
+            $(#[target_feature(enable = $feature)])?
+            unsafe fn fma<T: Elem>(a: &[T], b: &[T], o: &mut [T]) -> f64 { lanes::zip(a, b, o, |x, y| x.mul_add(y, T::from_f64(1.0))) }
+            $(#[target_feature(enable = $feature)])?
+            unsafe fn dot<T: Elem>(a: &[T], b: &[T], _: &mut [T]) -> f64 { lanes::dot(a, b).to_f64() }
+            $(#[target_feature(enable = $feature)])?
+            unsafe fn normalize<T: Elem>(a: &[T], _: &[T], o: &mut [T]) -> f64 { lanes::normalize(a, o) }
+
+            // SAFETY (all below): `variants()` only hands out this module's
+            // kernels after detecting its target feature.
+            pub fn kernel<T: Elem>(op: &str) -> Option<Kernel<T>> {
+                Some(match op {
+                    "add" => |a, b, o| unsafe { add(a, b, o) },
+                    "mul" => |a, b, o| unsafe { mul(a, b, o) },
+                    "fma" => |a, b, o| unsafe { fma(a, b, o) },
+                    "dot_product" => |a, b, o| unsafe { dot(a, b, o) },
+                    "normalize" => |a, b, o| unsafe { normalize(a, b, o) },
+                    _ => return None,
+                })
+            }
+        }
+    };
+}
+
+isa_variant!(baseline);
+#[cfg(target_arch = "x86_64")]
+isa_variant!(avx2, "avx2,fma");
+#[cfg(target_arch = "x86_64")]
+isa_variant!(avx512, "avx512f,fma");
+
+mod scalar {
+    use super::{Elem, Kernel};
+
+    #[inline(always)]
+    fn ld<T: Elem>(x: &T) -> T {
+        // SAFETY: `x` is a valid reference.
+        unsafe { std::ptr::read_volatile(x) }
+    }
+
+    fn zip<T: Elem>(a: &[T], b: &[T], out: &mut [T], f: impl Fn(T, T) -> T) -> f64 {
+        for ((o, x), y) in out.iter_mut().zip(a).zip(b) { *o = f(ld(x), ld(y)); }
+        0.0
+    }
+
+    fn dot<T: Elem>(a: &[T], b: &[T]) -> T {
+        a.iter().zip(b).fold(T::default(), |s, (x, y)| s + ld(x) * ld(y))
+    }
+
+    pub fn kernel<T: Elem>(op: &str) -> Option<Kernel<T>> {
+        Some(match op {
+            "add" => |a, b, o| zip(a, b, o, |x, y| x + y),
+            "mul" => |a, b, o| zip(a, b, o, |x, y| x * y),
+            "fma" => |a, b, o| zip(a, b, o, |x, y| x.mul_add(y, T::from_f64(1.0))),
+            "dot_product" => |a, b, _| dot(a, b).to_f64(),
+            "normalize" => |a, _, o| {
+                let mag = dot(a, a).sqrt();
+                for (o, x) in o.iter_mut().zip(a) { *o = ld(x) / mag; }
+                mag.to_f64()
+            },
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool { (a - b).abs() < 1e-9 }
+
+    #[test]
+    fn summarize_percentiles() {
+        // 1..=100 in scrambled order.
+        let samples: Vec<f64> = (0..100).map(|i| ((i * 37) % 100 + 1) as f64).collect();
+        let s = summarize(&samples);
+        assert_eq!(s.samples, 100);
+        assert_eq!((s.min_ns, s.max_ns), (1.0, 100.0));
+        assert!(close(s.mean_ns, 50.5) && close(s.median_ns, 50.5));
+        assert!(close(s.p5_ns, 5.95) && close(s.p95_ns, 95.05), "p5 {} p95 {}", s.p5_ns, s.p95_ns);
+        assert!(close(s.stddev_ns, (10100.0f64 / 12.0).sqrt()), "stddev {}", s.stddev_ns);
+        assert_eq!((s.outliers_low, s.outliers_high), (0, 0));
+    }
+
+    #[test]
+    fn summarize_counts_tukey_outliers() {
+        // -500, 1..=20, 1000: Q1 = 5.25, Q3 = 15.75, fences at -10.5 and 31.5.
+        let mut samples: Vec<f64> = (1..=20).map(f64::from).collect();
+        samples.extend([1000.0, -500.0]);
+        let s = summarize(&samples);
+        assert!(close(s.p5_ns, 1.05) && close(s.median_ns, 10.5));
+        assert_eq!((s.outliers_low, s.outliers_high), (1, 1));
+        // Now Q1 = 4.75, Q3 = 16.25: -10 and 31 fall inside the fences at -12.5 and 33.5.
+        samples.extend([31.0, -10.0]);
+        let s = summarize(&samples);
+        assert_eq!((s.outliers_low, s.outliers_high), (1, 1));
+    }
+
+    fn sweep(min: usize, max: usize) -> Result<Vec<usize>, String> {
+        let req: BenchmarkRequest = serde_json::from_value(serde_json::json!({ "sweep": { "min": min, "max": max } })).unwrap();
+        Suite::from_request(&req).map(|s| s.sizes)
+    }
+
+    #[test]
+    fn sweep_expands_powers_of_two_without_overflow() {
+        assert_eq!(sweep(1000, 8192).unwrap(), [1024, 2048, 4096, 8192]);
+        assert_eq!(sweep(1, 1).unwrap(), [1]);
+        assert_eq!(sweep(1 << 20, 1 << 20).unwrap(), [1 << 20]);
+        // Neither doubling past usize::MAX nor rounding min up may wrap.
+        assert!(sweep(1, usize::MAX).unwrap_err().contains("more than"));
+        assert_eq!(sweep(1 << 40, usize::MAX).unwrap().len(), 24);
+        assert!(sweep((1 << 63) + 1, usize::MAX).unwrap_err().contains("no power of two"));
+        assert!(sweep(5, 7).is_err());
+        assert!(sweep(0, 4).is_err() && sweep(8, 4).is_err());
+    }
+
+    #[test]
+    fn summarize_degenerate_inputs() {
+        assert_eq!(summarize(&[]).samples, 0);
+        let s = summarize(&[42.0]);
+        assert_eq!((s.min_ns, s.median_ns, s.p5_ns, s.p95_ns, s.max_ns), (42.0, 42.0, 42.0, 42.0, 42.0));
+        assert_eq!((s.stddev_ns, s.outliers_low, s.outliers_high), (0.0, 0, 0));
+        let s = summarize(&[7.0; 9]);
+        assert_eq!((s.median_ns, s.outliers_low, s.outliers_high), (7.0, 0, 0));
+    }
+}
@@ -0,0 +1,352 @@
+// ── Stored collections ──────────────────────────────────────
+// Named in-memory vector collections with an optional ANN index attached.
+// Collections and their snapshots belong to the caller (`X-User-Id`;
+// anonymous callers share one namespace), like baselines and history.
+// Size-like request parameters are range-checked up front: they feed
+// allocation sizes, and a capacity overflow would abort the engine.
+use crate::ann::{AnnIndex, Hnsw, IvfPq, Metric, VectorStore};
+use crate::history::User;
+use crate::limits::Limits;
+use crate::{api_err, ApiError, AppState};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use uuid::Uuid;
+
+pub struct Collection {
+    store: VectorStore,
+    slots: HashMap<u64, u32>,
+    next_id: u64,
+    index: Option<AnnIndex>,
+}
+
+#[derive(Deserialize)]
+struct Snapshot { store: VectorStore, next_id: u64, index: Option<AnnIndex> }
+#[derive(Serialize)]
+struct SnapshotRef<'a> { name: &'a str, store: &'a VectorStore, next_id: u64, index: Option<&'a AnnIndex> }
+
+/// Keyed by owner and name.
+pub type Collections = RwLock<HashMap<(Option<Uuid>, String), Arc<RwLock<Collection>>>>;
+
+const MAX_K: usize = 10_000;
+const MAX_EF: usize = 10_000;
+const MAX_RERANK: usize = 100;
+const MAX_HNSW_M: usize = 256;
+const MAX_KMEANS_ITERATIONS: usize = 1_000;
+
+/// 400 unless `value` is in `1..=max`.
+fn in_range(name: &str, value: usize, max: usize) -> Result<usize, ApiError> {
+    if (1..=max).contains(&value) { return Ok(value); }
+    Err(api_err(StatusCode::BAD_REQUEST, "Parameter out of range", Some(format!("{name} is {value}; expected 1..={max}"))))
+}
+
+impl Collection {
+    fn new(dim: usize, metric: Metric) -> Self {
+        Self { store: VectorStore::new(dim, metric), slots: HashMap::new(), next_id: 0, index: None }
+    }
+
+    fn from_snapshot(s: Snapshot) -> Self {
+        let slots = s.store.ids.iter().enumerate()
+            .filter(|(i, _)| s.store.live[*i])
+            .map(|(i, id)| (*id, i as u32)).collect();
+        Self { store: s.store, slots, next_id: s.next_id, index: s.index }
+    }
+
+    /// Inserts or replaces `id`, which must be below `u64::MAX` (see
+    /// `insert_vectors`); a replaced vector is tombstoned first.
+    fn upsert(&mut self, id: u64, v: &[f64]) {
+        self.next_id = self.next_id.max(id + 1);
+        self.delete(id);
+        let prepared = self.store.prepare(v);
+        let slot = self.store.push(id, &prepared);
+        self.slots.insert(id, slot);
+        if let Some(idx) = self.index.as_mut() { idx.insert(&self.store, slot); }
+    }
+
+    fn delete(&mut self, id: u64) -> bool {
+        let Some(slot) = self.slots.remove(&id) else { return false };
+        self.store.live[slot as usize] = false;
+        if let Some(idx) = self.index.as_mut() { idx.remove(slot); }
+        true
+    }
+
+    fn info(&self, name: &str) -> CollectionInfo {
+        CollectionInfo {
+            name: name.into(), dim: self.store.dim, metric: self.store.metric,
+            count: self.slots.len(), tombstones: self.store.len() - self.slots.len(),
+            index: self.index.as_ref().map(|i| i.kind().to_string()),
+            index_params: self.index.as_ref().map(|i| i.params()),
+        }
+    }
+}
+
+// ── Types ───────────────────────────────────────────────────
+#[derive(Deserialize)]
+struct CreateCollectionRequest { name: String, dim: usize, metric: Option<String> }
+
+#[derive(Serialize)]
+struct CollectionInfo {
+    name: String, dim: usize, metric: Metric, count: usize, tombstones: usize,
+    index: Option<String>, index_params: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct InsertRequest { vectors: Vec<Vec<f64>>, ids: Option<Vec<u64>> }
+#[derive(Serialize)]
+struct InsertResponse { ids: Vec<u64>, count: usize, elapsed_ns: u128 }
+
+#[derive(Deserialize)]
+struct DeleteRequest { ids: Vec<u64> }
+#[derive(Serialize)]
+struct DeleteResponse { deleted: usize, count: usize }
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum IndexRequest {
+    Hnsw { m: Option<usize>, ef_construction: Option<usize>, seed: Option<u64> },
+    IvfPq { nlist: Option<usize>, m: Option<usize>, nbits: Option<u32>, iterations: Option<usize>, seed: Option<u64> },
+}
+#[derive(Serialize)]
+struct IndexResponse { index: String, params: serde_json::Value, indexed: usize, build_ns: u128 }
+
+#[derive(Deserialize)]
+struct SearchRequest { vector: Vec<f64>, k: Option<usize>, ef: Option<usize>, nprobe: Option<usize>, rerank: Option<usize>, exact: Option<bool> }
+#[derive(Serialize)]
+struct SearchHit { id: u64, distance: f32 }
+#[derive(Serialize)]
+struct SearchResponse { hits: Vec<SearchHit>, index: Option<String>, elapsed_ns: u128 }
+
+#[derive(Serialize)]
+struct SnapshotResponse { name: String, path: String, bytes: u64, count: usize }
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/v1/simd/collections", get(list_collections).post(create_collection))
+        .route("/api/v1/simd/collections/:name", get(get_collection).delete(drop_collection))
+        .route("/api/v1/simd/collections/:name/vectors", post(insert_vectors).delete(delete_vectors))
+        .route("/api/v1/simd/collections/:name/index", post(build_index).delete(drop_index))
+        .route("/api/v1/simd/collections/:name/search", post(search))
+        .route("/api/v1/simd/collections/:name/snapshot", post(snapshot))
+        .route("/api/v1/simd/collections/:name/restore", post(restore))
+}
+
+// ── Handlers ────────────────────────────────────────────────
+fn lookup(s: &AppState, user: User, name: &str) -> Result<Arc<RwLock<Collection>>, ApiError> {
+    s.collections.read().unwrap().get(&(user.0, name.to_string())).cloned()
+        .ok_or_else(|| api_err(StatusCode::NOT_FOUND, "Collection not found", Some(name.into())))
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= 128
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+async fn list_collections(State(s): State<Arc<AppState>>, user: User) -> Json<Vec<CollectionInfo>> {
+    let map = s.collections.read().unwrap();
+    let mut out: Vec<CollectionInfo> = map.iter().filter(|((owner, _), _)| *owner == user.0)
+        .map(|((_, n), c)| c.read().unwrap().info(n)).collect();
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    Json(out)
+}
+
+async fn create_collection(
+    State(s): State<Arc<AppState>>, user: User, Json(req): Json<CreateCollectionRequest>,
+) -> Result<Json<CollectionInfo>, ApiError> {
+    if !valid_name(&req.name) {
+        return Err(api_err(StatusCode::BAD_REQUEST, "Invalid collection name", Some("use [A-Za-z0-9_-], max 128 chars".into())));
+    }
+    if req.dim == 0 {
+        return Err(api_err(StatusCode::BAD_REQUEST, "dim must be positive", None));
+    }
+    let metric = Metric::parse(req.metric.as_deref().unwrap_or("l2"))
+        .ok_or_else(|| api_err(StatusCode::BAD_REQUEST, "Unknown metric", Some("expected l2, cosine or dot".into())))?;
+    let mut map = s.collections.write().unwrap();
+    let key = (user.0, req.name);
+    if map.contains_key(&key) {
+        return Err(api_err(StatusCode::CONFLICT, "Collection already exists", Some(key.1)));
+    }
+    let c = Collection::new(req.dim, metric);
+    let info = c.info(&key.1);
+    map.insert(key, Arc::new(RwLock::new(c)));
+    Ok(Json(info))
+}
+
+async fn get_collection(
+    State(s): State<Arc<AppState>>, user: User, Path(name): Path<String>,
+) -> Result<Json<CollectionInfo>, ApiError> {
+    let c = lookup(&s, user, &name)?;
+    let info = c.read().unwrap().info(&name);
+    Ok(Json(info))
+}
+
+async fn drop_collection(
+    State(s): State<Arc<AppState>>, user: User, Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    match s.collections.write().unwrap().remove(&(user.0, name.clone())) {
+        Some(_) => Ok(StatusCode::NO_CONTENT),
+        None => Err(api_err(StatusCode::NOT_FOUND, "Collection not found", Some(name))),
+    }
+}
+
+async fn insert_vectors(
+    State(s): State<Arc<AppState>>, user: User, limits: Limits, Path(name): Path<String>, Json(req): Json<InsertRequest>,
+) -> Result<Json<InsertResponse>, ApiError> {
+    let t = Instant::now();
+    limits.elements("vectors elements", req.vectors.iter().map(Vec::len).sum())?;
+    let c = lookup(&s, user, &name)?;
+    let mut c = c.write().unwrap();
+    if let Some(ids) = &req.ids {
+        if ids.len() != req.vectors.len() {
+            return Err(api_err(StatusCode::BAD_REQUEST, "ids and vectors length mismatch", None));
+        }
+    }
+    if let Some(bad) = req.vectors.iter().position(|v| v.len() != c.store.dim) {
+        return Err(api_err(StatusCode::BAD_REQUEST, "Dimension mismatch",
+            Some(format!("vector {bad} has {} elements, collection dim is {}", req.vectors[bad].len(), c.store.dim))));
+    }
+    // Ids are checked before anything is inserted, so a refused batch
+    // changes nothing; `next_id` is one past the largest id.
+    let ids: Vec<u64> = match req.ids {
+        Some(ids) => ids,
+        None => (0..req.vectors.len() as u64).map(|i| c.next_id.saturating_add(i)).collect(),
+    };
+    if let Some(id) = ids.iter().find(|id| id.checked_add(1).is_none()) {
+        return Err(api_err(StatusCode::BAD_REQUEST, "Invalid vector id", Some(format!("{id}; ids must be below {}", u64::MAX))));
+    }
+    for (id, v) in ids.iter().zip(&req.vectors) { c.upsert(*id, v); }
+    Ok(Json(InsertResponse { ids, count: c.slots.len(), elapsed_ns: t.elapsed().as_nanos() }))
+}
+
+async fn delete_vectors(
+    State(s): State<Arc<AppState>>, user: User, Path(name): Path<String>, Json(req): Json<DeleteRequest>,
+) -> Result<Json<DeleteResponse>, ApiError> {
+    let c = lookup(&s, user, &name)?;
+    let mut c = c.write().unwrap();
+    let deleted = req.ids.iter().filter(|id| c.delete(**id)).count();
+    Ok(Json(DeleteResponse { deleted, count: c.slots.len() }))
+}
+
+async fn build_index(
+    State(s): State<Arc<AppState>>, user: User, Path(name): Path<String>, Json(req): Json<IndexRequest>,
+) -> Result<Json<IndexResponse>, ApiError> {
+    let t = Instant::now();
+    let c = lookup(&s, user, &name)?;
+    let mut c = c.write().unwrap();
+    let index = match req {
+        IndexRequest::Hnsw { m, ef_construction, seed } => AnnIndex::Hnsw(Hnsw::build(
+            &c.store, in_range("m", m.unwrap_or(16), MAX_HNSW_M)?, in_range("ef_construction", ef_construction.unwrap_or(200), MAX_EF)?,
+            seed.unwrap_or(42),
+        )),
+        IndexRequest::IvfPq { nlist, m, nbits, iterations, seed } => {
+            let default_nlist = ((c.slots.len() as f64).sqrt() as usize).max(1);
+            let default_m = [16, 8, 4, 2].into_iter().find(|m| c.store.dim.is_multiple_of(*m)).unwrap_or(1);
+            // nlist is clamped to the collection size and m must divide dim.
+            AnnIndex::IvfPq(IvfPq::build(
+                &c.store, nlist.unwrap_or(default_nlist), m.unwrap_or(default_m),
+                nbits.unwrap_or(8), in_range("iterations", iterations.unwrap_or(20), MAX_KMEANS_ITERATIONS)?, seed.unwrap_or(42),
+            ).map_err(|e| api_err(StatusCode::BAD_REQUEST, "Index build failed", Some(e)))?)
+        }
+    };
+    // A cancelled build stopped part-way; keep whatever index was there.
+    if crate::jobs::cancelled() {
+        return Err(api_err(StatusCode::SERVICE_UNAVAILABLE, "Index build cancelled", None));
+    }
+    let resp = IndexResponse {
+        index: index.kind().into(), params: index.params(), indexed: c.slots.len(),
+        build_ns: t.elapsed().as_nanos(),
+    };
+    c.index = Some(index);
+    Ok(Json(resp))
+}
+
+async fn drop_index(
+    State(s): State<Arc<AppState>>, user: User, Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let c = lookup(&s, user, &name)?;
+    c.write().unwrap().index = None;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn search(
+    State(s): State<Arc<AppState>>, user: User, Path(name): Path<String>, Json(req): Json<SearchRequest>,
+) -> Result<Json<SearchResponse>, ApiError> {
+    let t = Instant::now();
+    let c = lookup(&s, user, &name)?;
+    let c = c.read().unwrap();
+    if req.vector.len() != c.store.dim {
+        return Err(api_err(StatusCode::BAD_REQUEST, "Dimension mismatch",
+            Some(format!("query has {} elements, collection dim is {}", req.vector.len(), c.store.dim))));
+    }
+    // Out-of-range values are refused; in-range ones are then clamped to
+    // the collection, which bounds every buffer the search allocates.
+    let size = c.store.len().max(1);
+    let k = in_range("k", req.k.unwrap_or(10), MAX_K)?.min(size);
+    let ef = in_range("ef", req.ef.unwrap_or(64), MAX_EF)?.min(size);
+    let nprobe = in_range("nprobe", req.nprobe.unwrap_or(8), usize::MAX)?;
+    let rerank = match req.rerank { None | Some(0) => 0, Some(r) => in_range("rerank", r, MAX_RERANK)? };
+    let q = c.store.prepare(&req.vector);
+    let index = c.index.as_ref().filter(|_| !req.exact.unwrap_or(false));
+    let found = match index {
+        Some(idx) => idx.search(&c.store, &q, k, ef, nprobe, rerank),
+        None => c.store.brute_force(&q, k),
+    };
+    let hits = found.into_iter()
+        .map(|(d, slot)| SearchHit { id: c.store.ids[slot as usize], distance: d }).collect();
+    Ok(Json(SearchResponse {
+        hits, index: index.map(|i| i.kind().to_string()), elapsed_ns: t.elapsed().as_nanos(),
+    }))
+}
+
+// ── Snapshot / restore ──────────────────────────────────────
+/// `<dir>/<user>/<name>.json`, or `<dir>/<name>.json` when anonymous.
+fn snapshot_path(user: User, name: &str) -> std::path::PathBuf {
+    let mut dir = std::path::PathBuf::from(std::env::var("SIMD_SNAPSHOT_DIR").unwrap_or_else(|_| "./snapshots".into()));
+    if let Some(id) = user.0 { dir.push(id.to_string()); }
+    dir.join(format!("{name}.json"))
+}
+
+async fn snapshot(
+    State(s): State<Arc<AppState>>, user: User, Path(name): Path<String>,
+) -> Result<Json<SnapshotResponse>, ApiError> {
+    let c = lookup(&s, user, &name)?;
+    let path = snapshot_path(user, &name);
+    let io = |e: std::io::Error| api_err(StatusCode::INTERNAL_SERVER_ERROR, "Snapshot failed", Some(e.to_string()));
+    if let Some(dir) = path.parent() { std::fs::create_dir_all(dir).map_err(io)?; }
+    let (written, count) = {
+        let c = c.read().unwrap();
+        let snap = SnapshotRef { name: &name, store: &c.store, next_id: c.next_id, index: c.index.as_ref() };
+        let written = serde_json::to_vec(&snap)
+            .map_err(|e| api_err(StatusCode::INTERNAL_SERVER_ERROR, "Snapshot failed", Some(e.to_string())))?;
+        (written, c.slots.len())
+    };
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, &written).map_err(io)?;
+    std::fs::rename(&tmp, &path).map_err(io)?;
+    let bytes = written.len() as u64;
+    Ok(Json(SnapshotResponse { name, path: path.display().to_string(), bytes, count }))
+}
+
+async fn restore(
+    State(s): State<Arc<AppState>>, user: User, Path(name): Path<String>,
+) -> Result<Json<CollectionInfo>, ApiError> {
+    if !valid_name(&name) {
+        return Err(api_err(StatusCode::BAD_REQUEST, "Invalid collection name", None));
+    }
+    let path = snapshot_path(user, &name);
+    let raw = std::fs::read(&path)
+        .map_err(|e| api_err(StatusCode::NOT_FOUND, "Snapshot not found", Some(format!("{}: {e}", path.display()))))?;
+    let snap: Snapshot = serde_json::from_slice(&raw)
+        .map_err(|e| api_err(StatusCode::UNPROCESSABLE_ENTITY, "Corrupt snapshot", Some(e.to_string())))?;
+    let c = Collection::from_snapshot(snap);
+    let info = c.info(&name);
+    s.collections.write().unwrap().insert((user.0, name), Arc::new(RwLock::new(c)));
+    Ok(Json(info))
+}
@@ -0,0 +1,153 @@
+// ── Cost model ──────────────────────────────────────────────
+// FLOPs and bytes moved per operation, shared by the compute, matrix,
+// benchmark and roofline reports. Bytes are compulsory traffic (every
+// input read and every output written once per pass), so GB/s is a lower
+// bound on what the memory system actually served. Comparisons (min, max,
+// clamp), square roots and divisions count as one FLOP each.
+use crate::solver::SolveInfo;
+use crate::MatrixRequest;
+
+#[derive(Clone, Copy, Default)]
+pub struct Cost { pub flops: f64, pub bytes: f64 }
+
+impl Cost {
+    fn new(flops: f64, bytes: f64) -> Cost { Cost { flops, bytes } }
+
+    pub fn gflops(&self, ns: u128) -> f64 { if ns > 0 { self.flops / ns as f64 } else { 0.0 } }
+    pub fn gbytes_per_sec(&self, ns: u128) -> f64 { if ns > 0 { self.bytes / ns as f64 } else { 0.0 } }
+}
+
+/// Element-wise and reduction operations over `n` elements of `a` and `m`
+/// of `b`, `elem` bytes each.
+pub fn vector(op: &str, n: usize, m: usize, elem: usize) -> Cost {
+    // (FLOPs per element, passes over a, reads b, writes output)
+    let (per, a_passes, reads_b, writes) = match op {
+        "add" | "mul" | "min" | "max" => (1.0, 1, true, true),
+        "fma" => (2.0, 1, true, true),
+        "dot_product" => (2.0, 1, true, false),
+        // sum of squares, then one divide per element
+        "normalize" => (3.0, 2, false, true),
+        "clamp" => (2.0, 1, false, true),
+        "distance" => (3.0, 1, true, false),
+        "lerp" => (3.0, 1, true, true),
+        _ => (0.0, 1, false, true),
+    };
+    let b = if reads_b { n.min(m) } else { 0 };
+    let elems = a_passes * n + b + if writes { n } else { 0 };
+    Cost::new(per * n as f64 + if op == "normalize" { 1.0 } else { 0.0 }, (elems * elem) as f64)
+}
+
+/// Row-major `m×k · k×n`.
+pub fn gemm(m: usize, n: usize, k: usize) -> Cost {
+    let (m, n, k) = (m as f64, n as f64, k as f64);
+    Cost::new(2.0 * m * n * k, 8.0 * (m * k + k * n + m * n))
+}
+
+/// The benchmarked kernels; matrix operations take `size = n²`.
+pub fn kernel(op: &str, size: usize, elem: usize) -> Cost {
+    let n = size.isqrt();
+    match op {
+        "gemm" => gemm(n, n, n),
+        "transpose" => Cost::new(0.0, (2 * size * elem) as f64),
+        _ => vector(op, size, size, elem),
+    }
+}
+
+/// FLOPs of the engine's determinant: closed forms up to 3×3, LU
+/// elimination above that (one divide and m multiply-subtracts for each of
+/// the m rows below a pivot, then the product of the diagonal; ~2n³/3).
+pub fn determinant_flops(n: usize) -> f64 {
+    match n {
+        0 | 1 => 0.0,
+        2 => 3.0,
+        3 => 14.0,
+        _ => (1..n).map(|m| (m * (2 * m + 1)) as f64).sum::<f64>() + (n - 1) as f64,
+    }
+}
+
+/// CSR storage: values and column indices per non-zero plus the row pointer.
+fn csr_bytes(rows: usize, nnz: usize) -> f64 {
+    (16 * nnz + 8 * (rows + 1)) as f64
+}
+
+/// Per-iteration (sparse mat-vecs, vector ops, preconditioner applications)
+/// of each solver; GMRES orthogonalises against on average half a restart
+/// cycle of basis vectors.
+fn solver_work(method: &str, restart: usize) -> (f64, f64, f64) {
+    match method {
+        "bicgstab" => (2.0, 12.0, 2.0),
+        "gmres" => (1.0, 4.0 + restart as f64, 1.0),
+        _ => (1.0, 5.0, 1.0),
+    }
+}
+
+/// The `matrix` endpoint. Failed operations cost nothing.
+pub fn matrix(req: &MatrixRequest, solve: Option<&SolveInfo>) -> Cost {
+    let a = &req.matrix_a;
+    let (rows, cols) = (a.len(), a.first().map(|r| r.len()).unwrap_or(0));
+    let b = req.matrix_b.as_deref().unwrap_or(&[]);
+    let b_cols = b.first().map(|r| r.len()).unwrap_or(0);
+    let dense = (rows * cols) as f64;
+    let sparse = req.sparse_a.as_ref().map(|s| (s.shape(), s.nnz()));
+    match req.operation.as_str() {
+        "multiply" => gemm(rows, b_cols, cols.min(b.len())),
+        "transpose" => Cost::new(0.0, 16.0 * dense),
+        "add" => Cost::new(dense, 24.0 * dense),
+        "scale" => Cost::new(dense, 16.0 * dense),
+        "determinant" => Cost::new(determinant_flops(rows), 8.0 * dense),
+        "inverse" => {
+            // determinant, n² cofactors, then scale by 1/det
+            let flops = determinant_flops(rows) + dense * (determinant_flops(rows.saturating_sub(1)) + 1.0);
+            Cost::new(flops, 16.0 * dense)
+        }
+        "pairwise_distance" | "pairwise_similarity" => {
+            let other = req.matrix_b.as_ref().filter(|m| *m != a).map(|m| m.len()).unwrap_or(rows);
+            let pairs = if req.condensed.unwrap_or(false) && other == rows { rows * rows.saturating_sub(1) / 2 } else { rows * other };
+            // difference (or product), square and accumulate per component
+            Cost::new(3.0 * (pairs * cols) as f64, 8.0 * ((rows + other) * cols + pairs) as f64)
+        }
+        "spmv" => match sparse {
+            Some(([r, c], nnz)) => Cost::new(2.0 * nnz as f64, csr_bytes(r, nnz) + 8.0 * (r + c) as f64),
+            None => Cost::default(),
+        },
+        "spmm" => match sparse {
+            Some(([r, c], nnz)) => Cost::new(2.0 * (nnz * b_cols) as f64, csr_bytes(r, nnz) + 8.0 * ((r + c) * b_cols) as f64),
+            None => Cost::default(),
+        },
+        "sparse_dot" => match &req.sparse_vector {
+            Some(v) => Cost::new(2.0 * v.data.len() as f64, 24.0 * v.data.len() as f64),
+            None => Cost::default(),
+        },
+        "to_dense" => match sparse {
+            Some(([r, c], nnz)) => Cost::new(0.0, csr_bytes(r, nnz) + 8.0 * (r * c) as f64),
+            None => Cost::default(),
+        },
+        "to_sparse" => {
+            let nnz = a.iter().flatten().filter(|v| **v != 0.0).count();
+            Cost::new(0.0, 8.0 * dense + csr_bytes(rows, nnz))
+        }
+        "convert" => match sparse {
+            Some(([r, _], nnz)) => Cost::new(0.0, 2.0 * csr_bytes(r, nnz)),
+            None => Cost::default(),
+        },
+        "solve" => {
+            let Some(info) = solve else { return Cost::default() };
+            let ([n, _], nnz) = sparse.unwrap_or(([rows, cols], a.iter().flatten().filter(|v| **v != 0.0).count()));
+            let (spmvs, vec_ops, pc_applies) = solver_work(&info.method, req.restart.unwrap_or(30));
+            let pc = match info.preconditioner.as_str() {
+                "jacobi" => Cost::new(n as f64, 24.0 * n as f64),
+                "ilu0" => Cost::new(2.0 * nnz as f64, csr_bytes(n, nnz) + 16.0 * n as f64),
+                _ => Cost::default(),
+            };
+            let (nnz, n) = (nnz as f64, n as f64);
+            // each vector op (dot or axpy) is 2n FLOPs over ~3 vectors
+            let per_it = Cost::new(
+                spmvs * 2.0 * nnz + vec_ops * 2.0 * n + pc_applies * pc.flops,
+                spmvs * (csr_bytes(n as usize, nnz as usize) + 16.0 * n) + vec_ops * 24.0 * n + pc_applies * pc.bytes,
+            );
+            let its = info.iterations as f64;
+            Cost::new(per_it.flops * its, per_it.bytes * its)
+        }
+        _ => Cost::default(),
+    }
+}
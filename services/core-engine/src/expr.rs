@@ -0,0 +1,569 @@
+// ── Expression DSL ──────────────────────────────────────────
+// `clamp(a * b + 0.5 * c, 0, 1)` over named vector/scalar inputs.
+// Source is parsed to an AST, type-checked against the input shapes,
+// constant-folded and lowered to a postfix program. The program runs as
+// one fused loop over BLOCK-sized register tiles, so no full-length
+// intermediate vectors are ever materialised.
+use crate::cost::Cost;
+use crate::limits::Limits;
+use crate::{api_err, ApiError, AppState};
+use crate::telemetry::Json;
+use axum::{extract::State, http::StatusCode, routing::post, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
+
+pub const BLOCK: usize = 64;
+/// Deepest nesting the parser accepts. Every later pass recurses over the
+/// AST, so this keeps a body full of `(` from overflowing the stack.
+pub const MAX_NESTING: usize = 256;
+
+// ── AST ─────────────────────────────────────────────────────
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    Num(f64),
+    Var(String),
+    Neg(Box<Node>),
+    Bin(BinOp, Box<Node>, Box<Node>),
+    Call(Func, Vec<Node>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BinOp { Add, Sub, Mul, Div, Pow }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Func { Abs, Sqrt, Exp, Log, Sin, Cos, Tanh, Floor, Ceil, Min, Max, Clamp, Fma, Lerp }
+
+impl Func {
+    fn lookup(name: &str) -> Option<Self> {
+        Some(match name {
+            "abs" => Func::Abs, "sqrt" => Func::Sqrt, "exp" => Func::Exp, "log" | "ln" => Func::Log,
+            "sin" => Func::Sin, "cos" => Func::Cos, "tanh" => Func::Tanh,
+            "floor" => Func::Floor, "ceil" => Func::Ceil, "min" => Func::Min, "max" => Func::Max,
+            "clamp" => Func::Clamp, "fma" => Func::Fma, "lerp" => Func::Lerp,
+            _ => return None,
+        })
+    }
+    pub fn arity(self) -> usize {
+        match self {
+            Func::Min | Func::Max => 2,
+            Func::Clamp | Func::Fma | Func::Lerp => 3,
+            _ => 1,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Func::Abs => "abs", Func::Sqrt => "sqrt", Func::Exp => "exp", Func::Log => "log",
+            Func::Sin => "sin", Func::Cos => "cos", Func::Tanh => "tanh", Func::Floor => "floor",
+            Func::Ceil => "ceil", Func::Min => "min", Func::Max => "max", Func::Clamp => "clamp",
+            Func::Fma => "fma", Func::Lerp => "lerp",
+        }
+    }
+}
+
+// ── Lexer / parser ──────────────────────────────────────────
+#[derive(Clone, Debug, PartialEq)]
+enum Tok { Num(f64), Ident(String), Op(char), LParen, RParen, Comma }
+
+fn lex(src: &str) -> Result<Vec<(usize, Tok)>, String> {
+    let b = src.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < b.len() {
+        let c = b[i] as char;
+        if c.is_ascii_whitespace() { i += 1; continue; }
+        let start = i;
+        if c.is_ascii_digit() || (c == '.' && b.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            while i < b.len() && (b[i].is_ascii_digit() || b[i] == b'.') { i += 1; }
+            if i < b.len() && (b[i] == b'e' || b[i] == b'E') {
+                i += 1;
+                if i < b.len() && (b[i] == b'+' || b[i] == b'-') { i += 1; }
+                while i < b.len() && b[i].is_ascii_digit() { i += 1; }
+            }
+            let v = src[start..i].parse::<f64>().map_err(|_| format!("bad number '{}' at {start}", &src[start..i]))?;
+            out.push((start, Tok::Num(v)));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while i < b.len() && (b[i].is_ascii_alphanumeric() || b[i] == b'_') { i += 1; }
+            out.push((start, Tok::Ident(src[start..i].to_string())));
+        } else {
+            let t = match c {
+                '+' | '-' | '*' | '/' | '^' => Tok::Op(c),
+                '(' => Tok::LParen, ')' => Tok::RParen, ',' => Tok::Comma,
+                _ => return Err(format!("unexpected character '{c}' at {start}")),
+            };
+            out.push((start, t));
+            i += 1;
+        }
+    }
+    Ok(out)
+}
+
+/// `depth` counts open parentheses, calls, unary operators and chained
+/// binary operators, each of which adds a level to the AST.
+struct Parser { toks: Vec<(usize, Tok)>, pos: usize, len: usize, depth: usize }
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> { self.toks.get(self.pos).map(|t| &t.1) }
+    fn at(&self) -> usize { self.toks.get(self.pos).map(|t| t.0).unwrap_or(self.len) }
+    fn bump(&mut self) -> Option<Tok> { let t = self.toks.get(self.pos).map(|t| t.1.clone()); self.pos += 1; t }
+    fn expect(&mut self, t: Tok, what: &str) -> Result<(), String> {
+        if self.peek() == Some(&t) { self.pos += 1; Ok(()) } else { Err(format!("expected {what} at {}", self.at())) }
+    }
+    fn deeper(&mut self) -> Result<(), String> {
+        if self.depth >= MAX_NESTING { return Err(format!("expression nested deeper than {MAX_NESTING} levels at {}", self.at())); }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn expr(&mut self) -> Result<Node, String> {
+        let (mut lhs, depth) = (self.term()?, self.depth);
+        while let Some(Tok::Op(c @ ('+' | '-'))) = self.peek() {
+            let op = if *c == '+' { BinOp::Add } else { BinOp::Sub };
+            self.pos += 1;
+            self.deeper()?;
+            lhs = Node::Bin(op, Box::new(lhs), Box::new(self.term()?));
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+    fn term(&mut self) -> Result<Node, String> {
+        let (mut lhs, depth) = (self.unary()?, self.depth);
+        while let Some(Tok::Op(c @ ('*' | '/'))) = self.peek() {
+            let op = if *c == '*' { BinOp::Mul } else { BinOp::Div };
+            self.pos += 1;
+            self.deeper()?;
+            lhs = Node::Bin(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+    fn unary(&mut self) -> Result<Node, String> {
+        let sign = match self.peek() {
+            Some(Tok::Op(c @ ('-' | '+'))) => *c,
+            _ => return self.power(),
+        };
+        self.pos += 1;
+        self.deeper()?;
+        let e = self.unary()?;
+        self.depth -= 1;
+        Ok(if sign == '-' { Node::Neg(Box::new(e)) } else { e })
+    }
+    /// `^` is right-associative and binds tighter than unary minus.
+    fn power(&mut self) -> Result<Node, String> {
+        let base = self.atom()?;
+        if let Some(Tok::Op('^')) = self.peek() {
+            self.pos += 1;
+            self.deeper()?;
+            let exp = self.unary()?;
+            self.depth -= 1;
+            return Ok(Node::Bin(BinOp::Pow, Box::new(base), Box::new(exp)));
+        }
+        Ok(base)
+    }
+    fn atom(&mut self) -> Result<Node, String> {
+        let at = self.at();
+        match self.bump() {
+            Some(Tok::Num(v)) => Ok(Node::Num(v)),
+            Some(Tok::LParen) => {
+                self.deeper()?;
+                let e = self.expr()?;
+                self.depth -= 1;
+                self.expect(Tok::RParen, "')'")?;
+                Ok(e)
+            }
+            Some(Tok::Ident(name)) if self.peek() == Some(&Tok::LParen) => {
+                self.pos += 1;
+                self.deeper()?;
+                let f = Func::lookup(&name).ok_or_else(|| format!("unknown function '{name}' at {at}"))?;
+                let mut args = Vec::new();
+                if self.peek() != Some(&Tok::RParen) {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.peek() == Some(&Tok::Comma) { self.pos += 1; } else { break; }
+                    }
+                }
+                self.expect(Tok::RParen, "')'")?;
+                self.depth -= 1;
+                if args.len() != f.arity() {
+                    return Err(format!("{name}() takes {} argument(s), got {} at {at}", f.arity(), args.len()));
+                }
+                Ok(Node::Call(f, args))
+            }
+            Some(Tok::Ident(name)) => Ok(Node::Var(name)),
+            Some(t) => Err(format!("unexpected {t:?} at {at}")),
+            None => Err("unexpected end of expression".into()),
+        }
+    }
+}
+
+pub fn parse(src: &str) -> Result<Node, String> {
+    let toks = lex(src)?;
+    let mut p = Parser { toks, pos: 0, len: src.len(), depth: 0 };
+    let node = p.expr()?;
+    if p.pos < p.toks.len() {
+        return Err(format!("unexpected trailing input at {}", p.at()));
+    }
+    Ok(node)
+}
+
+// ── Type checking / lowering ────────────────────────────────
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dtype { F32, F64 }
+
+impl Dtype {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "f32" => Ok(Dtype::F32),
+            "f64" => Ok(Dtype::F64),
+            other => Err(format!("unsupported dtype '{other}' (expected f32 or f64)")),
+        }
+    }
+    pub fn name(self) -> &'static str { match self { Dtype::F32 => "f32", Dtype::F64 => "f64" } }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Op {
+    Const(f64),
+    /// Index into the vector inputs.
+    Load(usize),
+    Neg,
+    Bin(BinOp),
+    Call(Func),
+}
+
+/// Postfix program plus the shape information needed to run it.
+pub struct Program {
+    pub ops: Vec<Op>,
+    pub inputs: Vec<String>,
+    pub len: usize,
+    pub max_depth: usize,
+    /// Arithmetic ops executed per element (for throughput reporting).
+    pub flops_per_elem: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Input { Scalar(f64), Vector(Vec<f64>) }
+
+fn fold(node: Node) -> Node {
+    match node {
+        Node::Neg(e) => match fold(*e) { Node::Num(v) => Node::Num(-v), e => Node::Neg(Box::new(e)) },
+        Node::Bin(op, l, r) => match (fold(*l), fold(*r)) {
+            (Node::Num(a), Node::Num(b)) => Node::Num(eval_bin(op, a, b)),
+            (l, r) => Node::Bin(op, Box::new(l), Box::new(r)),
+        },
+        Node::Call(f, args) => {
+            let args: Vec<Node> = args.into_iter().map(fold).collect();
+            if let Some(vals) = args.iter().map(|a| if let Node::Num(v) = a { Some(*v) } else { None }).collect::<Option<Vec<_>>>() {
+                return Node::Num(eval_call(f, &vals));
+            }
+            Node::Call(f, args)
+        }
+        n => n,
+    }
+}
+
+fn eval_bin(op: BinOp, a: f64, b: f64) -> f64 {
+    match op { BinOp::Add => a + b, BinOp::Sub => a - b, BinOp::Mul => a * b, BinOp::Div => a / b, BinOp::Pow => a.powf(b) }
+}
+
+fn eval_call(f: Func, v: &[f64]) -> f64 {
+    match f {
+        Func::Abs => v[0].abs(), Func::Sqrt => v[0].sqrt(), Func::Exp => v[0].exp(), Func::Log => v[0].ln(),
+        Func::Sin => v[0].sin(), Func::Cos => v[0].cos(), Func::Tanh => v[0].tanh(),
+        Func::Floor => v[0].floor(), Func::Ceil => v[0].ceil(),
+        Func::Min => v[0].min(v[1]), Func::Max => v[0].max(v[1]),
+        Func::Clamp => v[0].max(v[1]).min(v[2]),
+        Func::Fma => v[0].mul_add(v[1], v[2]),
+        Func::Lerp => v[0] + (v[1] - v[0]) * v[2],
+    }
+}
+
+/// Resolves names against `inputs`, checks that every referenced vector has
+/// the same length, folds scalar subtrees and emits postfix ops.
+pub fn compile(ast: &Node, inputs: &HashMap<String, Input>) -> Result<Program, String> {
+    let mut len: Option<(String, usize)> = None;
+    let mut names: Vec<String> = Vec::new();
+    fn resolve(n: &Node, inputs: &HashMap<String, Input>, len: &mut Option<(String, usize)>, names: &mut Vec<String>) -> Result<Node, String> {
+        Ok(match n {
+            Node::Var(name) => match inputs.get(name) {
+                None => return Err(format!("unknown input '{name}'")),
+                Some(Input::Scalar(v)) => Node::Num(*v),
+                Some(Input::Vector(v)) => {
+                    match len {
+                        Some((other, l)) if *l != v.len() => {
+                            return Err(format!("shape mismatch: '{other}' has {l} elements, '{name}' has {}", v.len()));
+                        }
+                        None => *len = Some((name.clone(), v.len())),
+                        _ => {}
+                    }
+                    if !names.contains(name) { names.push(name.clone()); }
+                    n.clone()
+                }
+            },
+            Node::Num(_) => n.clone(),
+            Node::Neg(e) => Node::Neg(Box::new(resolve(e, inputs, len, names)?)),
+            Node::Bin(op, l, r) => Node::Bin(*op, Box::new(resolve(l, inputs, len, names)?), Box::new(resolve(r, inputs, len, names)?)),
+            Node::Call(f, args) => Node::Call(*f, args.iter().map(|a| resolve(a, inputs, len, names)).collect::<Result<_, _>>()?),
+        })
+    }
+    let resolved = fold(resolve(ast, inputs, &mut len, &mut names)?);
+
+    let mut ops = Vec::new();
+    fn emit(n: &Node, names: &[String], ops: &mut Vec<Op>) {
+        match n {
+            Node::Num(v) => ops.push(Op::Const(*v)),
+            Node::Var(name) => ops.push(Op::Load(names.iter().position(|x| x == name).unwrap_or(0))),
+            Node::Neg(e) => { emit(e, names, ops); ops.push(Op::Neg); }
+            Node::Bin(op, l, r) => { emit(l, names, ops); emit(r, names, ops); ops.push(Op::Bin(*op)); }
+            Node::Call(f, args) => { for a in args { emit(a, names, ops); } ops.push(Op::Call(*f)); }
+        }
+    }
+    emit(&resolved, &names, &mut ops);
+
+    let (mut depth, mut max_depth, mut flops) = (0usize, 0usize, 0usize);
+    for op in &ops {
+        match op {
+            Op::Const(_) | Op::Load(_) => depth += 1,
+            Op::Neg => flops += 1,
+            Op::Bin(_) => { depth -= 1; flops += 1; }
+            Op::Call(f) => { depth -= f.arity() - 1; flops += if *f == Func::Fma || *f == Func::Lerp { 2 } else { 1 }; }
+        }
+        max_depth = max_depth.max(depth);
+    }
+    Ok(Program { ops, inputs: names, len: len.map(|l| l.1).unwrap_or(0), max_depth, flops_per_elem: flops })
+}
+
+// ── Fused interpreter ───────────────────────────────────────
+pub trait Real: Copy + Default + PartialOrd
+    + std::ops::Add<Output = Self> + std::ops::Sub<Output = Self>
+    + std::ops::Mul<Output = Self> + std::ops::Div<Output = Self> + std::ops::Neg<Output = Self>
+{
+    fn from_f64(v: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn call(f: Func, a: Self, b: Self, c: Self) -> Self;
+    fn powf(self, e: Self) -> Self;
+}
+
+macro_rules! impl_real {
+    ($t:ty) => {
+        impl Real for $t {
+            #[inline] fn from_f64(v: f64) -> Self { v as $t }
+            #[inline] fn to_f64(self) -> f64 { self as f64 }
+            #[inline] fn powf(self, e: Self) -> Self { <$t>::powf(self, e) }
+            #[inline]
+            fn call(f: Func, a: Self, b: Self, c: Self) -> Self {
+                match f {
+                    Func::Abs => a.abs(), Func::Sqrt => a.sqrt(), Func::Exp => a.exp(), Func::Log => a.ln(),
+                    Func::Sin => a.sin(), Func::Cos => a.cos(), Func::Tanh => a.tanh(),
+                    Func::Floor => a.floor(), Func::Ceil => a.ceil(),
+                    Func::Min => a.min(b), Func::Max => a.max(b), Func::Clamp => a.max(b).min(c),
+                    Func::Fma => a.mul_add(b, c), Func::Lerp => a + (b - a) * c,
+                }
+            }
+        }
+    };
+}
+impl_real!(f32);
+impl_real!(f64);
+
+type Tile<T> = [T; BLOCK];
+
+#[inline]
+fn top2<T>(stack: &mut [Tile<T>], sp: usize) -> (&mut Tile<T>, &Tile<T>) {
+    let (lo, hi) = stack.split_at_mut(sp - 1);
+    (&mut lo[sp - 2], &hi[0])
+}
+
+/// Evaluates `prog` over `inputs` (ordered as `prog.inputs`).
+pub fn run<T: Real>(prog: &Program, inputs: &[&[T]]) -> Vec<T> {
+    let n = prog.len;
+    let mut out = vec![T::default(); n];
+    let mut stack: Vec<Tile<T>> = vec![[T::default(); BLOCK]; prog.max_depth.max(1)];
+    let consts: Vec<T> = prog.ops.iter().map(|op| if let Op::Const(c) = op { T::from_f64(*c) } else { T::default() }).collect();
+    for start in (0..n).step_by(BLOCK) {
+        let len = BLOCK.min(n - start);
+        let mut sp = 0;
+        for (pc, op) in prog.ops.iter().enumerate() {
+            match op {
+                Op::Const(_) => { stack[sp] = [consts[pc]; BLOCK]; sp += 1; }
+                Op::Load(i) => { stack[sp][..len].copy_from_slice(&inputs[*i][start..start + len]); sp += 1; }
+                Op::Neg => { for v in stack[sp - 1].iter_mut() { *v = -*v; } }
+                Op::Bin(b) => {
+                    let (l, r) = top2(&mut stack, sp);
+                    match b {
+                        BinOp::Add => for k in 0..BLOCK { l[k] = l[k] + r[k]; },
+                        BinOp::Sub => for k in 0..BLOCK { l[k] = l[k] - r[k]; },
+                        BinOp::Mul => for k in 0..BLOCK { l[k] = l[k] * r[k]; },
+                        BinOp::Div => for k in 0..BLOCK { l[k] = l[k] / r[k]; },
+                        BinOp::Pow => for k in 0..BLOCK { l[k] = l[k].powf(r[k]); },
+                    }
+                    sp -= 1;
+                }
+                Op::Call(f) => {
+                    let arity = f.arity();
+                    let base = sp - arity;
+                    let (lo, hi) = stack.split_at_mut(base + 1);
+                    let a = &mut lo[base];
+                    let zero = [T::default(); BLOCK];
+                    let b = if arity > 1 { &hi[0] } else { &zero };
+                    let c = if arity > 2 { &hi[1] } else { &zero };
+                    for k in 0..BLOCK { a[k] = T::call(*f, a[k], b[k], c[k]); }
+                    sp = base + 1;
+                }
+            }
+        }
+        out[start..start + len].copy_from_slice(&stack[0][..len]);
+    }
+    out
+}
+
+// ── Handler ─────────────────────────────────────────────────
+#[derive(Deserialize)]
+pub(crate) struct EvalRequest { expression: String, inputs: HashMap<String, Input>, dtype: Option<String>, jit: Option<bool> }
+
+#[derive(Serialize)]
+pub(crate) struct EvalResponse {
+    expression: String, result: serde_json::Value, scalar_result: Option<f64>, dtype: String,
+    input_size: usize, fused_ops: usize, backend: String,
+    #[serde(skip_serializing_if = "Option::is_none")] jit_cache_hit: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")] jit_error: Option<String>,
+    compile_ns: u128, run_ns: u128, elapsed_ns: u128, throughput_gflops: f64,
+}
+
+/// Inputs at least this long are JIT-compiled when the request leaves `jit` unset.
+pub const JIT_MIN_ELEMENTS: usize = 16 * 1024;
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/v1/simd/eval", post(eval))
+}
+
+/// Parses, checks and lowers an eval request; shared with the JIT path.
+pub fn prepare(expression: &str, inputs: &HashMap<String, Input>, dtype: Option<&str>) -> Result<(Program, Dtype), ApiError> {
+    let bad = |e: String| api_err(StatusCode::BAD_REQUEST, "Invalid expression", Some(e));
+    let dtype = Dtype::parse(dtype.unwrap_or("f64")).map_err(bad)?;
+    let ast = parse(expression).map_err(bad)?;
+    let prog = compile(&ast, inputs).map_err(bad)?;
+    Ok((prog, dtype))
+}
+
+/// Runs `prog` in the requested precision and returns the f64 result.
+pub fn execute(prog: &Program, dtype: Dtype, inputs: &HashMap<String, Input>) -> Vec<f64> {
+    let vecs: Vec<&[f64]> = prog.inputs.iter().map(|n| match &inputs[n] {
+        Input::Vector(v) => v.as_slice(),
+        Input::Scalar(_) => &[],
+    }).collect();
+    match dtype {
+        Dtype::F64 => run::<f64>(prog, &vecs),
+        Dtype::F32 => {
+            let conv: Vec<Vec<f32>> = vecs.iter().map(|v| v.iter().map(|x| *x as f32).collect()).collect();
+            let refs: Vec<&[f32]> = conv.iter().map(|v| v.as_slice()).collect();
+            run::<f32>(prog, &refs).into_iter().map(|x| x as f64).collect()
+        }
+    }
+}
+
+/// Backend outcome for one evaluation.
+struct Run { out: Vec<f64>, backend: &'static str, cache_hit: Option<bool>, jit_error: Option<String>, compile_ns: u128, run_ns: u128 }
+
+#[cfg(feature = "jit")]
+fn execute_jit(s: &AppState, prog: &Program, dtype: Dtype, inputs: &HashMap<String, Input>) -> Run {
+    let t = Instant::now();
+    let (kernel, hit) = match s.jit.get_or_compile(prog, dtype) {
+        Ok(k) => k,
+        Err(e) => {
+            let mut r = execute_interpreted(prog, dtype, inputs);
+            r.jit_error = Some(e);
+            return r;
+        }
+    };
+    let compile_ns = t.elapsed().as_nanos();
+    let t = Instant::now();
+    let vecs: Vec<&[f64]> = prog.inputs.iter().map(|n| match &inputs[n] {
+        Input::Vector(v) => v.as_slice(),
+        Input::Scalar(_) => &[],
+    }).collect();
+    let out = match dtype {
+        Dtype::F64 => kernel.run_f64(&vecs, prog.len),
+        Dtype::F32 => {
+            let conv: Vec<Vec<f32>> = vecs.iter().map(|v| v.iter().map(|x| *x as f32).collect()).collect();
+            let refs: Vec<&[f32]> = conv.iter().map(|v| v.as_slice()).collect();
+            kernel.run_f32(&refs, prog.len).into_iter().map(|x| x as f64).collect()
+        }
+    };
+    Run { out, backend: "jit", cache_hit: Some(hit), jit_error: None, compile_ns, run_ns: t.elapsed().as_nanos() }
+}
+
+fn execute_interpreted(prog: &Program, dtype: Dtype, inputs: &HashMap<String, Input>) -> Run {
+    let t = Instant::now();
+    let out = execute(prog, dtype, inputs);
+    Run { out, backend: "interpreter", cache_hit: None, jit_error: None, compile_ns: 0, run_ns: t.elapsed().as_nanos() }
+}
+
+pub(crate) async fn eval(State(s): State<Arc<AppState>>, limits: Limits, Json(req): Json<EvalRequest>) -> Result<Json<EvalResponse>, ApiError> {
+    let call = s.metrics.call("eval", "eval");
+    let t = Instant::now();
+    let (prog, dtype) = tracing::info_span!("validate").in_scope(|| {
+        let inputs = req.inputs.values().map(|i| match i { Input::Vector(v) => v.len(), Input::Scalar(_) => 0 }).sum();
+        limits.elements("input elements", inputs)?;
+        prepare(&req.expression, &req.inputs, req.dtype.as_deref())
+    })?;
+    let kernel = tracing::info_span!("kernel", elements = prog.len).entered();
+    let want_jit = req.jit.unwrap_or(prog.len >= JIT_MIN_ELEMENTS);
+    let (result, scalar_result, run) = match (prog.inputs.is_empty(), prog.ops.first()) {
+        (true, Some(Op::Const(v))) => (serde_json::Value::Null, Some(*v), execute_interpreted(&prog, dtype, &req.inputs)),
+        _ => {
+            #[cfg(feature = "jit")]
+            let run = if want_jit { execute_jit(&s, &prog, dtype, &req.inputs) } else { execute_interpreted(&prog, dtype, &req.inputs) };
+            #[cfg(not(feature = "jit"))]
+            let run = {
+                let mut r = execute_interpreted(&prog, dtype, &req.inputs);
+                if want_jit && req.jit == Some(true) { r.jit_error = Some("engine built without the `jit` feature".into()); }
+                r
+            };
+            let json = serde_json::Value::Array(run.out.iter().map(|v| serde_json::Value::from(*v)).collect());
+            (json, None, run)
+        }
+    };
+    drop(kernel);
+    let elapsed_ns = t.elapsed().as_nanos();
+    let flops = (prog.len * prog.flops_per_elem).max(1) as f64;
+    call.finish(true, prog.len, Cost { flops, bytes: 0.0 });
+    s.stats.total_computes.fetch_add(1, Ordering::Relaxed);
+    Ok(Json(EvalResponse {
+        expression: req.expression, result, scalar_result, dtype: dtype.name().into(),
+        input_size: prog.len, fused_ops: prog.ops.len(), backend: run.backend.into(),
+        jit_cache_hit: run.cache_hit, jit_error: run.jit_error,
+        compile_ns: run.compile_ns, run_ns: run.run_ns, elapsed_ns,
+        throughput_gflops: if run.run_ns > 0 { flops / run.run_ns as f64 } else { 0.0 },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_precedence() {
+        let ast = parse("-a ^ 2 + b * c").unwrap();
+        let var = |n: &str| Box::new(Node::Var(n.into()));
+        let pow = Node::Bin(BinOp::Pow, var("a"), Box::new(Node::Num(2.0)));
+        let mul = Node::Bin(BinOp::Mul, var("b"), var("c"));
+        assert_eq!(ast, Node::Bin(BinOp::Add, Box::new(Node::Neg(Box::new(pow))), Box::new(mul)));
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let ok = format!("{}a{}", "(".repeat(MAX_NESTING), ")".repeat(MAX_NESTING));
+        assert!(parse(&ok).is_ok());
+        let deep = format!("{}a{}", "(".repeat(MAX_NESTING + 1), ")".repeat(MAX_NESTING + 1));
+        assert!(parse(&deep).unwrap_err().contains("nested deeper"));
+        // Far past the limit: must fail cleanly, not overflow the stack.
+        assert!(parse(&"(".repeat(1 << 20)).is_err());
+        assert!(parse(&"-".repeat(1 << 20)).is_err());
+        assert!(parse(&vec!["a"; 10_000].join("+")).is_err());
+        assert!(parse(&format!("{}a", "abs(".repeat(MAX_NESTING + 1))).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+// ── Geometry kernels ────────────────────────────────────────
+// Vector, AABB, ray and plane primitives over the batch module's SoA
+// buffers. Tests are branch-free per lane: hit flags come back as 0/1
+// components next to the distances, so a ray can be broadcast against a
+// whole triangle or box buffer in one pass.
+use crate::batch::{batch_len, component_error, kernel, normalize, run, Lane, Output, Soa};
+
+const EPS: f64 = 1e-12;
+
+#[inline(always)]
+fn v3<T: Lane>(x: &[T]) -> [T; 3] { [x[0], x[1], x[2]] }
+
+#[inline(always)]
+fn sub3<T: Lane>(a: [T; 3], b: [T; 3]) -> [T; 3] { [a[0] - b[0], a[1] - b[1], a[2] - b[2]] }
+
+#[inline(always)]
+fn dot3<T: Lane>(a: [T; 3], b: [T; 3]) -> T { a[0] * b[0] + a[1] * b[1] + a[2] * b[2] }
+
+#[inline(always)]
+fn cross3<T: Lane>(a: [T; 3], b: [T; 3]) -> [T; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+// ── Vectors ─────────────────────────────────────────────────
+#[inline(always)]
+fn cross<T: Lane>(x: &[T]) -> [T; 3] { cross3(v3(x), v3(&x[3..])) }
+
+#[inline(always)]
+fn dot<T: Lane, const D: usize>(x: &[T]) -> [T; 1] {
+    [(1..D).fold(x[0] * x[D], |acc, i| acc + x[i] * x[D + i])]
+}
+
+#[inline(always)]
+fn length<T: Lane, const D: usize>(x: &[T]) -> [T; 1] {
+    [(1..D).fold(x[0] * x[0], |acc, i| acc + x[i] * x[i]).sqrt()]
+}
+
+#[inline(always)]
+fn normalized<T: Lane, const D: usize>(x: &[T]) -> [T; D] {
+    normalize(std::array::from_fn(|i| x[i]))
+}
+
+// ── AABBs ([min x, y, z, max x, y, z]) ──────────────────────
+#[inline(always)]
+fn aabb_union<T: Lane>(x: &[T]) -> [T; 6] {
+    std::array::from_fn(|i| if i < 3 { x[i].min(x[6 + i]) } else { x[i].max(x[6 + i]) })
+}
+
+/// Disjoint boxes yield an inverted box (some min > max).
+#[inline(always)]
+fn aabb_intersection<T: Lane>(x: &[T]) -> [T; 6] {
+    std::array::from_fn(|i| if i < 3 { x[i].max(x[6 + i]) } else { x[i].min(x[6 + i]) })
+}
+
+/// Slab test for rays `[origin, direction]` against boxes: `[hit, t]`,
+/// where `t` is the entry distance (0 when the origin is inside, -1 on miss).
+#[inline(always)]
+fn ray_aabb<T: Lane>(x: &[T]) -> [T; 2] {
+    let (o, d, lo, hi) = (v3(x), v3(&x[3..]), v3(&x[6..]), v3(&x[9..]));
+    let mut near = T::splat(0.0);
+    let mut far = T::splat(f64::INFINITY);
+    for i in 0..3 {
+        let inv = d[i].map(|v| 1.0 / v);
+        let (t1, t2) = ((lo[i] - o[i]) * inv, (hi[i] - o[i]) * inv);
+        near = near.max(t1.min(t2));
+        far = far.min(t1.max(t2));
+    }
+    let hit = near.zip(far, |n, f| if n <= f { 1.0 } else { 0.0 });
+    [hit, near.zip(hit, |n, h| if h > 0.0 { n } else { -1.0 })]
+}
+
+// ── Triangles ([v0, v1, v2]) ────────────────────────────────
+/// Möller–Trumbore, double-sided: `[hit, t, u, v]` with barycentrics of the
+/// hit point (`t` = -1 on miss).
+#[inline(always)]
+fn ray_triangle<T: Lane>(x: &[T]) -> [T; 4] {
+    let (o, d) = (v3(x), v3(&x[3..]));
+    let (v0, v1, v2) = (v3(&x[6..]), v3(&x[9..]), v3(&x[12..]));
+    let (e1, e2) = (sub3(v1, v0), sub3(v2, v0));
+    let p = cross3(d, e2);
+    let det = dot3(e1, p);
+    let inv = det.map(|d| if d.abs() < EPS { 0.0 } else { 1.0 / d });
+    let s = sub3(o, v0);
+    let u = dot3(s, p) * inv;
+    let q = cross3(s, e1);
+    let v = dot3(d, q) * inv;
+    let t = dot3(e2, q) * inv;
+    let inside = u.min(v).min(T::splat(1.0) - u - v);
+    let hit = inside.zip(t, |m, t| if m >= 0.0 && t > EPS { 1.0 } else { 0.0 }) * det.map(|d| if d.abs() < EPS { 0.0 } else { 1.0 });
+    let miss = |val: T, fill: f64| val.zip(hit, move |v, h| if h > 0.0 { v } else { fill });
+    [hit, miss(t, -1.0), miss(u, 0.0), miss(v, 0.0)]
+}
+
+// ── Planes ([nx, ny, nz, d], n·p + d = 0) ───────────────────
+#[inline(always)]
+fn point_plane<T: Lane>(x: &[T]) -> [T; 1] {
+    let (p, n, d) = (v3(x), v3(&x[3..]), x[6]);
+    let len = dot3(n, n).sqrt().map(|l| if l > EPS { 1.0 / l } else { 0.0 });
+    [(dot3(p, n) + d) * len]
+}
+
+// ── Dispatch ────────────────────────────────────────────────
+pub const OPERATIONS: &[&str] = &[
+    "cross", "dot", "length", "normalize", "aabb_union", "aabb_intersection", "ray_aabb", "ray_triangle",
+    "point_plane_distance",
+];
+
+/// Returns `Ok(None)` for an unknown operation name.
+pub fn dispatch(op: &str, a: &Soa, b: Option<&Soa>) -> Result<Option<Output>, String> {
+    let out = |result: Soa, flops_per_item: usize| Ok(Some(Output { result, singular: None, flops_per_item }));
+    let pair = || -> Result<(&Soa, usize), String> {
+        let b = b.ok_or("requires `b`")?;
+        Ok((b, batch_len(&[a, b])?))
+    };
+    match (op, a.comps, b.map(|b| b.comps)) {
+        ("cross", 3, Some(3)) => { let (b, n) = pair()?; out(run(&[a, b], n, kernel!(cross)), 9) }
+        ("dot", 3, Some(3)) => { let (b, n) = pair()?; out(run(&[a, b], n, kernel!(dot, 3)), 5) }
+        ("dot", 4, Some(4)) => { let (b, n) = pair()?; out(run(&[a, b], n, kernel!(dot, 4)), 7) }
+        ("length", 3, _) => out(run(&[a], a.n, kernel!(length, 3)), 6),
+        ("length", 4, _) => out(run(&[a], a.n, kernel!(length, 4)), 8),
+        ("normalize", 3, _) => out(run(&[a], a.n, kernel!(normalized, 3)), 9),
+        ("normalize", 4, _) => out(run(&[a], a.n, kernel!(normalized, 4)), 12),
+        ("aabb_union", 6, Some(6)) => { let (b, n) = pair()?; out(run(&[a, b], n, kernel!(aabb_union)), 6) }
+        ("aabb_intersection", 6, Some(6)) => { let (b, n) = pair()?; out(run(&[a, b], n, kernel!(aabb_intersection)), 6) }
+        ("ray_aabb", 6, Some(6)) => { let (b, n) = pair()?; out(run(&[a, b], n, kernel!(ray_aabb)), 24) }
+        ("ray_triangle", 6, Some(9)) => { let (b, n) = pair()?; out(run(&[a, b], n, kernel!(ray_triangle)), 50) }
+        ("point_plane_distance", 3, Some(4)) => { let (b, n) = pair()?; out(run(&[a, b], n, kernel!(point_plane)), 12) }
+        _ if OPERATIONS.contains(&op) => Err(component_error(a, b)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::{Layout, W};
+
+    /// Checks each `(a, b, expected)` case through the scalar kernel, then
+    /// all of them cycled across `W + 3` items through `dispatch`, so every
+    /// case lands in both a SIMD lane and the scalar tail.
+    fn check<const O: usize>(op: &str, kernel: fn(&[f64]) -> [f64; O], cases: &[(&[f64], &[f64], [f64; O])]) {
+        for (i, (a, b, want)) in cases.iter().enumerate() {
+            assert_eq!(kernel(&[*a, *b].concat()), *want, "{op} case {i}");
+        }
+        let n = W + 3;
+        let case = |i: usize| &cases[i % cases.len()];
+        let a = Soa::parse((0..n).map(|i| case(i).0.to_vec()).collect(), Layout::Aos, "a").unwrap();
+        let b = Soa::parse((0..n).map(|i| case(i).1.to_vec()).collect(), Layout::Aos, "b").unwrap();
+        let r = dispatch(op, &a, Some(&b)).unwrap().unwrap().result;
+        for i in 0..n {
+            let got: Vec<f64> = (0..O).map(|k| r.data[k * n + i]).collect();
+            assert_eq!(got, case(i).2, "{op} item {i}");
+        }
+    }
+
+    #[test]
+    fn ray_aabb_slab_test() {
+        let unit = [0.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+        check("ray_aabb", ray_aabb::<f64>, &[
+            (&[-1.0, 0.5, 0.5, 1.0, 0.0, 0.0], &unit, [1.0, 1.0]),
+            // Misses beside the box, behind the origin, and parallel outside a slab.
+            (&[-1.0, 2.0, 0.5, 1.0, 0.0, 0.0], &unit, [0.0, -1.0]),
+            (&[2.0, 0.5, 0.5, 1.0, 0.0, 0.0], &unit, [0.0, -1.0]),
+            (&[0.5, 0.5, 2.0, 1.0, 1.0, 0.0], &unit, [0.0, -1.0]),
+            // Origin inside: entry distance 0.
+            (&[0.5, 0.5, 0.5, 0.0, -1.0, 0.0], &unit, [1.0, 0.0]),
+            // Diagonal that only touches the (1, 1) edge at t = 1.
+            (&[0.0, 2.0, 0.5, 1.0, -1.0, 0.0], &unit, [1.0, 1.0]),
+            // The same diagonal shifted past the edge.
+            (&[0.0, 2.5, 0.5, 1.0, -1.0, 0.0], &unit, [0.0, -1.0]),
+            // Negative direction entering through the max face.
+            (&[0.5, 0.5, 3.0, 0.0, 0.0, -2.0], &unit, [1.0, 1.0]),
+        ]);
+    }
+
+    #[test]
+    fn ray_triangle_moller_trumbore() {
+        let tri = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        check("ray_triangle", ray_triangle::<f64>, &[
+            (&[0.25, 0.25, 1.0, 0.0, 0.0, -1.0], &tri, [1.0, 1.0, 0.25, 0.25]),
+            // Double-sided: hits from below too.
+            (&[0.25, 0.5, -2.0, 0.0, 0.0, 1.0], &tri, [1.0, 2.0, 0.25, 0.5]),
+            // Outside the hypotenuse, and the plane behind the origin.
+            (&[0.75, 0.75, 1.0, 0.0, 0.0, -1.0], &tri, [0.0, -1.0, 0.0, 0.0]),
+            (&[0.25, 0.25, 1.0, 0.0, 0.0, 1.0], &tri, [0.0, -1.0, 0.0, 0.0]),
+            // Grazing the hypotenuse and the v0 corner still hits.
+            (&[0.5, 0.5, 1.0, 0.0, 0.0, -1.0], &tri, [1.0, 1.0, 0.5, 0.5]),
+            (&[0.0, 0.0, 1.0, 0.0, 0.0, -1.0], &tri, [1.0, 1.0, 0.0, 0.0]),
+            // Parallel above the plane and within it: det = 0, no hit.
+            (&[0.0, 0.0, 1.0, 1.0, 0.0, 0.0], &tri, [0.0, -1.0, 0.0, 0.0]),
+            (&[-1.0, 0.25, 0.0, 1.0, 0.0, 0.0], &tri, [0.0, -1.0, 0.0, 0.0]),
+        ]);
+    }
+
+    #[test]
+    fn point_plane_signed_distance() {
+        // 2z - 4 = 0, i.e. z = 2 with an unnormalised normal.
+        let plane = [0.0, 0.0, 2.0, -4.0];
+        check("point_plane_distance", point_plane::<f64>, &[
+            (&[1.0, 1.0, 5.0], &plane, [3.0]),
+            (&[0.0, 0.0, 0.0], &plane, [-2.0]),
+            (&[7.0, -3.0, 2.0], &plane, [0.0]),
+            (&[3.0, 4.0, 0.0], &[3.0, 4.0, 0.0, 5.0], [6.0]),
+            // A zero normal is degenerate and reports 0.
+            (&[1.0, 2.0, 3.0], &[0.0, 0.0, 0.0, 1.0], [0.0]),
+        ]);
+    }
+}
@@ -0,0 +1,240 @@
+// ── History ─────────────────────────────────────────────────
+// Optional Postgres persistence, enabled by SIMD_DATABASE_URL. Compute and
+// matrix calls are recorded in `compute_jobs`, benchmark runs in
+// `compute_benchmarks` (database/migrations/006_domain.sql). Writes are
+// fire-and-forget so a slow or unreachable database never delays a
+// response. Callers are identified by `X-User-Id`, which the API gateway
+// sets from the verified token; anonymous calls are stored without a user.
+use crate::{api_err, ApiError, AppState};
+use axum::{
+    extract::{FromRequestParts, Query, State},
+    http::{request::Parts, StatusCode},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio_postgres::{Client, NoTls, Row};
+use uuid::Uuid;
+
+/// The calling user, from the `X-User-Id` header. Anything that is not a
+/// UUID (e.g. API-key callers) counts as anonymous.
+#[derive(Clone, Copy, Default)]
+pub struct User(pub Option<Uuid>);
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for User {
+    type Rejection = std::convert::Infallible;
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let id = parts.headers.get("x-user-id").and_then(|v| v.to_str().ok()).and_then(|v| Uuid::parse_str(v.trim()).ok());
+        Ok(User(id))
+    }
+}
+
+pub struct History {
+    url: Option<String>,
+    client: tokio::sync::Mutex<Option<Arc<Client>>>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        let url = std::env::var("SIMD_DATABASE_URL").ok().filter(|u| !u.trim().is_empty());
+        History { url, client: Default::default() }
+    }
+}
+
+/// One row of `compute_jobs`.
+pub struct JobRecord {
+    pub user: User,
+    pub operation: String,
+    pub input_dimensions: i32,
+    pub element_count: usize,
+    pub compute_time_ns: u128,
+    pub throughput_gflops: Option<f64>,
+    pub status: &'static str,
+    pub results: serde_json::Value,
+}
+
+/// One row of `compute_benchmarks`.
+pub struct BenchmarkRecord {
+    pub user: User,
+    pub name: String,
+    pub operations: serde_json::Value,
+    pub cpu_features: serde_json::Value,
+    pub scalar_time_ns: Option<u128>,
+    pub simd_time_ns: Option<u128>,
+    pub speedup_ratio: Option<f64>,
+    pub peak_gflops: Option<f64>,
+}
+
+fn micros(ns: u128) -> i64 { (ns / 1000).min(i64::MAX as u128) as i64 }
+
+/// `compute_jobs.operation` for a compute-endpoint operation.
+pub fn compute_operation(op: &str) -> String {
+    match op {
+        "add" | "mul" | "fma" | "min" | "max" => format!("vector_{op}"),
+        _ => op.to_string(),
+    }
+}
+
+impl History {
+    pub fn enabled(&self) -> bool { self.url.is_some() }
+
+    /// The shared connection, reconnecting if the previous one dropped.
+    async fn client(&self) -> Result<Arc<Client>, String> {
+        let url = self.url.as_deref().ok_or("persistence disabled (SIMD_DATABASE_URL not set)")?;
+        let mut slot = self.client.lock().await;
+        if let Some(c) = slot.as_ref().filter(|c| !c.is_closed()) {
+            return Ok(c.clone());
+        }
+        let (client, conn) = tokio_postgres::connect(url, NoTls).await.map_err(|e| e.to_string())?;
+        tokio::spawn(async move {
+            if let Err(e) = conn.await { tracing::warn!("history connection closed: {e}"); }
+        });
+        let client = Arc::new(client);
+        *slot = Some(client.clone());
+        Ok(client)
+    }
+
+    async fn insert_job(&self, r: &JobRecord) -> Result<(), String> {
+        let c = self.client().await?;
+        c.execute(
+            "insert into public.compute_jobs (user_id, operation, input_dimensions, element_count, precision, \
+             simd_backend, compute_time_us, throughput_gflops, status, results) \
+             values ($1, $2, $3, $4, 'f64', $5, $6, $7, $8, $9)",
+            &[&r.user.0, &r.operation, &r.input_dimensions, &(r.element_count as i64), &crate::simd::backend(),
+              &micros(r.compute_time_ns), &r.throughput_gflops, &r.status, &r.results],
+        ).await.map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    async fn insert_benchmark(&self, r: &BenchmarkRecord) -> Result<(), String> {
+        let c = self.client().await?;
+        c.execute(
+            "insert into public.compute_benchmarks (user_id, name, operations, cpu_features, scalar_time_us, \
+             simd_time_us, speedup_ratio, peak_gflops) values ($1, $2, $3, $4, $5, $6, $7, $8)",
+            &[&r.user.0, &r.name, &r.operations, &r.cpu_features, &r.scalar_time_ns.map(micros),
+              &r.simd_time_ns.map(micros), &r.speedup_ratio, &r.peak_gflops],
+        ).await.map(|_| ()).map_err(|e| e.to_string())
+    }
+}
+
+/// Queues a `compute_jobs` insert; a no-op when persistence is disabled.
+pub fn record_job(s: &Arc<AppState>, r: JobRecord) {
+    if !s.history.enabled() { return; }
+    let s = s.clone();
+    tokio::spawn(async move {
+        if let Err(e) = s.history.insert_job(&r).await { tracing::warn!("history: dropped {} record: {e}", r.operation); }
+    });
+}
+
+/// Queues a `compute_benchmarks` insert; a no-op when persistence is disabled.
+pub fn record_benchmark(s: &Arc<AppState>, r: BenchmarkRecord) {
+    if !s.history.enabled() { return; }
+    let s = s.clone();
+    tokio::spawn(async move {
+        if let Err(e) = s.history.insert_benchmark(&r).await { tracing::warn!("history: dropped benchmark record: {e}"); }
+    });
+}
+
+// ── API ─────────────────────────────────────────────────────
+#[derive(Deserialize)]
+struct JobQuery {
+    operation: Option<String>,
+    status: Option<String>,
+    since_ms: Option<i64>,
+    until_ms: Option<i64>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct BenchmarkQuery {
+    name: Option<String>,
+    since_ms: Option<i64>,
+    until_ms: Option<i64>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct JobRow {
+    id: String, operation: String, input_dimensions: i32, element_count: i64,
+    precision: Option<String>, simd_backend: Option<String>,
+    compute_time_us: Option<i64>, throughput_gflops: Option<f64>,
+    status: Option<String>, results: Option<serde_json::Value>, created_at_ms: i64,
+}
+
+#[derive(Serialize)]
+struct BenchmarkRow {
+    id: String, name: String, operations: serde_json::Value, cpu_features: Option<serde_json::Value>,
+    scalar_time_us: Option<i64>, simd_time_us: Option<i64>,
+    speedup_ratio: Option<f64>, peak_gflops: Option<f64>, created_at_ms: i64,
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/v1/simd/history/jobs", get(list_jobs))
+        .route("/api/v1/simd/history/benchmarks", get(list_benchmarks))
+}
+
+async fn context(s: &AppState, user: User) -> Result<(Arc<Client>, Uuid), ApiError> {
+    if !s.history.enabled() {
+        return Err(api_err(StatusCode::SERVICE_UNAVAILABLE, "History disabled", Some("set SIMD_DATABASE_URL".into())));
+    }
+    let user = user.0.ok_or_else(|| api_err(StatusCode::UNAUTHORIZED, "User required", Some("X-User-Id header".into())))?;
+    let client = s.history.client().await.map_err(|e| api_err(StatusCode::SERVICE_UNAVAILABLE, "History unavailable", Some(e)))?;
+    Ok((client, user))
+}
+
+fn page(limit: Option<i64>, offset: Option<i64>) -> (i64, i64) {
+    (limit.unwrap_or(50).clamp(1, 500), offset.unwrap_or(0).max(0))
+}
+
+fn query_err(e: tokio_postgres::Error) -> ApiError {
+    api_err(StatusCode::INTERNAL_SERVER_ERROR, "History query failed", Some(e.to_string()))
+}
+
+async fn list_jobs(State(s): State<Arc<AppState>>, user: User, Query(q): Query<JobQuery>) -> Result<Json<Vec<JobRow>>, ApiError> {
+    let (client, user) = context(&s, user).await?;
+    let (limit, offset) = page(q.limit, q.offset);
+    let rows = client.query(
+        "select id, operation, input_dimensions, element_count, precision, simd_backend, compute_time_us, \
+         throughput_gflops, status, results, (extract(epoch from created_at) * 1000)::bigint \
+         from public.compute_jobs \
+         where user_id = $1 and ($2::text is null or operation = $2) and ($3::text is null or status = $3) \
+         and ($4::bigint is null or created_at >= to_timestamp($4 / 1000.0)) \
+         and ($5::bigint is null or created_at < to_timestamp($5 / 1000.0)) \
+         order by created_at desc limit $6 offset $7",
+        &[&user, &q.operation, &q.status, &q.since_ms, &q.until_ms, &limit, &offset],
+    ).await.map_err(query_err)?;
+    Ok(Json(rows.iter().map(job_row).collect()))
+}
+
+fn job_row(r: &Row) -> JobRow {
+    JobRow {
+        id: r.get::<_, Uuid>(0).to_string(), operation: r.get(1), input_dimensions: r.get(2), element_count: r.get(3),
+        precision: r.get(4), simd_backend: r.get(5), compute_time_us: r.get(6), throughput_gflops: r.get(7),
+        status: r.get(8), results: r.get(9), created_at_ms: r.get(10),
+    }
+}
+
+async fn list_benchmarks(State(s): State<Arc<AppState>>, user: User, Query(q): Query<BenchmarkQuery>) -> Result<Json<Vec<BenchmarkRow>>, ApiError> {
+    let (client, user) = context(&s, user).await?;
+    let (limit, offset) = page(q.limit, q.offset);
+    let rows = client.query(
+        "select id, name, operations, cpu_features, scalar_time_us, simd_time_us, speedup_ratio, peak_gflops, \
+         (extract(epoch from created_at) * 1000)::bigint \
+         from public.compute_benchmarks \
+         where user_id = $1 and ($2::text is null or name = $2) \
+         and ($3::bigint is null or created_at >= to_timestamp($3 / 1000.0)) \
+         and ($4::bigint is null or created_at < to_timestamp($4 / 1000.0)) \
+         order by created_at desc limit $5 offset $6",
+        &[&user, &q.name, &q.since_ms, &q.until_ms, &limit, &offset],
+    ).await.map_err(query_err)?;
+    Ok(Json(rows.iter().map(|r| BenchmarkRow {
+        id: r.get::<_, Uuid>(0).to_string(), name: r.get(1), operations: r.get(2), cpu_features: r.get(3),
+        scalar_time_us: r.get(4), simd_time_us: r.get(5), speedup_ratio: r.get(6), peak_gflops: r.get(7),
+        created_at_ms: r.get(8),
+    }).collect()))
+}
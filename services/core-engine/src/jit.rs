@@ -0,0 +1,387 @@
+// ── Expression JIT (feature = "jit") ────────────────────────
+// Lowers an `expr::Program` to Cranelift IR: a 128-bit vector loop
+// (f64x2 / f32x4) followed by a scalar tail. Transcendentals and pow are
+// per-lane calls into Rust. Compiled kernels are cached by lowered
+// program + dtype, so folded scalar inputs are part of the key.
+use crate::expr::{BinOp, Dtype, Func, Op, Program};
+use cranelift_codegen::ir::{condcodes::{FloatCC, IntCC}, types, AbiParam, FuncRef, InstBuilder, MemFlags, Signature, Type, Value};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Linkage, Module};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+const CACHE_CAPACITY: usize = 256;
+
+type KernelFn = unsafe extern "C" fn(*const *const u8, *mut u8, usize);
+
+pub struct Kernel {
+    module: Option<JITModule>,
+    func: KernelFn,
+    dtype: Dtype,
+}
+
+// SAFETY: the module owns immutable, finalized code; `func` is only
+// invoked through `&self` and the module is freed once on drop.
+unsafe impl Send for Kernel {}
+unsafe impl Sync for Kernel {}
+
+impl Drop for Kernel {
+    fn drop(&mut self) {
+        if let Some(m) = self.module.take() {
+            // SAFETY: the last `Arc<Kernel>` is gone, so no caller can still be running `func`.
+            unsafe { m.free_memory() };
+        }
+    }
+}
+
+impl Kernel {
+    /// Runs the kernel; `inputs` must be ordered as `prog.inputs` and
+    /// each hold at least `n` elements of the kernel's dtype.
+    fn call<T>(&self, inputs: &[&[T]], n: usize) -> Vec<T> where T: Copy + Default {
+        let ptrs: Vec<*const u8> = inputs.iter().map(|s| { assert!(s.len() >= n); s.as_ptr() as *const u8 }).collect();
+        let mut out = vec![T::default(); n];
+        // SAFETY: pointers are valid for `n` elements and `out` is sized to `n`.
+        unsafe { (self.func)(ptrs.as_ptr(), out.as_mut_ptr() as *mut u8, n) };
+        out
+    }
+
+    pub fn run_f64(&self, inputs: &[&[f64]], n: usize) -> Vec<f64> {
+        assert!(self.dtype == Dtype::F64);
+        self.call(inputs, n)
+    }
+
+    pub fn run_f32(&self, inputs: &[&[f32]], n: usize) -> Vec<f32> {
+        assert!(self.dtype == Dtype::F32);
+        self.call(inputs, n)
+    }
+}
+
+#[derive(Default)]
+pub struct JitCache {
+    kernels: Mutex<HashMap<(String, Dtype), Arc<Kernel>>>,
+}
+
+impl JitCache {
+    /// Returns the cached kernel or compiles one; the flag reports a hit.
+    pub fn get_or_compile(&self, prog: &Program, dtype: Dtype) -> Result<(Arc<Kernel>, bool), String> {
+        let key = (format!("{:?}", prog.ops), dtype);
+        if let Some(k) = self.kernels.lock().unwrap().get(&key) {
+            return Ok((k.clone(), true));
+        }
+        let kernel = Arc::new(compile(prog, dtype)?);
+        let mut map = self.kernels.lock().unwrap();
+        if map.len() >= CACHE_CAPACITY { map.clear(); }
+        map.insert(key, kernel.clone());
+        Ok((kernel, false))
+    }
+
+    pub fn len(&self) -> usize { self.kernels.lock().unwrap().len() }
+}
+
+// ── Host callbacks ──────────────────────────────────────────
+macro_rules! host_fns {
+    ($t:ty, $exp:ident, $log:ident, $sin:ident, $cos:ident, $tanh:ident, $pow:ident, $fma:ident) => {
+        extern "C" fn $exp(x: $t) -> $t { x.exp() }
+        extern "C" fn $log(x: $t) -> $t { x.ln() }
+        extern "C" fn $sin(x: $t) -> $t { x.sin() }
+        extern "C" fn $cos(x: $t) -> $t { x.cos() }
+        extern "C" fn $tanh(x: $t) -> $t { x.tanh() }
+        extern "C" fn $pow(x: $t, y: $t) -> $t { x.powf(y) }
+        extern "C" fn $fma(x: $t, y: $t, z: $t) -> $t { x.mul_add(y, z) }
+    };
+}
+host_fns!(f64, exp_f64, log_f64, sin_f64, cos_f64, tanh_f64, pow_f64, fma_f64);
+host_fns!(f32, exp_f32, log_f32, sin_f32, cos_f32, tanh_f32, pow_f32, fma_f32);
+
+/// Host-side fallbacks: (DSL op, symbol, arity, f64 impl, f32 impl).
+fn host_symbols() -> [(&'static str, usize, *const u8, *const u8); 7] {
+    [
+        ("exp", 1, exp_f64 as *const u8, exp_f32 as *const u8),
+        ("log", 1, log_f64 as *const u8, log_f32 as *const u8),
+        ("sin", 1, sin_f64 as *const u8, sin_f32 as *const u8),
+        ("cos", 1, cos_f64 as *const u8, cos_f32 as *const u8),
+        ("tanh", 1, tanh_f64 as *const u8, tanh_f32 as *const u8),
+        ("pow", 2, pow_f64 as *const u8, pow_f32 as *const u8),
+        ("fma", 3, fma_f64 as *const u8, fma_f32 as *const u8),
+    ]
+}
+
+fn native_fma() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    { crate::simd::has_avx2_fma() }
+    #[cfg(target_arch = "aarch64")]
+    { true }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    { false }
+}
+
+/// Name of the host symbol an op needs, if it has no native lowering.
+fn host_call(op: &Op) -> Option<&'static str> {
+    match op {
+        Op::Bin(BinOp::Pow) => Some("pow"),
+        Op::Call(Func::Exp) => Some("exp"),
+        Op::Call(Func::Log) => Some("log"),
+        Op::Call(Func::Sin) => Some("sin"),
+        Op::Call(Func::Cos) => Some("cos"),
+        Op::Call(Func::Tanh) => Some("tanh"),
+        Op::Call(Func::Fma) if !native_fma() => Some("fma"),
+        _ => None,
+    }
+}
+
+// ── Code generation ─────────────────────────────────────────
+pub fn compile(prog: &Program, dtype: Dtype) -> Result<Kernel, String> {
+    let mut flags = settings::builder();
+    flags.set("opt_level", "speed").map_err(|e| e.to_string())?;
+    flags.set("use_colocated_libcalls", "false").map_err(|e| e.to_string())?;
+    flags.set("is_pic", "false").map_err(|e| e.to_string())?;
+    let isa = cranelift_native::builder()?
+        .finish(settings::Flags::new(flags)).map_err(|e| e.to_string())?;
+
+    let (elem, vec_ty, lanes): (Type, Type, usize) = match dtype {
+        Dtype::F64 => (types::F64, types::F64X2, 2),
+        Dtype::F32 => (types::F32, types::F32X4, 4),
+    };
+    let size = elem.bytes() as i64;
+
+    let mut jb = JITBuilder::with_isa(isa, default_libcall_names());
+    let hosts = host_symbols();
+    for (name, _, f64_ptr, f32_ptr) in hosts {
+        jb.symbol(format!("simd_jit_{name}"), if dtype == Dtype::F64 { f64_ptr } else { f32_ptr });
+    }
+    let mut module = JITModule::new(jb);
+    let ptr = module.target_config().pointer_type();
+
+    let mut ctx = module.make_context();
+    for _ in 0..3 { ctx.func.signature.params.push(AbiParam::new(ptr)); }
+
+    let mut imports: HashMap<&'static str, FuncRef> = HashMap::new();
+    for op in &prog.ops {
+        let Some(name) = host_call(op) else { continue };
+        if imports.contains_key(name) { continue; }
+        let arity = hosts.iter().find(|h| h.0 == name).map(|h| h.1).unwrap_or(1);
+        let mut sig = Signature::new(module.isa().default_call_conv());
+        for _ in 0..arity { sig.params.push(AbiParam::new(elem)); }
+        sig.returns.push(AbiParam::new(elem));
+        let id = module.declare_function(&format!("simd_jit_{name}"), Linkage::Import, &sig).map_err(|e| e.to_string())?;
+        imports.insert(name, module.declare_func_in_func(id, &mut ctx.func));
+    }
+
+    let mut fctx = FunctionBuilderContext::new();
+    let mut b = FunctionBuilder::new(&mut ctx.func, &mut fctx);
+    let entry = b.create_block();
+    let vec_head = b.create_block();
+    let vec_body = b.create_block();
+    let tail_head = b.create_block();
+    let tail_body = b.create_block();
+    let exit = b.create_block();
+    for blk in [vec_head, vec_body, tail_head, tail_body] { b.append_block_param(blk, ptr); }
+
+    b.append_block_params_for_function_params(entry);
+    b.switch_to_block(entry);
+    let (inputs_ptr, out_ptr, n) = (b.block_params(entry)[0], b.block_params(entry)[1], b.block_params(entry)[2]);
+    let bases: Vec<Value> = (0..prog.inputs.len())
+        .map(|i| b.ins().load(ptr, MemFlags::trusted(), inputs_ptr, (i * ptr.bytes() as usize) as i32))
+        .collect();
+    let vec_end = b.ins().band_imm(n, -(lanes as i64));
+    let zero = b.ins().iconst(ptr, 0);
+    b.ins().jump(vec_head, &[zero]);
+
+    // Vector loop.
+    b.switch_to_block(vec_head);
+    let i = b.block_params(vec_head)[0];
+    let more = b.ins().icmp(IntCC::UnsignedLessThan, i, vec_end);
+    b.ins().brif(more, vec_body, &[i], tail_head, &[i]);
+
+    b.switch_to_block(vec_body);
+    let i = b.block_params(vec_body)[0];
+    let off = b.ins().imul_imm(i, size);
+    let v = emit(&mut b, prog, &bases, off, elem, Some((vec_ty, lanes)), &imports);
+    let dst = b.ins().iadd(out_ptr, off);
+    b.ins().store(MemFlags::new(), v, dst, 0);
+    let next = b.ins().iadd_imm(i, lanes as i64);
+    b.ins().jump(vec_head, &[next]);
+
+    // Scalar tail.
+    b.switch_to_block(tail_head);
+    let i = b.block_params(tail_head)[0];
+    let more = b.ins().icmp(IntCC::UnsignedLessThan, i, n);
+    b.ins().brif(more, tail_body, &[i], exit, &[]);
+
+    b.switch_to_block(tail_body);
+    let i = b.block_params(tail_body)[0];
+    let off = b.ins().imul_imm(i, size);
+    let v = emit(&mut b, prog, &bases, off, elem, None, &imports);
+    let dst = b.ins().iadd(out_ptr, off);
+    b.ins().store(MemFlags::new(), v, dst, 0);
+    let next = b.ins().iadd_imm(i, 1);
+    b.ins().jump(tail_head, &[next]);
+
+    b.switch_to_block(exit);
+    b.ins().return_(&[]);
+    b.seal_all_blocks();
+    b.finalize();
+
+    let id = module.declare_anonymous_function(&ctx.func.signature).map_err(|e| e.to_string())?;
+    module.define_function(id, &mut ctx).map_err(|e| format!("{e:?}"))?;
+    module.clear_context(&mut ctx);
+    module.finalize_definitions().map_err(|e| e.to_string())?;
+    let code = module.get_finalized_function(id);
+    // SAFETY: the function was built with exactly this signature.
+    let func = unsafe { std::mem::transmute::<*const u8, KernelFn>(code) };
+    Ok(Kernel { module: Some(module), func, dtype })
+}
+
+/// Emits the postfix program once; `vector` selects the SIMD type.
+fn emit(
+    b: &mut FunctionBuilder, prog: &Program, bases: &[Value], off: Value, elem: Type,
+    vector: Option<(Type, usize)>, imports: &HashMap<&'static str, FuncRef>,
+) -> Value {
+    let ty = vector.map(|v| v.0).unwrap_or(elem);
+    let mut stack: Vec<Value> = Vec::with_capacity(prog.max_depth);
+    for op in &prog.ops {
+        if let Some(name) = host_call(op) {
+            let arity = match op { Op::Bin(_) => 2, Op::Call(f) => f.arity(), _ => 1 };
+            let args = stack.split_off(stack.len() - arity);
+            let f = imports[name];
+            let r = match vector {
+                None => { let call = b.ins().call(f, &args); b.inst_results(call)[0] }
+                Some((_, lanes)) => {
+                    let mut acc = args[0];
+                    for lane in 0..lanes as u8 {
+                        let lane_args: Vec<Value> = args.iter().map(|a| b.ins().extractlane(*a, lane)).collect();
+                        let call = b.ins().call(f, &lane_args);
+                        let r = b.inst_results(call)[0];
+                        acc = b.ins().insertlane(acc, r, lane);
+                    }
+                    acc
+                }
+            };
+            stack.push(r);
+            continue;
+        }
+        let v = match op {
+            Op::Const(c) => {
+                let s = if elem == types::F64 { b.ins().f64const(*c) } else { b.ins().f32const(*c as f32) };
+                if vector.is_some() { b.ins().splat(ty, s) } else { s }
+            }
+            Op::Load(idx) => {
+                let addr = b.ins().iadd(bases[*idx], off);
+                b.ins().load(ty, MemFlags::new(), addr, 0)
+            }
+            Op::Neg => { let a = stack.pop().unwrap(); b.ins().fneg(a) }
+            Op::Bin(op) => {
+                let r = stack.pop().unwrap();
+                let l = stack.pop().unwrap();
+                match op {
+                    BinOp::Add => b.ins().fadd(l, r),
+                    BinOp::Sub => b.ins().fsub(l, r),
+                    BinOp::Mul => b.ins().fmul(l, r),
+                    BinOp::Div => b.ins().fdiv(l, r),
+                    BinOp::Pow => unreachable!("pow is a host call"),
+                }
+            }
+            Op::Call(f) => {
+                let args = stack.split_off(stack.len() - f.arity());
+                match f {
+                    Func::Abs => b.ins().fabs(args[0]),
+                    Func::Sqrt => b.ins().sqrt(args[0]),
+                    Func::Floor => b.ins().floor(args[0]),
+                    Func::Ceil => b.ins().ceil(args[0]),
+                    Func::Min => min_max(b, args[0], args[1], false),
+                    Func::Max => min_max(b, args[0], args[1], true),
+                    Func::Clamp => { let lo = min_max(b, args[0], args[1], true); min_max(b, lo, args[2], false) }
+                    Func::Fma => b.ins().fma(args[0], args[1], args[2]),
+                    Func::Lerp => {
+                        let d = b.ins().fsub(args[1], args[0]);
+                        let t = b.ins().fmul(d, args[2]);
+                        b.ins().fadd(args[0], t)
+                    }
+                    _ => unreachable!("transcendentals are host calls"),
+                }
+            }
+        };
+        stack.push(v);
+    }
+    stack.pop().expect("program leaves one value")
+}
+
+/// `f64::min`/`f64::max`: a NaN operand yields the other one. Cranelift's
+/// `fmin`/`fmax` propagate NaN instead, so this compares and selects.
+fn min_max(b: &mut FunctionBuilder, x: Value, y: Value, max: bool) -> Value {
+    let cc = if max { FloatCC::GreaterThan } else { FloatCC::LessThan };
+    let wins = b.ins().fcmp(cc, x, y);
+    let y_nan = b.ins().fcmp(FloatCC::Unordered, y, y);
+    let take_x = b.ins().bor(wins, y_nan);
+    let ty = b.func.dfg.value_type(x);
+    if ty.is_vector() {
+        let mask = b.ins().bitcast(ty, MemFlags::new(), take_x);
+        b.ins().bitselect(mask, x, y)
+    } else {
+        b.ins().select(take_x, x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::{execute, prepare, Input};
+
+    /// Runs `expression` through the interpreter and the JIT in both dtypes
+    /// and checks they agree (NaN matches NaN).
+    fn check(expression: &str, inputs: &[(&str, Vec<f64>)]) {
+        let inputs: HashMap<String, Input> = inputs.iter().map(|(n, v)| (n.to_string(), Input::Vector(v.clone()))).collect();
+        for (dtype, tol) in [("f64", 1e-12), ("f32", 1e-5)] {
+            let Ok((prog, dt)) = prepare(expression, &inputs, Some(dtype)) else { panic!("{expression} does not compile") };
+            let want = execute(&prog, dt, &inputs);
+            let kernel = compile(&prog, dt).unwrap();
+            let vecs: Vec<&[f64]> = prog.inputs.iter().map(|n| match &inputs[n] {
+                Input::Vector(v) => v.as_slice(),
+                Input::Scalar(_) => unreachable!(),
+            }).collect();
+            let got: Vec<f64> = match dt {
+                Dtype::F64 => kernel.run_f64(&vecs, prog.len),
+                Dtype::F32 => {
+                    let conv: Vec<Vec<f32>> = vecs.iter().map(|v| v.iter().map(|x| *x as f32).collect()).collect();
+                    let refs: Vec<&[f32]> = conv.iter().map(|v| v.as_slice()).collect();
+                    kernel.run_f32(&refs, prog.len).into_iter().map(|x| x as f64).collect()
+                }
+            };
+            for (i, (w, g)) in want.iter().zip(&got).enumerate() {
+                let same = (w.is_nan() && g.is_nan()) || w == g || (w - g).abs() <= tol * w.abs().max(1.0);
+                assert!(same, "{expression} ({dtype}) at {i}: interpreter {w}, jit {g}");
+            }
+        }
+    }
+
+    #[test]
+    fn min_max_clamp_ignore_nan_like_the_interpreter() {
+        // 11 elements: a full vector loop plus a scalar tail for both dtypes.
+        let nan = f64::NAN;
+        let a = vec![nan, 1.0, nan, -2.0, 5.0, nan, 3.0, 0.5, nan, -1.0, 7.0];
+        let b = vec![1.0, nan, nan, 4.0, -3.0, 2.0, nan, 0.25, -6.0, nan, 8.0];
+        let c = vec![2.0, 0.0, 1.0, nan, nan, nan, 2.5, 1.0, 3.0, 0.0, nan];
+        let inputs = [("a", a), ("b", b), ("c", c)];
+        check("min(a, b)", &inputs);
+        check("max(a, b)", &inputs);
+        check("clamp(a, b, c)", &inputs);
+        check("min(max(a, b), c) + max(min(c, a), b)", &inputs);
+    }
+
+    #[test]
+    fn matches_interpreter() {
+        let a: Vec<f64> = (0..37).map(|i| (i as f64 - 18.0) * 0.37).collect();
+        let b: Vec<f64> = (0..37).map(|i| 1.0 + (i as f64 * 0.61).sin()).collect();
+        let c: Vec<f64> = (0..37).map(|i| (i % 5) as f64 * 0.25).collect();
+        let inputs = [("a", a), ("b", b), ("c", c)];
+        for e in [
+            "a * b + c - a / b", "-a ^ 2 + b ^ 0.5", "sqrt(abs(a)) * exp(c) - log(b)",
+            "sin(a) * cos(b) + tanh(c)", "floor(a) + ceil(b)", "fma(a, b, c) + lerp(a, b, c)",
+            "clamp(a, -1, 1) * min(b, 1.5) + max(c, 0.3)",
+        ] {
+            check(e, &inputs);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+// ── Asynchronous jobs ───────────────────────────────────────
+// Long computations submitted as jobs run on a bounded worker pool instead
+// of blocking the request. Any synchronous endpoint can be wrapped: the
+// job body is that endpoint's request. Lifecycle (the `status` column of
+// `compute_jobs`): pending → running → completed | failed | cancelled.
+// Finished jobs are kept for `SIMD_JOB_TTL_SECS` and then expire.
+use crate::history::User;
+use crate::limits::Limits;
+use crate::{api_err, ApiError, AppState};
+use crate::telemetry::Json;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Router,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+pub const KINDS: &[&str] = &["compute", "matrix", "benchmark", "roofline", "tensor", "batch", "eval"];
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status { Pending, Running, Completed, Failed, Cancelled }
+
+impl Status {
+    fn finished(self) -> bool { matches!(self, Status::Completed | Status::Failed | Status::Cancelled) }
+}
+
+/// Shared between the job table and the code running the job.
+#[derive(Default)]
+pub struct Control { progress: AtomicU64, cancelled: AtomicBool }
+
+impl Control {
+    pub fn progress(&self) -> f64 { f64::from_bits(self.progress.load(Ordering::Relaxed)) }
+    pub fn is_cancelled(&self) -> bool { self.cancelled.load(Ordering::Relaxed) }
+    pub fn cancel(&self) { self.cancelled.store(true, Ordering::Relaxed); }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<Control>>> = const { RefCell::new(None) };
+}
+
+/// Records progress (0..=1) for the job running on this thread, if any.
+/// Long-running code calls this at natural checkpoints.
+pub fn report_progress(fraction: f64) {
+    CURRENT.with(|c| if let Some(c) = &*c.borrow() {
+        c.progress.store(fraction.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    });
+}
+
+/// Whether the job (or pooled request, see `pool`) running on this thread
+/// has been cancelled.
+pub fn cancelled() -> bool {
+    CURRENT.with(|c| c.borrow().as_ref().is_some_and(|c| c.is_cancelled()))
+}
+
+/// Runs `f` with `control` installed for `report_progress`/`cancelled`.
+/// Blocking threads are reused, so it is removed again even if `f` panics.
+pub fn with_control<R>(control: Arc<Control>, f: impl FnOnce() -> R) -> R {
+    struct Uninstall;
+    impl Drop for Uninstall {
+        fn drop(&mut self) { CURRENT.with(|c| *c.borrow_mut() = None); }
+    }
+    CURRENT.with(|c| *c.borrow_mut() = Some(control));
+    let _uninstall = Uninstall;
+    f()
+}
+
+struct Job {
+    /// Only this caller sees, reads and cancels the job.
+    owner: User,
+    kind: String,
+    status: Status,
+    control: Arc<Control>,
+    created: SystemTime,
+    started: Option<SystemTime>,
+    finished: Option<(SystemTime, Instant)>,
+    result: Option<serde_json::Value>,
+    error: Option<String>,
+}
+
+struct Queued { id: Uuid, kind: String, request: serde_json::Value, user: User, limits: Limits }
+
+pub struct Jobs {
+    table: Mutex<HashMap<Uuid, Job>>,
+    queue: mpsc::Sender<Queued>,
+    receiver: Mutex<Option<mpsc::Receiver<Queued>>>,
+    pub workers: usize,
+    pub ttl: Duration,
+}
+
+fn env_usize(key: &str) -> Option<usize> {
+    std::env::var(key).ok().and_then(|v| v.parse().ok())
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        let capacity = env_usize("SIMD_JOB_QUEUE").unwrap_or(64).max(1);
+        let workers = env_usize("SIMD_JOB_WORKERS")
+            .unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(2))
+            .max(1);
+        let ttl = Duration::from_secs(env_usize("SIMD_JOB_TTL_SECS").unwrap_or(3600) as u64);
+        let (queue, rx) = mpsc::channel(capacity);
+        Jobs { table: Mutex::new(HashMap::new()), queue, receiver: Mutex::new(Some(rx)), workers, ttl }
+    }
+}
+
+/// Starts the worker pool and the expiry sweeper.
+pub fn start(state: Arc<AppState>) {
+    let Some(rx) = state.jobs.receiver.lock().unwrap().take() else { return };
+    let rx = Arc::new(tokio::sync::Mutex::new(rx));
+    for _ in 0..state.jobs.workers {
+        let (state, rx) = (state.clone(), rx.clone());
+        tokio::spawn(async move {
+            loop {
+                let Some(job) = rx.lock().await.recv().await else { break };
+                run(&state, job).await;
+            }
+        });
+    }
+    let sweeper = state.clone();
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(30));
+        loop {
+            tick.tick().await;
+            sweeper.jobs.expire();
+        }
+    });
+}
+
+impl Jobs {
+    fn expire(&self) {
+        let ttl = self.ttl;
+        self.table.lock().unwrap().retain(|_, j| j.finished.is_none_or(|(_, at)| at.elapsed() < ttl));
+    }
+
+    fn finish(&self, id: Uuid, outcome: Result<serde_json::Value, String>) {
+        let mut table = self.table.lock().unwrap();
+        let Some(job) = table.get_mut(&id) else { return };
+        if job.status == Status::Cancelled { return; }
+        job.finished = Some((SystemTime::now(), Instant::now()));
+        match outcome {
+            Ok(v) => {
+                job.status = Status::Completed;
+                job.control.progress.store(1.0f64.to_bits(), Ordering::Relaxed);
+                job.result = Some(v);
+            }
+            Err(e) => { job.status = Status::Failed; job.error = Some(e); }
+        }
+    }
+}
+
+async fn run(state: &Arc<AppState>, q: Queued) {
+    let control = {
+        let mut table = state.jobs.table.lock().unwrap();
+        match table.get_mut(&q.id) {
+            Some(job) if job.status == Status::Pending => {
+                job.status = Status::Running;
+                job.started = Some(SystemTime::now());
+                job.control.clone()
+            }
+            _ => return, // cancelled (or expired) while queued
+        }
+    };
+    let (st, handle) = (state.clone(), tokio::runtime::Handle::current());
+    // The endpoints are CPU-bound, so run them off the async workers with
+    // this job's control installed for `report_progress`/`cancelled`.
+    let outcome = tokio::task::spawn_blocking(move || {
+        with_control(control, || handle.block_on(execute(st, &q.kind, q.request, q.user, q.limits)))
+    }).await.unwrap_or_else(|e| Err(format!("job panicked: {e}")));
+    state.jobs.finish(q.id, outcome);
+}
+
+/// Normalises the synchronous handlers' return types.
+trait JobOutput { fn into_job(self) -> Result<serde_json::Value, String>; }
+
+impl<T: Serialize> JobOutput for Json<T> {
+    fn into_job(self) -> Result<serde_json::Value, String> { serde_json::to_value(self.0).map_err(|e| e.to_string()) }
+}
+
+impl<T: Serialize> JobOutput for Result<Json<T>, ApiError> {
+    fn into_job(self) -> Result<serde_json::Value, String> {
+        match self {
+            Ok(j) => j.into_job(),
+            Err((_, Json(e))) => Err(match e.details { Some(d) => format!("{}: {d}", e.error), None => e.error }),
+        }
+    }
+}
+
+fn parse<T: DeserializeOwned>(v: serde_json::Value) -> Result<Json<T>, String> {
+    serde_json::from_value(v).map(Json).map_err(|e| format!("invalid request: {e}"))
+}
+
+async fn execute(s: Arc<AppState>, kind: &str, request: serde_json::Value, user: User, limits: Limits) -> Result<serde_json::Value, String> {
+    match kind {
+        "compute" => crate::compute(State(s), user, limits, parse(request)?).await.into_job(),
+        "matrix" => crate::matrix(State(s), user, limits, parse(request)?).await.into_job(),
+        "benchmark" => crate::benchmark(State(s), user, limits, parse(request)?).await.into_job(),
+        "roofline" => crate::roofline::roofline(State(s), limits, parse(request)?).await.into_job(),
+        "tensor" => crate::tensor::tensor(State(s), limits, parse(request)?).await.into_job(),
+        "batch" => crate::batch::batch(State(s), limits, parse(request)?).await.into_job(),
+        "eval" => crate::expr::eval(State(s), limits, parse(request)?).await.into_job(),
+        other => Err(format!("unknown job kind '{other}'")),
+    }
+}
+
+// ── API ─────────────────────────────────────────────────────
+#[derive(Deserialize)]
+struct SubmitRequest {
+    /// Which endpoint to run: compute, matrix, benchmark, roofline, tensor, batch or eval.
+    kind: String,
+    /// That endpoint's request body.
+    request: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct JobView {
+    id: String, kind: String, status: Status, progress: f64,
+    created_at_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    started_at_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    finished_at_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_in_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn unix_ms(t: SystemTime) -> u128 {
+    t.duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0)
+}
+
+fn view(id: &Uuid, j: &Job, ttl: Duration, with_result: bool) -> JobView {
+    JobView {
+        id: id.to_string(), kind: j.kind.clone(), status: j.status, progress: j.control.progress(),
+        created_at_ms: unix_ms(j.created),
+        started_at_ms: j.started.map(unix_ms),
+        finished_at_ms: j.finished.map(|(t, _)| unix_ms(t)),
+        expires_in_secs: j.finished.map(|(_, at)| ttl.saturating_sub(at.elapsed()).as_secs()),
+        result: if with_result { j.result.clone() } else { None },
+        error: j.error.clone(),
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/v1/simd/jobs", post(submit).get(list))
+        .route("/api/v1/simd/jobs/:id", get(status).delete(cancel))
+}
+
+fn job_id(id: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(id).map_err(|_| api_err(StatusCode::BAD_REQUEST, "Invalid job id", Some(id.into())))
+}
+
+fn not_found(id: Uuid) -> ApiError { api_err(StatusCode::NOT_FOUND, "Job not found", Some(id.to_string())) }
+
+async fn submit(State(s): State<Arc<AppState>>, user: User, limits: Limits, Json(req): Json<SubmitRequest>) -> Result<(StatusCode, Json<JobView>), ApiError> {
+    if !KINDS.contains(&req.kind.as_str()) {
+        return Err(api_err(StatusCode::BAD_REQUEST, "Unknown job kind", Some(format!("{} (expected one of {})", req.kind, KINDS.join(", ")))));
+    }
+    let id = Uuid::new_v4();
+    let job = Job {
+        owner: user, kind: req.kind.clone(), status: Status::Pending, control: Default::default(), created: SystemTime::now(),
+        started: None, finished: None, result: None, error: None,
+    };
+    let v = view(&id, &job, s.jobs.ttl, false);
+    s.jobs.table.lock().unwrap().insert(id, job);
+    if s.jobs.queue.try_send(Queued { id, kind: req.kind, request: req.request, user, limits }).is_err() {
+        s.jobs.table.lock().unwrap().remove(&id);
+        return Err(api_err(StatusCode::TOO_MANY_REQUESTS, "Job queue full", Some("retry later".into())));
+    }
+    Ok((StatusCode::ACCEPTED, Json(v)))
+}
+
+async fn list(State(s): State<Arc<AppState>>, user: User) -> Json<Vec<JobView>> {
+    s.jobs.expire();
+    let table = s.jobs.table.lock().unwrap();
+    let mut out: Vec<JobView> = table.iter().filter(|(_, j)| j.owner.0 == user.0).map(|(id, j)| view(id, j, s.jobs.ttl, false)).collect();
+    out.sort_by_key(|v| std::cmp::Reverse(v.created_at_ms));
+    Json(out)
+}
+
+async fn status(State(s): State<Arc<AppState>>, user: User, Path(id): Path<String>) -> Result<Json<JobView>, ApiError> {
+    let id = job_id(&id)?;
+    s.jobs.expire();
+    let table = s.jobs.table.lock().unwrap();
+    table.get(&id).filter(|j| j.owner.0 == user.0).map(|j| Json(view(&id, j, s.jobs.ttl, true))).ok_or_else(|| not_found(id))
+}
+
+/// Cancels a pending or running job; deleting a finished job removes it.
+async fn cancel(State(s): State<Arc<AppState>>, user: User, Path(id): Path<String>) -> Result<Json<JobView>, ApiError> {
+    let id = job_id(&id)?;
+    let mut table = s.jobs.table.lock().unwrap();
+    let job = table.get_mut(&id).filter(|j| j.owner.0 == user.0).ok_or_else(|| not_found(id))?;
+    if job.status.finished() {
+        let job = table.remove(&id).unwrap();
+        return Ok(Json(view(&id, &job, s.jobs.ttl, false)));
+    }
+    job.status = Status::Cancelled;
+    job.control.cancel();
+    job.finished = Some((SystemTime::now(), Instant::now()));
+    Ok(Json(view(&id, job, s.jobs.ttl, false)))
+}
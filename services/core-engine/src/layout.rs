@@ -0,0 +1,333 @@
+// ── Tensor layout operations ────────────────────────────────
+// reshape / permute / slice / tile / repeat are stride tricks that return
+// views; concat, pad, gather and scatter_add materialise a new buffer.
+use crate::simd;
+use crate::tensor::{checked_numel, contiguous_strides, numel, Tensor};
+use serde::Deserialize;
+use std::sync::Arc;
+
+fn axis_index(ndim: usize, axis: isize) -> Result<usize, String> {
+    let r = if axis < 0 { axis + ndim as isize } else { axis };
+    if r < 0 || r >= ndim as isize { return Err(format!("axis {axis} out of range for {ndim}-d tensor")); }
+    Ok(r as usize)
+}
+
+fn view(t: &Tensor, shape: Vec<usize>, strides: Vec<isize>, offset: usize) -> Tensor {
+    Tensor { shape, strides, offset, data: t.data.clone() }
+}
+
+fn owned(shape: Vec<usize>, data: Vec<f64>) -> Tensor {
+    Tensor { strides: contiguous_strides(&shape), shape, offset: 0, data: Arc::new(data) }
+}
+
+/// One `-1` entry is inferred. Non-contiguous inputs are copied first.
+pub fn reshape(t: &Tensor, shape: &[isize]) -> Result<Tensor, String> {
+    let n = t.numel();
+    let infer = shape.iter().filter(|d| **d < 0).count();
+    if infer > 1 || shape.iter().any(|d| *d < -1) { return Err("at most one -1 dimension allowed".into()); }
+    let given: Vec<usize> = shape.iter().filter(|d| **d >= 0).map(|d| *d as usize).collect();
+    let known = checked_numel(&given).ok_or_else(|| format!("shape {shape:?} overflows"))?;
+    let shape: Vec<usize> = shape.iter().map(|&d| if d < 0 { n.checked_div(known).unwrap_or(0) } else { d as usize }).collect();
+    if checked_numel(&shape) != Some(n) {
+        return Err(format!("cannot reshape {:?} ({n} elements) into {shape:?}", t.shape));
+    }
+    let src = if t.is_contiguous() { t.clone() } else { t.contiguous() };
+    Ok(view(&src, shape.clone(), contiguous_strides(&shape), src.offset))
+}
+
+/// `None` reverses the axes (plain transpose).
+pub fn permute(t: &Tensor, perm: Option<&[usize]>) -> Result<Tensor, String> {
+    let nd = t.ndim();
+    let perm: Vec<usize> = perm.map(|p| p.to_vec()).unwrap_or_else(|| (0..nd).rev().collect());
+    let mut seen = vec![false; nd];
+    if perm.len() != nd || perm.iter().any(|&p| p >= nd || std::mem::replace(&mut seen[p], true)) {
+        return Err(format!("{perm:?} is not a permutation of {nd} axes"));
+    }
+    Ok(view(t, perm.iter().map(|&p| t.shape[p]).collect(), perm.iter().map(|&p| t.strides[p]).collect(), t.offset))
+}
+
+#[derive(Clone, Default, Deserialize)]
+pub struct SliceSpec { pub start: Option<isize>, pub stop: Option<isize>, pub step: Option<isize> }
+
+/// NumPy basic slicing; missing trailing specs select the whole axis.
+pub fn slice(t: &Tensor, specs: &[SliceSpec]) -> Result<Tensor, String> {
+    if specs.len() > t.ndim() { return Err(format!("{} slices for {}-d tensor", specs.len(), t.ndim())); }
+    let (mut shape, mut strides) = (t.shape.clone(), t.strides.clone());
+    let mut offset = t.offset as isize;
+    for (d, s) in specs.iter().enumerate() {
+        let n = t.shape[d] as isize;
+        let step = s.step.unwrap_or(1);
+        if step == 0 { return Err("slice step cannot be zero".into()); }
+        let norm = |i: isize, lo: isize, hi: isize| { let i = if i < 0 { i + n } else { i }; i.clamp(lo, hi) };
+        let (start, stop) = if step > 0 {
+            (s.start.map(|i| norm(i, 0, n)).unwrap_or(0), s.stop.map(|i| norm(i, 0, n)).unwrap_or(n))
+        } else {
+            (s.start.map(|i| norm(i, -1, n - 1)).unwrap_or(n - 1), s.stop.map(|i| norm(i, -1, n - 1)).unwrap_or(-1))
+        };
+        let len = if step > 0 { (stop - start + step - 1).max(0) / step } else { (start - stop - step - 1).max(0) / -step };
+        if len > 0 { offset += start * t.strides[d]; }
+        shape[d] = len as usize;
+        strides[d] = t.strides[d] * step;
+    }
+    Ok(view(t, shape, strides, offset.max(0) as usize))
+}
+
+pub fn concat(ts: &[Tensor], axis: isize) -> Result<Tensor, String> {
+    let first = ts.first().ok_or("concat needs at least one tensor")?;
+    let nd = first.ndim();
+    if nd == 0 { return Err("cannot concatenate 0-d tensors".into()); }
+    let ax = axis_index(nd, axis)?;
+    for t in ts {
+        let same = t.ndim() == nd && (0..nd).all(|d| d == ax || t.shape[d] == first.shape[d]);
+        if !same { return Err(format!("shape {:?} does not match {:?} outside axis {ax}", t.shape, first.shape)); }
+    }
+    let mut shape = first.shape.clone();
+    shape[ax] = ts.iter().map(|t| t.shape[ax]).sum();
+    let outer: usize = first.shape[..ax].iter().product();
+    let parts: Vec<Vec<f64>> = ts.iter().map(|t| t.to_vec()).collect();
+    let blocks: Vec<usize> = ts.iter().map(|t| t.shape[ax..].iter().product()).collect();
+    let mut out = Vec::with_capacity(numel(&shape));
+    for o in 0..outer {
+        for (p, &bl) in parts.iter().zip(&blocks) { out.extend_from_slice(&p[o * bl..(o + 1) * bl]); }
+    }
+    Ok(owned(shape, out))
+}
+
+pub fn stack(ts: &[Tensor], axis: isize) -> Result<Tensor, String> {
+    let first = ts.first().ok_or("stack needs at least one tensor")?;
+    if ts.iter().any(|t| t.shape != first.shape) { return Err("stack requires equal shapes".into()); }
+    let ax = axis_index(first.ndim() + 1, axis)?;
+    let expanded: Vec<Tensor> = ts.iter().map(|t| {
+        let mut shape: Vec<isize> = t.shape.iter().map(|d| *d as isize).collect();
+        shape.insert(ax, 1);
+        reshape(t, &shape)
+    }).collect::<Result<_, _>>()?;
+    concat(&expanded, ax as isize)
+}
+
+/// `np.tile`: interleaves zero-stride repeat axes, then merges them.
+pub fn tile(t: &Tensor, reps: &[usize]) -> Result<Tensor, String> {
+    let nd = t.ndim().max(reps.len());
+    let pad_t = nd - t.ndim();
+    let pad_r = nd - reps.len();
+    let (mut shape, mut strides, mut merged) = (Vec::new(), Vec::new(), Vec::new());
+    for d in 0..nd {
+        let r = if d < pad_r { 1 } else { reps[d - pad_r] };
+        let (sd, ss) = if d < pad_t { (1, 0) } else { (t.shape[d - pad_t], t.strides[d - pad_t]) };
+        shape.extend([r, sd]);
+        strides.extend([0, ss]);
+        merged.push((r * sd) as isize);
+    }
+    reshape(&view(t, shape, strides, t.offset), &merged)
+}
+
+/// `np.repeat`: each element repeated `repeats` times along `axis`
+/// (flattened first when no axis is given).
+pub fn repeat(t: &Tensor, repeats: usize, axis: Option<isize>) -> Result<Tensor, String> {
+    let (src, ax) = match axis {
+        Some(a) => (t.clone(), axis_index(t.ndim(), a)?),
+        None => (reshape(t, &[-1])?, 0),
+    };
+    let (mut shape, mut strides) = (src.shape.clone(), src.strides.clone());
+    shape.insert(ax + 1, repeats);
+    strides.insert(ax + 1, 0);
+    let mut merged: Vec<isize> = src.shape.iter().map(|d| *d as isize).collect();
+    merged[ax] *= repeats as isize;
+    reshape(&view(&src, shape, strides, src.offset), &merged)
+}
+
+/// Builds an output whose per-axis index `i` reads source index `maps[d][i]`
+/// (`None` writes `fill`).
+fn remap(t: &Tensor, maps: &[Vec<Option<usize>>], fill: f64) -> Tensor {
+    let shape: Vec<usize> = maps.iter().map(|m| m.len()).collect();
+    let n = numel(&shape);
+    let mut out = Vec::with_capacity(n);
+    if n == 0 { return owned(shape, out); }
+    let nd = shape.len();
+    if nd == 0 { return owned(shape, vec![t.data[t.offset]]); }
+    let mut idx = vec![0usize; nd];
+    'outer: loop {
+        let mut base = Some(t.offset as isize);
+        for d in 0..nd - 1 {
+            base = match (base, maps[d][idx[d]]) { (Some(b), Some(s)) => Some(b + s as isize * t.strides[d]), _ => None };
+        }
+        let last = &maps[nd - 1];
+        match base {
+            None => out.extend(std::iter::repeat_n(fill, last.len())),
+            Some(b) => out.extend(last.iter().map(|s| s.map(|s| t.data[(b + s as isize * t.strides[nd - 1]) as usize]).unwrap_or(fill))),
+        }
+        let mut d = nd - 1;
+        loop {
+            if d == 0 { break 'outer; }
+            d -= 1;
+            idx[d] += 1;
+            if idx[d] < shape[d] { break; }
+            idx[d] = 0;
+        }
+    }
+    owned(shape, out)
+}
+
+pub fn pad(t: &Tensor, widths: &[[usize; 2]], mode: &str, value: f64) -> Result<Tensor, String> {
+    if widths.len() != t.ndim() { return Err(format!("pad_width has {} entries for {}-d tensor", widths.len(), t.ndim())); }
+    let maps: Vec<Vec<Option<usize>>> = t.shape.iter().zip(widths).map(|(&n, &[before, after])| {
+        (0..before + n + after).map(|o| {
+            let i = o as isize - before as isize;
+            if (0..n as isize).contains(&i) { return Ok(Some(i as usize)); }
+            match mode {
+                "constant" => Ok(None),
+                _ if n == 0 => Err("cannot pad an empty axis with edge/reflect".to_string()),
+                "edge" => Ok(Some(i.clamp(0, n as isize - 1) as usize)),
+                "reflect" if n == 1 => Ok(Some(0)),
+                "reflect" => {
+                    let period = 2 * (n as isize - 1);
+                    let j = i.rem_euclid(period);
+                    Ok(Some((if j >= n as isize { period - j } else { j }) as usize))
+                }
+                other => Err(format!("unknown pad mode '{other}' (expected constant, reflect or edge)")),
+            }
+        }).collect()
+    }).collect::<Result<_, _>>()?;
+    Ok(remap(t, &maps, value))
+}
+
+fn resolve_indices(indices: &[isize], n: usize) -> Result<Vec<usize>, String> {
+    indices.iter().map(|&i| {
+        let r = if i < 0 { i + n as isize } else { i };
+        if r < 0 || r >= n as isize { Err(format!("index {i} out of bounds for axis of size {n}")) } else { Ok(r as usize) }
+    }).collect()
+}
+
+/// `np.take` along `axis`.
+pub fn gather(t: &Tensor, indices: &[isize], axis: isize) -> Result<Tensor, String> {
+    let ax = axis_index(t.ndim(), axis)?;
+    let idx = resolve_indices(indices, t.shape[ax])?;
+    let maps: Vec<Vec<Option<usize>>> = (0..t.ndim()).map(|d| {
+        if d == ax { idx.iter().map(|i| Some(*i)).collect() } else { (0..t.shape[d]).map(Some).collect() }
+    }).collect();
+    Ok(remap(t, &maps, 0.0))
+}
+
+/// `out = a; out[.., indices[k], ..] += updates[.., k, ..]` along `axis`.
+/// Duplicate indices accumulate. `updates` broadcasts to the target shape.
+pub fn scatter_add(a: &Tensor, indices: &[isize], updates: &Tensor, axis: isize) -> Result<Tensor, String> {
+    let ax = axis_index(a.ndim(), axis)?;
+    let idx = resolve_indices(indices, a.shape[ax])?;
+    let mut ushape = a.shape.clone();
+    ushape[ax] = idx.len();
+    let upd = updates.broadcast_to(&ushape)
+        .map_err(|_| format!("updates shape {:?} does not broadcast to {ushape:?}", updates.shape))?
+        .to_vec();
+    let mut out = a.to_vec();
+    let outer: usize = a.shape[..ax].iter().product();
+    let inner: usize = a.shape[ax + 1..].iter().product();
+    let (n, k) = (a.shape[ax], idx.len());
+    for o in 0..outer {
+        for (j, &i) in idx.iter().enumerate() {
+            let dst = (o * n + i) * inner;
+            let src = (o * k + j) * inner;
+            simd::axpy_f64(1.0, &upd[src..src + inner], &mut out[dst..dst + inner]);
+        }
+    }
+    Ok(owned(a.shape.clone(), out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(shape: &[usize], data: Vec<f64>) -> Tensor {
+        Tensor::new(shape.to_vec(), data).unwrap()
+    }
+
+    fn iota(shape: &[usize]) -> Tensor {
+        t(shape, (0..numel(shape)).map(|i| i as f64).collect())
+    }
+
+    /// 2×3 holding 1..=6.
+    fn m23() -> Tensor {
+        t(&[2, 3], vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0])
+    }
+
+    fn neg(a: &Tensor) -> Tensor {
+        crate::tensor::map1(a, |x| -x)
+    }
+
+    #[test]
+    fn reshape_infers_minus_one() {
+        let a = iota(&[2, 3, 4]);
+        let r = reshape(&a, &[4, -1]).unwrap();
+        assert_eq!((r.shape.clone(), r.strides.clone()), (vec![4, 6], vec![6, 1]));
+        assert!(Arc::ptr_eq(&r.data, &a.data), "contiguous reshape is a view");
+        assert_eq!(reshape(&a, &[-1]).unwrap().shape, [24]);
+        assert_eq!(reshape(&a, &[2, -1, 3]).unwrap().shape, [2, 4, 3]);
+        assert_eq!(reshape(&iota(&[0, 3]), &[-1, 3]).unwrap().shape, [0, 3]);
+        // A transposed view is copied into row-major order first.
+        let flat = reshape(&permute(&m23(), None).unwrap(), &[-1]).unwrap();
+        assert_eq!(flat.to_vec(), [1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn permute_swaps_strides_without_copying() {
+        let a = iota(&[2, 3, 4]);
+        let p = permute(&a, Some(&[2, 0, 1])).unwrap();
+        assert_eq!((p.shape.clone(), p.strides.clone()), (vec![4, 2, 3], vec![1, 12, 4]));
+        assert!(Arc::ptr_eq(&p.data, &a.data));
+        let v = p.to_vec();
+        for (i, j, k) in [(0, 0, 0), (3, 1, 2), (1, 0, 2), (2, 1, 0)] {
+            assert_eq!(v[i * 6 + j * 3 + k], (j * 12 + k * 4 + i) as f64, "p[{i},{j},{k}]");
+        }
+        let tr = permute(&a, None).unwrap();
+        assert_eq!((tr.shape, tr.strides), (vec![4, 3, 2], vec![1, 4, 12]));
+        assert_eq!(permute(&m23(), None).unwrap().to_vec(), [1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn concat_stack_and_gather() {
+        let a = m23();
+        let c = concat(&[a.clone(), t(&[1, 3], vec![7.0, 8.0, 9.0])], 0).unwrap();
+        assert_eq!((c.shape.clone(), c.to_vec()), (vec![3, 3], (1..=9).map(f64::from).collect::<Vec<_>>()));
+        let c = concat(&[a.clone(), t(&[2, 1], vec![10.0, 20.0])], -1).unwrap();
+        assert_eq!((c.shape.clone(), c.to_vec()), (vec![2, 4], vec![1.0, 2.0, 3.0, 10.0, 4.0, 5.0, 6.0, 20.0]));
+
+        let s = stack(&[a.clone(), neg(&a)], 0).unwrap();
+        assert_eq!((s.shape.clone(), s.to_vec()), (vec![2, 2, 3], vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, -1.0, -2.0, -3.0, -4.0, -5.0, -6.0]));
+        let s = stack(&[a.clone(), neg(&a)], -1).unwrap();
+        assert_eq!((s.shape.clone(), s.to_vec()), (vec![2, 3, 2], vec![1.0, -1.0, 2.0, -2.0, 3.0, -3.0, 4.0, -4.0, 5.0, -5.0, 6.0, -6.0]));
+
+        let g = gather(&a, &[2, -3, 2], 1).unwrap();
+        assert_eq!((g.shape.clone(), g.to_vec()), (vec![2, 3], vec![3.0, 1.0, 3.0, 6.0, 4.0, 6.0]));
+        let g = gather(&a, &[1], 0).unwrap();
+        assert_eq!((g.shape.clone(), g.to_vec()), (vec![1, 3], vec![4.0, 5.0, 6.0]));
+        let g = gather(&permute(&a, None).unwrap(), &[2, 0], 0).unwrap();
+        assert_eq!((g.shape.clone(), g.to_vec()), (vec![2, 2], vec![3.0, 6.0, 1.0, 4.0]));
+    }
+
+    #[test]
+    fn rejects_invalid_layouts() {
+        let a = m23();
+        assert!(reshape(&a, &[-1, -1]).is_err());
+        assert!(reshape(&a, &[-2, 3]).is_err());
+        assert!(reshape(&a, &[4, 4]).is_err());
+        assert!(reshape(&a, &[4, -1]).is_err());
+        let err = reshape(&a, &[isize::MAX, isize::MAX, 0]).unwrap_err();
+        assert!(err.contains("overflows"), "{err}");
+        let err = reshape(&iota(&[0]), &[isize::MAX, 4, -1]).unwrap_err();
+        assert!(err.contains("overflows"), "{err}");
+
+        assert!(permute(&a, Some(&[0, 0])).is_err());
+        assert!(permute(&a, Some(&[0])).is_err());
+        assert!(permute(&a, Some(&[0, 2])).is_err());
+
+        assert!(concat(&[], 0).is_err());
+        assert!(concat(&[Tensor::scalar(1.0)], 0).is_err());
+        assert!(concat(&[a.clone(), iota(&[2, 2])], 0).is_err());
+        assert!(concat(&[a.clone(), a.clone()], 2).is_err());
+        assert!(stack(&[a.clone(), iota(&[3, 2])], 0).is_err());
+        assert!(stack(std::slice::from_ref(&a), 3).is_err());
+
+        assert!(gather(&a, &[3], 1).is_err());
+        assert!(gather(&a, &[-4], 1).is_err());
+        assert!(gather(&a, &[0], 2).is_err());
+    }
+}
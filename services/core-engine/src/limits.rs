@@ -0,0 +1,228 @@
+// ── Plan limits ─────────────────────────────────────────────
+// Per-request caps on body size, vector length, matrix dimension, batch
+// size and benchmark work, chosen by the caller's plan. The gateway sets
+// `X-Plan` from the verified token (and strips any the client sent), the
+// same way it sets `X-User-Id`; requests without one get SIMD_DEFAULT_PLAN
+// (default Free). Each plan's built-in caps can be overridden through
+// SIMD_LIMITS, a JSON object of plan → partial limits, e.g.
+// `{"Free": {"max_elements": 250000}}`. A request over a cap is refused with
+// 413 before any work is done.
+use crate::bench::Suite;
+use crate::solver;
+use crate::telemetry::Json;
+use crate::{api_err, ApiError, AppState, BenchmarkRequest, MatrixRequest};
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Plan { Free, Pro, Enterprise }
+
+impl Plan {
+    const ALL: [Plan; 3] = [Plan::Free, Plan::Pro, Plan::Enterprise];
+
+    fn parse(s: &str) -> Option<Plan> {
+        Plan::ALL.into_iter().find(|p| p.name().eq_ignore_ascii_case(s.trim()))
+    }
+
+    fn name(self) -> &'static str {
+        match self { Plan::Free => "Free", Plan::Pro => "Pro", Plan::Enterprise => "Enterprise" }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct Limits {
+    #[serde(skip_deserializing, default = "free")]
+    pub plan: Plan,
+    /// Request body, checked against Content-Length.
+    pub max_body_bytes: usize,
+    /// Input elements: vector lengths, matrix cells, sparse non-zeros,
+    /// tensor and expression inputs.
+    pub max_elements: usize,
+    /// Rows or columns of any dense matrix.
+    pub max_matrix_dim: usize,
+    /// Items in a batch request.
+    pub max_batch_items: usize,
+    /// Benchmark and roofline work: elements processed (FLOPs for matrix
+    /// operations) over every warmup and timed iteration; see `Suite::work`.
+    pub max_benchmark_work: u64,
+}
+
+fn free() -> Plan { Plan::Free }
+
+const MIB: usize = 1 << 20;
+
+fn defaults(plan: Plan) -> Limits {
+    match plan {
+        Plan::Free => Limits {
+            plan, max_body_bytes: 16 * MIB, max_elements: 1 << 22, max_matrix_dim: 1024,
+            max_batch_items: 100_000, max_benchmark_work: 2_000_000_000,
+        },
+        Plan::Pro => Limits {
+            plan, max_body_bytes: 128 * MIB, max_elements: 1 << 24, max_matrix_dim: 4096,
+            max_batch_items: 1_000_000, max_benchmark_work: 50_000_000_000,
+        },
+        Plan::Enterprise => Limits {
+            plan, max_body_bytes: 512 * MIB, max_elements: 1 << 26, max_matrix_dim: 8192,
+            max_batch_items: 10_000_000, max_benchmark_work: 500_000_000_000,
+        },
+    }
+}
+
+pub struct Plans { limits: [Limits; 3], default: Plan }
+
+impl Default for Plans {
+    fn default() -> Self {
+        let mut limits = Plan::ALL.map(defaults);
+        if let Ok(raw) = std::env::var("SIMD_LIMITS") {
+            match overrides(&raw, &mut limits) {
+                Ok(()) => {}
+                Err(e) => tracing::warn!("Ignoring SIMD_LIMITS: {e}"),
+            }
+        }
+        let default = std::env::var("SIMD_DEFAULT_PLAN").ok().and_then(|p| Plan::parse(&p)).unwrap_or(Plan::Free);
+        Plans { limits, default }
+    }
+}
+
+/// Merges `{"Plan": {field: value}}` over the built-in caps.
+fn overrides(raw: &str, limits: &mut [Limits; 3]) -> Result<(), String> {
+    let map: serde_json::Map<String, serde_json::Value> = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+    let mut merged = *limits;
+    for (name, fields) in map {
+        let plan = Plan::parse(&name).ok_or_else(|| format!("unknown plan '{name}'"))?;
+        let slot = &mut merged[plan as usize];
+        let mut v = serde_json::to_value(*slot).map_err(|e| e.to_string())?;
+        let serde_json::Value::Object(fields) = fields else { return Err(format!("{name}: expected an object")) };
+        for (k, x) in fields {
+            if k == "plan" || v.get(&k).is_none() { return Err(format!("{name}: unknown limit '{k}'")); }
+            v[k] = x;
+        }
+        *slot = Limits { plan, ..serde_json::from_value(v).map_err(|e| format!("{name}: {e}"))? };
+    }
+    *limits = merged;
+    Ok(())
+}
+
+impl Plans {
+    pub fn get(&self, plan: Plan) -> Limits { self.limits[plan as usize] }
+
+    /// The caller's limits, from `X-Plan`.
+    pub fn resolve(&self, headers: &HeaderMap) -> Limits {
+        let plan = headers.get("x-plan").and_then(|v| v.to_str().ok()).and_then(Plan::parse);
+        self.get(plan.unwrap_or(self.default))
+    }
+
+    /// The largest body any plan accepts; the router's hard cap.
+    pub fn max_body_bytes(&self) -> usize {
+        self.limits.iter().map(|l| l.max_body_bytes).max().unwrap_or(0)
+    }
+}
+
+#[axum::async_trait]
+impl FromRequestParts<Arc<AppState>> for Limits {
+    type Rejection = std::convert::Infallible;
+    async fn from_request_parts(parts: &mut Parts, s: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        Ok(s.limits.resolve(&parts.headers))
+    }
+}
+
+impl Limits {
+    fn exceeded(&self, what: &str, value: u128, field: &str, max: u128) -> Result<(), ApiError> {
+        if value <= max { return Ok(()); }
+        Err(api_err(StatusCode::PAYLOAD_TOO_LARGE, "Request exceeds plan limits",
+            Some(format!("{what} is {value}; the {} plan's {field} is {max}", self.plan.name()))))
+    }
+
+    pub fn elements(&self, what: &str, n: usize) -> Result<(), ApiError> {
+        self.exceeded(what, n as u128, "max_elements", self.max_elements as u128)
+    }
+
+    /// Both dimensions and the cell count of a dense matrix.
+    pub fn matrix(&self, what: &str, rows: usize, cols: usize) -> Result<(), ApiError> {
+        self.exceeded(&format!("{what} rows"), rows as u128, "max_matrix_dim", self.max_matrix_dim as u128)?;
+        self.exceeded(&format!("{what} columns"), cols as u128, "max_matrix_dim", self.max_matrix_dim as u128)?;
+        self.elements(&format!("{what} cells"), rows * cols)
+    }
+
+    /// Every operand of a matrix request. Sparse dimensions are bounded by
+    /// `max_elements` (dense vectors of that length get allocated),
+    /// `to_dense` by its output and GMRES by its Krylov basis.
+    pub fn matrix_request(&self, req: &MatrixRequest) -> Result<(), ApiError> {
+        let dense = |m: &[Vec<f64>]| (m.len(), m.iter().map(Vec::len).max().unwrap_or(0));
+        let (rows, cols) = dense(&req.matrix_a);
+        self.matrix("matrix_a", rows, cols)?;
+        if let Some(b) = &req.matrix_b {
+            let (rows, cols) = dense(b);
+            self.matrix("matrix_b", rows, cols)?;
+        }
+        if let Some(sp) = &req.sparse_a {
+            let [rows, cols] = sp.shape();
+            self.elements("sparse_a non-zeros", sp.nnz())?;
+            self.elements("sparse_a rows", rows)?;
+            self.elements("sparse_a columns", cols)?;
+            if req.operation == "to_dense" { self.matrix("dense result", rows, cols)?; }
+            if req.operation == "spmm" {
+                let b_cols = req.matrix_b.as_ref().map_or(0, |b| dense(b).1);
+                self.elements("spmm result cells", rows.saturating_mul(b_cols))?;
+            }
+        }
+        if let Some(v) = &req.sparse_vector {
+            self.elements("sparse_vector size", v.size)?;
+        }
+        if req.operation == "solve" && req.method.as_deref() == Some("gmres") {
+            let n = req.sparse_a.as_ref().map_or(req.matrix_a.len(), |sp| sp.shape()[0]);
+            let restart = solver::gmres_restart(req.restart.unwrap_or(solver::DEFAULT_RESTART), n,
+                req.max_iterations.unwrap_or(solver::DEFAULT_MAX_ITERATIONS));
+            self.elements("gmres Krylov basis", (restart + 1).saturating_mul(n))?;
+        }
+        self.elements("vector", req.vector.as_ref().map_or(0, Vec::len))?;
+        self.elements("x0", req.x0.as_ref().map_or(0, Vec::len))
+    }
+
+    pub fn batch(&self, what: &str, items: usize) -> Result<(), ApiError> {
+        self.exceeded(what, items as u128, "max_batch_items", self.max_batch_items as u128)
+    }
+
+    /// The requested sizes, before a sweep is expanded into them.
+    pub fn benchmark_request(&self, req: &BenchmarkRequest) -> Result<(), ApiError> {
+        for &size in req.sizes.iter().flatten().chain(&req.size) { self.elements("benchmark size", size)?; }
+        if let Some(sweep) = &req.sweep { self.elements("benchmark sweep max", sweep.max)?; }
+        Ok(())
+    }
+
+    /// Each size is allocated as a vector, so it counts against
+    /// `max_elements` as well as the total work.
+    pub fn benchmark(&self, suite: &Suite) -> Result<(), ApiError> {
+        for &size in &suite.sizes { self.elements("benchmark size", size)?; }
+        self.work("benchmark work", suite.work())
+    }
+
+    pub fn work(&self, what: &str, work: u128) -> Result<(), ApiError> {
+        self.exceeded(what, work, "max_benchmark_work", self.max_benchmark_work as u128)
+    }
+}
+
+/// Refuses bodies whose declared length is over the caller's plan cap.
+/// Bodies without Content-Length stop at the router-wide cap instead.
+pub async fn body_limit(State(s): State<Arc<AppState>>, req: Request, next: Next) -> Result<Response, ApiError> {
+    let limits = s.limits.resolve(req.headers());
+    if let Some(len) = req.headers().get(header::CONTENT_LENGTH).and_then(|v| v.to_str().ok()?.parse::<u128>().ok()) {
+        limits.exceeded("request body (bytes)", len, "max_body_bytes", limits.max_body_bytes as u128)?;
+    }
+    Ok(next.run(req).await)
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/v1/simd/limits", get(limits))
+}
+
+/// The caller's effective limits.
+async fn limits(limits: Limits) -> Json<Limits> { Json(limits) }
@@ -1,28 +1,77 @@
 #![allow(dead_code)]
-use axum::{extract::State, response::Json, routing::{get, post}, Router};
+mod ann;
+mod baseline;
+mod batch;
+mod bench;
+mod collections;
+mod cost;
+mod expr;
+mod geometry;
+mod history;
+#[cfg(feature = "jit")]
+mod jit;
+mod jobs;
+mod layout;
+mod limits;
+mod metrics;
+mod pairwise;
+mod perf;
+mod pool;
+mod roofline;
+mod simd;
+mod solver;
+mod sparse;
+mod stats;
+mod telemetry;
+mod tensor;
+mod topology;
+
+use axum::{extract::{DefaultBodyLimit, State}, http::StatusCode, middleware, routing::{get, post}, Router};
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
+use telemetry::Json;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
 // ── State ───────────────────────────────────────────────────
 struct AppState {
     start_time: Instant,
-    stats: Mutex<Stats>,
+    stats: Stats,
+    metrics: metrics::Metrics,
+    collections: collections::Collections,
+    jobs: jobs::Jobs,
+    history: history::History,
+    baselines: baseline::Baselines,
+    limits: limits::Plans,
+    pool: pool::Pool,
+    #[cfg(feature = "jit")]
+    jit: jit::JitCache,
 }
 
+#[derive(Default)]
 struct Stats {
-    total_computes: u64,
-    total_matrix_ops: u64,
-    total_benchmarks: u64,
+    total_computes: AtomicU64,
+    total_matrix_ops: AtomicU64,
+    total_benchmarks: AtomicU64,
 }
 
 // ── Types ───────────────────────────────────────────────────
+#[derive(Serialize)]
+struct Err { error: String, #[serde(skip_serializing_if = "Option::is_none")] details: Option<String> }
+type ApiError = (StatusCode, Json<Err>);
+
+fn api_err(code: StatusCode, error: &str, details: Option<String>) -> ApiError {
+    (code, Json(Err { error: error.into(), details }))
+}
+
 #[derive(Serialize)]
 struct Health { status: String, version: String, uptime_secs: u64, total_ops: u64 }
 
 // Compute
+const COMPUTE_OPERATIONS: &[&str] = &["add", "mul", "fma", "dot_product", "normalize", "clamp", "distance", "lerp", "min", "max"];
+
 #[derive(Deserialize)]
 struct ComputeRequest {
     operation: String,
@@ -33,32 +82,86 @@ struct ComputeRequest {
 #[derive(Serialize)]
 struct ComputeResponse {
     operation: String, result: serde_json::Value, scalar_result: Option<f64>,
-    input_size: usize, simd_lanes_used: u32, elapsed_ns: u128, throughput_gflops: f64,
+    input_size: usize, simd_lanes_used: u32, elapsed_ns: u128,
+    flops: f64, bytes_processed: u64, throughput_gflops: f64, gbytes_per_sec: f64,
 }
 
 // Matrix
+const MATRIX_OPERATIONS: &[&str] = &[
+    "multiply", "transpose", "determinant", "inverse", "add", "scale", "pairwise_distance", "pairwise_similarity",
+    "spmv", "spmm", "sparse_dot", "to_dense", "to_sparse", "convert", "solve",
+];
+
 #[derive(Deserialize)]
 struct MatrixRequest {
     operation: String,
+    #[serde(default)]
     matrix_a: Vec<Vec<f64>>,
     matrix_b: Option<Vec<Vec<f64>>>,
     scalar: Option<f64>,
+    metric: Option<String>,
+    condensed: Option<bool>,
+    sparse_a: Option<sparse::SparseInput>,
+    sparse_vector: Option<sparse::SparseVector>,
+    vector: Option<Vec<f64>>,
+    /// Output format for sparse results: coo, csr (default) or csc.
+    format: Option<String>,
+    // Iterative solve
+    method: Option<String>,
+    preconditioner: Option<String>,
+    tolerance: Option<f64>,
+    max_iterations: Option<usize>,
+    restart: Option<usize>,
+    x0: Option<Vec<f64>>,
 }
 #[derive(Serialize)]
 struct MatrixResponse {
     operation: String, result: serde_json::Value, scalar_result: Option<f64>,
     dimensions: String, elapsed_ns: u128,
+    flops: f64, bytes_processed: u64, throughput_gflops: f64, gbytes_per_sec: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solver: Option<solver::SolveInfo>,
 }
 
 // Benchmark
 #[derive(Deserialize)]
-struct BenchmarkRequest { size: Option<usize>, iterations: Option<usize> }
-#[derive(Serialize)]
-struct BenchmarkResponse { simd_capability: String, benchmarks: Vec<BenchmarkResult> }
+struct BenchmarkRequest {
+    size: Option<usize>, iterations: Option<usize>, warmup: Option<usize>,
+    // Suite axes; each defaults to the single-point suite (vector ops, `size`, f64, one thread).
+    operations: Option<Vec<String>>,
+    sizes: Option<Vec<usize>>,
+    sweep: Option<bench::Sweep>,
+    dtypes: Option<Vec<String>>,
+    threads: Option<Vec<usize>>,
+    /// Kernel variants to time (default: every one this CPU supports).
+    variants: Option<Vec<String>>,
+    /// Read hardware performance counters around each run (default true).
+    counters: Option<bool>,
+    // Regression checks
+    /// Save this run as the named baseline, replacing any earlier one.
+    save_baseline: Option<String>,
+    /// Compare this run against the named baseline.
+    compare_to: Option<String>,
+    /// Median slowdown beyond which a significant change is a regression (default 0.05).
+    threshold: Option<f64>,
+    /// Significance level of the Mann-Whitney test (default 0.05).
+    alpha: Option<f64>,
+}
 #[derive(Serialize)]
-struct BenchmarkResult {
-    operation: String, size: usize, iterations: usize,
-    total_ns: u128, per_op_ns: u128, throughput_gflops: f64,
+struct BenchmarkResponse {
+    simd_capability: String,
+    /// Kernel variants timed on this CPU, scalar first and widest last.
+    variants: Vec<String>,
+    grid: bench::Axes,
+    /// Whether `counters` could be read, and why not; absent when not requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    perf_counters: Option<perf::Status>,
+    scalar_total_ns: Option<u128>, simd_total_ns: Option<u128>, speedup_ratio: Option<f64>,
+    benchmarks: Vec<bench::BenchmarkResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comparison: Option<baseline::Comparison>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    saved_baseline: Option<String>,
 }
 
 // Capabilities
@@ -66,48 +169,82 @@ struct BenchmarkResult {
 struct Capabilities {
     arch: String, simd_width: u32, max_vector_size: u32,
     features: Vec<String>, supported_types: Vec<String>,
+    /// Model, cores, NUMA nodes and caches.
+    #[serde(flatten)]
+    topology: &'static topology::Topology,
+    /// Matrix kernel tiles derived from the cache sizes.
+    gemm_blocking: simd::GemmBlocking,
 }
 
-#[derive(Serialize)]
-struct StatsResponse { total_computes: u64, total_matrix_ops: u64, total_benchmarks: u64 }
-
 // ── Main ────────────────────────────────────────────────────
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::try_from_default_env()
-            .unwrap_or_else(|_| "simd_engine=info".into()))
-        .init();
+    let tracer = telemetry::init();
     let state = Arc::new(AppState {
         start_time: Instant::now(),
-        stats: Mutex::new(Stats { total_computes: 0, total_matrix_ops: 0, total_benchmarks: 0 }),
+        stats: Default::default(),
+        metrics: Default::default(),
+        collections: Default::default(),
+        jobs: Default::default(),
+        history: Default::default(),
+        baselines: Default::default(),
+        limits: Default::default(),
+        pool: Default::default(),
+        #[cfg(feature = "jit")]
+        jit: Default::default(),
     });
+    jobs::start(state.clone());
+    if state.history.enabled() { tracing::info!("Recording compute history to Postgres"); }
     let cors = CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any);
-    let app = Router::new()
-        .route("/health", get(health))
+    // Endpoints that run kernels go through the compute pool.
+    let kernels = Router::new()
         .route("/api/v1/simd/compute", post(compute))
         .route("/api/v1/simd/matrix", post(matrix))
         .route("/api/v1/simd/benchmark", post(benchmark))
+        .merge(baseline::router())
+        .merge(batch::router())
+        .merge(collections::router())
+        .merge(expr::router())
+        .merge(roofline::router())
+        .merge(tensor::router())
+        .route_layer(middleware::from_fn_with_state(state.clone(), pool::offload));
+    let app = Router::new()
+        .route("/health", get(health))
         .route("/api/v1/simd/capabilities", get(capabilities))
-        .route("/api/v1/simd/stats", get(stats))
-        .layer(cors).layer(TraceLayer::new_for_http()).with_state(state);
+        .merge(kernels)
+        .merge(history::router())
+        .merge(jobs::router())
+        .merge(limits::router())
+        .merge(metrics::router())
+        .merge(stats::router())
+        .layer(middleware::from_fn_with_state(state.clone(), limits::body_limit))
+        .layer(DefaultBodyLimit::max(state.limits.max_body_bytes()))
+        .layer(cors)
+        .layer(TraceLayer::new_for_http().make_span_with(telemetry::request_span).on_response(telemetry::record_status))
+        .with_state(state);
     let addr = std::env::var("SIMD_ADDR").unwrap_or_else(|_| "0.0.0.0:8081".into());
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
     tracing::info!("SIMD Compute Engine on {addr}");
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app).with_graceful_shutdown(telemetry::shutdown_signal()).await.unwrap();
+    if let Some(t) = tracer { let _ = t.shutdown(); }
 }
 
 // ── Handlers ────────────────────────────────────────────────
 async fn health(State(s): State<Arc<AppState>>) -> Json<Health> {
-    let st = s.stats.lock().unwrap();
+    let st = &s.stats;
     Json(Health {
         status: "ok".into(), version: env!("CARGO_PKG_VERSION").into(),
         uptime_secs: s.start_time.elapsed().as_secs(),
-        total_ops: st.total_computes + st.total_matrix_ops,
+        total_ops: st.total_computes.load(Ordering::Relaxed) + st.total_matrix_ops.load(Ordering::Relaxed),
     })
 }
 
-async fn compute(State(s): State<Arc<AppState>>, Json(req): Json<ComputeRequest>) -> Json<ComputeResponse> {
+async fn compute(State(s): State<Arc<AppState>>, user: history::User, limits: limits::Limits, Json(req): Json<ComputeRequest>) -> Result<Json<ComputeResponse>, ApiError> {
+    let call = s.metrics.call("compute", &req.operation);
+    tracing::info_span!("validate").in_scope(|| {
+        limits.elements("data_a", req.data_a.len())?;
+        limits.elements("data_b", req.data_b.as_ref().map_or(0, Vec::len))
+    })?;
     let t = Instant::now();
     let a = &req.data_a;
     let b = req.data_b.as_deref().unwrap_or(&[]);
@@ -117,6 +254,7 @@ async fn compute(State(s): State<Arc<AppState>>, Json(req): Json<ComputeRequest>
     // Simulate SIMD lane width based on architecture
     let simd_lanes: u32 = if cfg!(target_arch = "x86_64") { 8 } else { 4 };
 
+    let kernel = tracing::info_span!("kernel", operation = %req.operation, elements = n).entered();
     let (result_vec, scalar_result): (Option<Vec<f64>>, Option<f64>) = match req.operation.as_str() {
         "add" => {
             let r: Vec<f64> = a.iter().zip(b.iter().chain(std::iter::repeat(&0.0)))
@@ -177,12 +315,19 @@ async fn compute(State(s): State<Arc<AppState>>, Json(req): Json<ComputeRequest>
         }
         _ => (Some(a.clone()), None),
     };
+    drop(kernel);
 
     let elapsed_ns = t.elapsed().as_nanos();
-    let flops = n.max(1) as f64;
-    let throughput = if elapsed_ns > 0 { flops / elapsed_ns as f64 } else { 0.0 };
-
-    s.stats.lock().unwrap().total_computes += 1;
+    let cost = cost::vector(&req.operation, n, b.len(), 8);
+    let throughput = cost.gflops(elapsed_ns);
+    call.finish(true, n + b.len(), cost);
+
+    s.stats.total_computes.fetch_add(1, Ordering::Relaxed);
+    history::record_job(&s, history::JobRecord {
+        user, operation: history::compute_operation(&req.operation), input_dimensions: 1,
+        element_count: n + b.len(), compute_time_ns: elapsed_ns, throughput_gflops: Some(throughput),
+        status: "completed", results: serde_json::json!({ "input_size": n, "scalar_result": scalar_result }),
+    });
 
     let result_json = if let Some(v) = result_vec {
         serde_json::Value::Array(v.iter().map(|x| serde_json::Value::from(*x)).collect())
@@ -190,38 +335,76 @@ async fn compute(State(s): State<Arc<AppState>>, Json(req): Json<ComputeRequest>
         serde_json::Value::Null
     };
 
-    Json(ComputeResponse {
+    Ok(Json(ComputeResponse {
         operation: req.operation, result: result_json, scalar_result,
-        input_size: n, simd_lanes_used: simd_lanes, elapsed_ns, throughput_gflops: throughput,
-    })
+        input_size: n, simd_lanes_used: simd_lanes, elapsed_ns,
+        flops: cost.flops, bytes_processed: cost.bytes as u64, throughput_gflops: throughput,
+        gbytes_per_sec: cost.gbytes_per_sec(elapsed_ns),
+    }))
 }
 
 #[allow(clippy::needless_range_loop)]
-async fn matrix(State(s): State<Arc<AppState>>, Json(req): Json<MatrixRequest>) -> Json<MatrixResponse> {
+async fn matrix(State(s): State<Arc<AppState>>, user: history::User, limits: limits::Limits, Json(req): Json<MatrixRequest>) -> Result<Json<MatrixResponse>, ApiError> {
+    let call = s.metrics.call("matrix", &req.operation);
+    tracing::info_span!("validate").in_scope(|| limits.matrix_request(&req))?;
     let t = Instant::now();
     let a = &req.matrix_a;
     let rows_a = a.len();
     let cols_a = a.first().map(|r| r.len()).unwrap_or(0);
     let scalar = req.scalar.unwrap_or(1.0);
+    let mut solve_info = None;
 
+    let kernel = tracing::info_span!("kernel", operation = %req.operation, rows = rows_a, cols = cols_a).entered();
     let (result_json, scalar_result, dims) = match req.operation.as_str() {
         "multiply" => {
             let b = req.matrix_b.as_deref().unwrap_or(&[]);
             let rows_b = b.len();
             let cols_b = b.first().map(|r| r.len()).unwrap_or(0);
-            let mut result = vec![vec![0.0f64; cols_b]; rows_a];
-            for i in 0..rows_a {
-                for j in 0..cols_b {
-                    let mut sum = 0.0;
-                    for k in 0..cols_a.min(rows_b) {
-                        sum += a[i][k] * b[k][j];
-                    }
-                    result[i][j] = sum;
-                }
-            }
+            let k = cols_a.min(rows_b);
+            let fa = pairwise::flatten(a, rows_a, k);
+            let fb = pairwise::flatten(b, k, cols_b);
+            let mut fc = vec![0.0f64; rows_a * cols_b];
+            simd::gemm_f64(rows_a, cols_b, k, &fa, &fb, &mut fc);
+            let result: Vec<Vec<f64>> = (0..rows_a).map(|i| fc[i * cols_b..(i + 1) * cols_b].to_vec()).collect();
             let json = mat_to_json(&result);
             (json, None, format!("{rows_a}x{cols_a} * {rows_b}x{cols_b}"))
         }
+        "pairwise_distance" | "pairwise_similarity" => {
+            let default_metric = if req.operation == "pairwise_distance" { "l2" } else { "cosine_similarity" };
+            let metric = req.metric.as_deref().unwrap_or(default_metric);
+            let metric = if req.operation == "pairwise_similarity" && metric == "cosine" { "cosine_similarity" } else { metric };
+            if req.operation == "pairwise_similarity" && !matches!(metric, "cosine_similarity" | "dot") {
+                return Err(api_err(StatusCode::BAD_REQUEST, "Not a similarity metric", Some(format!("{metric} is not cosine or dot; use pairwise_distance for distances"))));
+            }
+            let b = req.matrix_b.as_deref().filter(|b| *b != a.as_slice());
+            let condensed = req.condensed.unwrap_or(false);
+            pairwise::validate(a, b, condensed)
+                .map_err(|e| api_err(StatusCode::BAD_REQUEST, "Invalid pairwise operands", Some(e)))?;
+            let rows_b = b.map(|b| b.len()).unwrap_or(rows_a);
+            let fa = pairwise::flatten(a, rows_a, cols_a);
+            let fb = b.map(|b| pairwise::flatten(b, rows_b, cols_a));
+            let Some(p) = pairwise::distance(&fa, fb.as_deref(), rows_a, rows_b, cols_a, metric, condensed) else {
+                return Err(api_err(StatusCode::BAD_REQUEST, "Unsupported metric", Some(metric.to_string())));
+            };
+            if p.condensed {
+                let json = serde_json::Value::Array(p.values.iter().map(|v| serde_json::Value::from(*v)).collect());
+                (json, None, format!("{rows_a}x{cols_a} -> condensed {}", p.values.len()))
+            } else {
+                let result: Vec<Vec<f64>> = (0..p.rows_a).map(|i| p.values[i * p.rows_b..(i + 1) * p.rows_b].to_vec()).collect();
+                (mat_to_json(&result), None, format!("{rows_a}x{cols_a} vs {rows_b}x{cols_a} -> {rows_a}x{rows_b}"))
+            }
+        }
+        "spmv" | "spmm" | "sparse_dot" | "to_dense" | "to_sparse" | "convert" => match sparse::matrix_op(&req) {
+            Ok(r) => r,
+            Err(e) => return Err(api_err(StatusCode::BAD_REQUEST, "Invalid sparse operation", Some(e))),
+        },
+        "solve" => match sparse::solve(&req) {
+            Ok((x, info, dims)) => {
+                solve_info = Some(info);
+                (serde_json::Value::from(x), None, dims)
+            }
+            Err(e) => return Err(api_err(StatusCode::BAD_REQUEST, "Invalid solve request", Some(e))),
+        },
         "transpose" => {
             let mut result = vec![vec![0.0f64; rows_a]; cols_a];
             #[allow(clippy::needless_range_loop)]
@@ -233,6 +416,9 @@ async fn matrix(State(s): State<Arc<AppState>>, Json(req): Json<MatrixRequest>)
             let json = mat_to_json(&result);
             (json, None, format!("{rows_a}x{cols_a} -> {cols_a}x{rows_a}"))
         }
+        "determinant" if a.iter().any(|r| r.len() != rows_a) => {
+            (serde_json::Value::Null, None, "unsupported: determinant needs a square matrix".into())
+        }
         "determinant" => {
             let det = matrix_determinant(a);
             (serde_json::Value::Null, Some(det), format!("{rows_a}x{cols_a}"))
@@ -265,19 +451,46 @@ async fn matrix(State(s): State<Arc<AppState>>, Json(req): Json<MatrixRequest>)
         }
         _ => (serde_json::Value::Null, None, "unknown".into()),
     };
+    drop(kernel);
 
     let elapsed_ns = t.elapsed().as_nanos();
-    s.stats.lock().unwrap().total_matrix_ops += 1;
+    s.stats.total_matrix_ops.fetch_add(1, Ordering::Relaxed);
+    let b_elems: usize = req.matrix_b.iter().flatten().map(|r| r.len()).sum();
+    let a_elems = req.sparse_a.as_ref().map(|sp| sp.nnz()).unwrap_or(rows_a * cols_a);
+    let failed = dims.starts_with("unsupported") || dims == "unknown";
+    let cost = if failed { cost::Cost::default() } else { cost::matrix(&req, solve_info.as_ref()) };
+    call.finish(!failed, a_elems + b_elems, cost);
+    history::record_job(&s, history::JobRecord {
+        user, operation: format!("matrix_{}", req.operation), input_dimensions: 2,
+        element_count: a_elems + b_elems, compute_time_ns: elapsed_ns, throughput_gflops: Some(cost.gflops(elapsed_ns)),
+        status: if failed { "failed" } else { "completed" },
+        results: serde_json::json!({ "dimensions": dims, "scalar_result": scalar_result, "solver": solve_info }),
+    });
 
-    Json(MatrixResponse {
+    Ok(Json(MatrixResponse {
         operation: req.operation, result: result_json, scalar_result,
         dimensions: dims, elapsed_ns,
-    })
+        flops: cost.flops, bytes_processed: cost.bytes as u64, throughput_gflops: cost.gflops(elapsed_ns),
+        gbytes_per_sec: cost.gbytes_per_sec(elapsed_ns), solver: solve_info,
+    }))
 }
 
-async fn benchmark(State(s): State<Arc<AppState>>, Json(req): Json<BenchmarkRequest>) -> Json<BenchmarkResponse> {
-    let size = req.size.unwrap_or(10000);
-    let iterations = req.iterations.unwrap_or(100);
+async fn benchmark(State(s): State<Arc<AppState>>, user: history::User, limits: limits::Limits, Json(req): Json<BenchmarkRequest>) -> Result<Json<BenchmarkResponse>, ApiError> {
+    let call = s.metrics.call("benchmark", "benchmark");
+    let validate = tracing::info_span!("validate").entered();
+    limits.benchmark_request(&req)?;
+    let suite = bench::Suite::from_request(&req).map_err(|e| api_err(StatusCode::BAD_REQUEST, "Invalid benchmark suite", Some(e)))?;
+    limits.benchmark(&suite)?;
+    if let Some(name) = req.save_baseline.as_deref().filter(|n| !baseline::valid_name(n)) {
+        return Err(api_err(StatusCode::BAD_REQUEST, "Invalid baseline name", Some(format!("'{name}': use [A-Za-z0-9._-], max 128 chars"))));
+    }
+    let compare_to = req.compare_to.as_deref().map(|n| baseline::lookup(&s, user, n)).transpose()?;
+    let threshold = req.threshold.unwrap_or(baseline::DEFAULT_THRESHOLD);
+    let alpha = req.alpha.unwrap_or(baseline::DEFAULT_ALPHA);
+    if !(threshold >= 0.0 && alpha > 0.0 && alpha < 1.0) {
+        return Err(api_err(StatusCode::BAD_REQUEST, "Invalid regression thresholds", Some("need threshold >= 0 and 0 < alpha < 1".into())));
+    }
+    drop(validate);
 
     let simd_cap = if cfg!(target_arch = "x86_64") {
         "AVX2 (256-bit, 8x f32)"
@@ -287,42 +500,42 @@ async fn benchmark(State(s): State<Arc<AppState>>, Json(req): Json<BenchmarkRequ
         "Scalar"
     }.to_string();
 
-    let ops = ["add", "mul", "fma", "dot_product", "normalize"];
-    let mut benchmarks = Vec::with_capacity(ops.len());
-
-    // Pre-generate test data
-    let data_a: Vec<f64> = (0..size).map(|i| (i as f64) * 0.001).collect();
-    let data_b: Vec<f64> = (0..size).map(|i| (i as f64) * 0.002 + 1.0).collect();
-
-    for op in &ops {
-        let t = Instant::now();
-        for _ in 0..iterations {
-            match *op {
-                "add" => { let _: Vec<f64> = data_a.iter().zip(data_b.iter()).map(|(a, b)| a + b).collect(); }
-                "mul" => { let _: Vec<f64> = data_a.iter().zip(data_b.iter()).map(|(a, b)| a * b).collect(); }
-                "fma" => { let _: Vec<f64> = data_a.iter().zip(data_b.iter()).map(|(a, b)| a.mul_add(*b, 1.0)).collect(); }
-                "dot_product" => { let _: f64 = data_a.iter().zip(data_b.iter()).map(|(a, b)| a * b).sum(); }
-                "normalize" => {
-                    let mag = data_a.iter().map(|x| x * x).sum::<f64>().sqrt();
-                    let _: Vec<f64> = data_a.iter().map(|x| x / mag).collect();
-                }
-                _ => {}
-            }
-        }
-        let total_ns = t.elapsed().as_nanos();
-        let per_op = total_ns / iterations as u128;
-        let flops = (size * iterations) as f64;
-        let throughput = if total_ns > 0 { flops / total_ns as f64 } else { 0.0 };
-
-        benchmarks.push(BenchmarkResult {
-            operation: op.to_string(), size, iterations,
-            total_ns, per_op_ns: per_op, throughput_gflops: throughput,
-        });
+    let benchmarks = tracing::info_span!("kernel", iterations = suite.iterations).in_scope(|| suite.run());
+
+    // Per-cell medians summed over the cells that timed a scalar variant,
+    // so the totals don't depend on `iterations`.
+    let compared: Vec<_> = benchmarks.iter().filter(|b| b.speedup_vs_scalar.is_some()).collect();
+    let (scalar_total_ns, simd_total_ns) = if compared.is_empty() {
+        (None, None)
+    } else {
+        let sum = |pick: fn(&bench::BenchmarkResult) -> f64| Some(compared.iter().map(|b| pick(b)).sum::<f64>() as u128);
+        (sum(|b| b.variants[0].stats.median_ns), sum(|b| b.stats.median_ns))
+    };
+    let speedup_ratio = scalar_total_ns.zip(simd_total_ns).filter(|&(_, simd)| simd > 0).map(|(sc, simd)| sc as f64 / simd as f64);
+    let variants: Vec<String> = suite.variants.iter().map(|v| v.name.to_string()).collect();
+    call.finish(true, benchmarks.iter().map(|b| b.size).sum(), cost::Cost::default());
+    let comparison = compare_to.map(|b| baseline::compare(&b, &benchmarks, threshold, alpha));
+    if let Some(name) = &req.save_baseline {
+        baseline::save(&s, user, baseline::Baseline::new(name, &suite, &benchmarks))?;
     }
 
-    s.stats.lock().unwrap().total_benchmarks += 1;
+    s.stats.total_benchmarks.fetch_add(1, Ordering::Relaxed);
+    history::record_benchmark(&s, history::BenchmarkRecord {
+        user, name: req.save_baseline.clone().unwrap_or_else(|| "default".into()),
+        operations: serde_json::to_value(&benchmarks).unwrap_or_default(),
+        cpu_features: serde_json::json!({ "simd_capability": simd_cap, "backend": simd::backend(), "variants": variants }),
+        scalar_time_ns: scalar_total_ns,
+        simd_time_ns: simd_total_ns,
+        speedup_ratio,
+        peak_gflops: benchmarks.iter().map(|b| b.throughput_gflops).reduce(f64::max),
+    });
 
-    Json(BenchmarkResponse { simd_capability: simd_cap, benchmarks })
+    Ok(Json(BenchmarkResponse {
+        simd_capability: simd_cap, variants, grid: suite.axes(),
+        perf_counters: suite.counters.then(perf::status),
+        scalar_total_ns, simd_total_ns, speedup_ratio, benchmarks,
+        comparison, saved_baseline: req.save_baseline,
+    }))
 }
 
 async fn capabilities() -> Json<Capabilities> {
@@ -345,15 +558,8 @@ async fn capabilities() -> Json<Capabilities> {
             "f32".into(), "f64".into(), "i32".into(), "i64".into(),
             "u32".into(), "u64".into(), "f16 (emulated)".into(),
         ],
-    })
-}
-
-async fn stats(State(s): State<Arc<AppState>>) -> Json<StatsResponse> {
-    let st = s.stats.lock().unwrap();
-    Json(StatsResponse {
-        total_computes: st.total_computes,
-        total_matrix_ops: st.total_matrix_ops,
-        total_benchmarks: st.total_benchmarks,
+        topology: topology::get(),
+        gemm_blocking: simd::gemm_blocking(),
     })
 }
 
@@ -378,16 +584,22 @@ fn matrix_determinant(m: &[Vec<f64>]) -> f64 {
             + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
         }
         _ => {
-            // Cofactor expansion along first row
-            let mut det = 0.0;
-            for j in 0..n {
-                let minor: Vec<Vec<f64>> = (1..n).map(|i|
-                    (0..n).filter(|&k| k != j).map(|k| m[i][k]).collect()
-                ).collect();
-                let sign = if j % 2 == 0 { 1.0 } else { -1.0 };
-                det += sign * m[0][j] * matrix_determinant(&minor);
+            // LU elimination with partial pivoting
+            let mut a: Vec<Vec<f64>> = m.iter().map(|r| r[..n].to_vec()).collect();
+            let mut det = 1.0;
+            for k in 0..n {
+                if k % 64 == 63 && jobs::cancelled() { return f64::NAN; }
+                let p = (k..n).max_by(|&i, &j| a[i][k].abs().total_cmp(&a[j][k].abs())).unwrap_or(k);
+                if a[p][k] == 0.0 { return 0.0; }
+                if p != k { a.swap(p, k); det = -det; }
+                let (top, rest) = a.split_at_mut(k + 1);
+                let pivot = &top[k];
+                for row in rest {
+                    let f = row[k] / pivot[k];
+                    for (x, y) in row[k..].iter_mut().zip(&pivot[k..]) { *x -= f * y; }
+                }
             }
-            det
+            det * (0..n).map(|i| a[i][i]).product::<f64>()
         }
     }
 }
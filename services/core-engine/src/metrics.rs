@@ -0,0 +1,188 @@
+// ── Metrics ─────────────────────────────────────────────────
+// Prometheus text exposition at GET /metrics. Every value is a plain
+// atomic, so recording never takes a lock. The (endpoint, operation)
+// series are fixed at startup from the operations each endpoint knows;
+// anything else is folded into operation "other", so clients cannot grow
+// the label set. Handlers open a `Call` when they start; it counts as in
+// flight until finished, and a call dropped without `finish` (an early
+// error return) counts as an error.
+use crate::cost::Cost;
+use crate::stats::OpStats;
+use crate::AppState;
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering::Relaxed};
+use std::sync::Arc;
+use std::time::Instant;
+
+const LATENCY_BUCKETS_NS: &[u64] = &[
+    1_000, 5_000, 10_000, 50_000, 100_000, 500_000, 1_000_000, 5_000_000,
+    10_000_000, 50_000_000, 100_000_000, 500_000_000, 1_000_000_000, 10_000_000_000,
+];
+const ELEMENT_BUCKETS: &[u64] = &[1, 10, 100, 1_000, 10_000, 100_000, 1_000_000, 10_000_000, 100_000_000];
+
+struct Histogram { bounds: &'static [u64], buckets: Vec<AtomicU64>, count: AtomicU64, sum: AtomicU64 }
+
+impl Histogram {
+    fn new(bounds: &'static [u64]) -> Histogram {
+        Histogram { bounds, buckets: bounds.iter().map(|_| AtomicU64::new(0)).collect(), count: AtomicU64::new(0), sum: AtomicU64::new(0) }
+    }
+
+    /// Buckets are stored non-cumulative and summed when rendered.
+    fn observe(&self, v: u64) {
+        if let Some(i) = self.bounds.iter().position(|&b| v <= b) { self.buckets[i].fetch_add(1, Relaxed); }
+        self.count.fetch_add(1, Relaxed);
+        self.sum.fetch_add(v, Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str, scale: f64) {
+        let mut cumulative = 0;
+        for (b, c) in self.bounds.iter().zip(&self.buckets) {
+            cumulative += c.load(Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{}\"}} {cumulative}", *b as f64 / scale);
+        }
+        let count = self.count.load(Relaxed);
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {count}");
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum.load(Relaxed) as f64 / scale);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {count}");
+    }
+}
+
+struct Series {
+    endpoint: &'static str,
+    operation: &'static str,
+    requests: AtomicU64,
+    errors: AtomicU64,
+    flops: AtomicU64,
+    bytes: AtomicU64,
+    latency: Histogram,
+    elements: Histogram,
+    /// Resettable, windowed figures for the stats endpoint.
+    stats: OpStats,
+}
+
+pub struct Metrics {
+    series: Vec<Series>,
+    in_flight: Vec<(&'static str, AtomicI64)>,
+}
+
+/// Endpoints and the operations each one labels separately.
+const ENDPOINTS: &[(&str, &[&str])] = &[
+    ("compute", crate::COMPUTE_OPERATIONS),
+    ("matrix", crate::MATRIX_OPERATIONS),
+    ("batch", crate::batch::OPERATIONS),
+    ("tensor", &[]),
+    ("eval", &[]),
+    ("benchmark", &[]),
+    ("roofline", &[]),
+];
+
+impl Default for Metrics {
+    fn default() -> Self {
+        let mut series = Vec::new();
+        for &(endpoint, ops) in ENDPOINTS {
+            // Endpoints without per-operation labels use their own name.
+            let names: Vec<&'static str> = if ops.is_empty() { vec![endpoint] } else { ops.iter().copied().chain(["other"]).collect() };
+            for operation in names {
+                series.push(Series {
+                    endpoint, operation,
+                    requests: AtomicU64::new(0), errors: AtomicU64::new(0),
+                    flops: AtomicU64::new(0), bytes: AtomicU64::new(0),
+                    latency: Histogram::new(LATENCY_BUCKETS_NS), elements: Histogram::new(ELEMENT_BUCKETS),
+                    stats: OpStats::default(),
+                });
+            }
+        }
+        Metrics { series, in_flight: ENDPOINTS.iter().map(|&(e, _)| (e, AtomicI64::new(0))).collect() }
+    }
+}
+
+impl Metrics {
+    /// Starts timing a call of `operation` on `endpoint`.
+    pub fn call(&self, endpoint: &str, operation: &str) -> Call<'_> {
+        let in_endpoint = || self.series.iter().filter(move |s| s.endpoint == endpoint);
+        let series = in_endpoint().find(|s| s.operation == operation)
+            .or_else(|| in_endpoint().find(|s| s.operation == "other"))
+            .or_else(|| in_endpoint().next())
+            .expect("metrics endpoint not registered");
+        let in_flight = &self.in_flight.iter().find(|(e, _)| *e == endpoint).expect("metrics endpoint not registered").1;
+        in_flight.fetch_add(1, Relaxed);
+        Call { series, in_flight, start: Instant::now(), done: false }
+    }
+
+    /// Every (endpoint, operation) series with its stats-endpoint figures.
+    pub fn operations(&self) -> impl Iterator<Item = (&'static str, &'static str, &OpStats)> {
+        self.series.iter().map(|x| (x.endpoint, x.operation, &x.stats))
+    }
+
+    fn render(&self, s: &AppState) -> String {
+        let mut out = String::with_capacity(64 * 1024);
+        let labels = |x: &Series| format!("endpoint=\"{}\",operation=\"{}\"", x.endpoint, x.operation);
+        let counter = |out: &mut String, name: &str, help: &str, get: &dyn Fn(&Series) -> u64| {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} counter");
+            for x in &self.series { let _ = writeln!(out, "{name}{{{}}} {}", labels(x), get(x)); }
+        };
+        counter(&mut out, "simd_requests_total", "Calls handled, by endpoint and operation.", &|x| x.requests.load(Relaxed));
+        counter(&mut out, "simd_request_errors_total", "Calls that failed.", &|x| x.errors.load(Relaxed));
+        counter(&mut out, "simd_flops_total", "Floating-point operations performed, from the cost model.", &|x| x.flops.load(Relaxed));
+        counter(&mut out, "simd_bytes_processed_total", "Bytes read and written, from the cost model.", &|x| x.bytes.load(Relaxed));
+
+        let _ = writeln!(out, "# HELP simd_request_duration_seconds Call latency.\n# TYPE simd_request_duration_seconds histogram");
+        for x in &self.series { x.latency.render(&mut out, "simd_request_duration_seconds", &labels(x), 1e9); }
+        let _ = writeln!(out, "# HELP simd_request_elements Input elements per call.\n# TYPE simd_request_elements histogram");
+        for x in &self.series { x.elements.render(&mut out, "simd_request_elements", &labels(x), 1.0); }
+
+        let _ = writeln!(out, "# HELP simd_requests_in_flight Calls currently running.\n# TYPE simd_requests_in_flight gauge");
+        for (e, n) in &self.in_flight { let _ = writeln!(out, "simd_requests_in_flight{{endpoint=\"{e}\"}} {}", n.load(Relaxed)); }
+
+        let _ = writeln!(out, "# HELP simd_kernel_variant Kernel variant the dispatching SIMD paths use on this CPU.\n# TYPE simd_kernel_variant gauge");
+        let _ = writeln!(out, "simd_kernel_variant{{variant=\"{}\"}} 1", crate::simd::backend());
+        let _ = writeln!(out, "# HELP simd_build_info Engine version.\n# TYPE simd_build_info gauge");
+        let _ = writeln!(out, "simd_build_info{{version=\"{}\"}} 1", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(out, "# HELP simd_uptime_seconds Seconds since the engine started.\n# TYPE simd_uptime_seconds gauge");
+        let _ = writeln!(out, "simd_uptime_seconds {}", s.start_time.elapsed().as_secs_f64());
+        out
+    }
+}
+
+/// One call in flight; see the module comment.
+pub struct Call<'a> { series: &'a Series, in_flight: &'a AtomicI64, start: Instant, done: bool }
+
+impl Call<'_> {
+    /// A call whose job or request was cancelled counts as an error, since
+    /// its result never reached anyone.
+    pub fn finish(mut self, ok: bool, elements: usize, cost: Cost) {
+        self.done = true;
+        let ok = ok && !crate::jobs::cancelled();
+        let x = self.series;
+        x.requests.fetch_add(1, Relaxed);
+        if !ok { x.errors.fetch_add(1, Relaxed); }
+        x.flops.fetch_add(cost.flops as u64, Relaxed);
+        x.bytes.fetch_add(cost.bytes as u64, Relaxed);
+        let ns = self.start.elapsed().as_nanos() as u64;
+        x.latency.observe(ns);
+        x.elements.observe(elements as u64);
+        x.stats.record(ok, elements as u64, ns);
+    }
+}
+
+impl Drop for Call<'_> {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Relaxed);
+        if !self.done {
+            self.series.requests.fetch_add(1, Relaxed);
+            self.series.errors.fetch_add(1, Relaxed);
+            let ns = self.start.elapsed().as_nanos() as u64;
+            self.series.latency.observe(ns);
+            self.series.stats.record(false, 0, ns);
+        }
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/metrics", get(metrics))
+}
+
+async fn metrics(State(s): State<Arc<AppState>>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")], s.metrics.render(&s))
+}
@@ -0,0 +1,150 @@
+// ── Pairwise distances ──────────────────────────────────────
+// All-pairs distances/similarities between the rows of A and B. L2 and
+// cosine go through the blocked GEMM (||a||² + ||b||² - 2ab); the others
+// are per-pair SIMD reductions.
+use crate::simd;
+
+pub struct Pairwise {
+    /// Row-major `rows_a × rows_b`, or the condensed upper triangle.
+    pub values: Vec<f64>,
+    pub rows_a: usize,
+    pub rows_b: usize,
+    pub condensed: bool,
+}
+
+/// Flattens a row-major matrix to `rows × cols`, zero-padding ragged rows.
+pub fn flatten(m: &[Vec<f64>], rows: usize, cols: usize) -> Vec<f64> {
+    let mut out = vec![0.0; rows * cols];
+    for (i, row) in m.iter().take(rows).enumerate() {
+        let n = row.len().min(cols);
+        out[i * cols..i * cols + n].copy_from_slice(&row[..n]);
+    }
+    out
+}
+
+/// Checks that every row of `m` has `cols` entries; `name` labels the error.
+fn check_rows(m: &[Vec<f64>], cols: usize, name: &str) -> Result<(), String> {
+    match m.iter().position(|r| r.len() != cols) {
+        Some(i) => Err(format!("{name} row {i} has {} columns, expected {cols}", m[i].len())),
+        None => Ok(()),
+    }
+}
+
+/// Column count of a matrix whose rows must all match the first.
+pub fn row_width(m: &[Vec<f64>], name: &str) -> Result<usize, String> {
+    let cols = m.first().map(|r| r.len()).unwrap_or(0);
+    check_rows(m, cols, name)?;
+    Ok(cols)
+}
+
+/// Checks the operands before any work: rows of equal length, B (when
+/// given) with A's column count, and `condensed` only for A against itself.
+/// Returns the column count.
+pub fn validate(a: &[Vec<f64>], b: Option<&[Vec<f64>]>, condensed: bool) -> Result<usize, String> {
+    let cols = row_width(a, "matrix_a")?;
+    if let Some(b) = b {
+        if condensed {
+            return Err("condensed output needs matrix_b omitted or equal to matrix_a".into());
+        }
+        check_rows(b, cols, "matrix_b")?;
+    }
+    Ok(cols)
+}
+
+/// `A · Bᵀ` through the shared GEMM kernel.
+fn gram(a: &[f64], b: &[f64], rows_a: usize, rows_b: usize, d: usize) -> Vec<f64> {
+    let bt = simd::transpose_f64(rows_b, d, b);
+    let mut g = vec![0.0; rows_a * rows_b];
+    simd::gemm_f64(rows_a, rows_b, d, a, &bt, &mut g);
+    g
+}
+
+/// `b = None` compares A with itself. Returns `None` for an unknown metric.
+pub fn distance(a: &[f64], b: Option<&[f64]>, rows_a: usize, rows_b: usize, d: usize, metric: &str, condensed: bool) -> Option<Pairwise> {
+    let same = b.is_none();
+    let b = b.unwrap_or(a);
+    let rows_b = if same { rows_a } else { rows_b };
+    let norms = |m: &[f64], rows: usize| -> Vec<f64> {
+        (0..rows).map(|i| simd::norm_sq_f64(&m[i * d..(i + 1) * d])).collect()
+    };
+
+    let mut full = match metric {
+        "l2" | "euclidean" | "sqeuclidean" => {
+            let (na, nb) = (norms(a, rows_a), norms(b, rows_b));
+            let mut g = gram(a, b, rows_a, rows_b, d);
+            let sq = metric == "sqeuclidean";
+            for i in 0..rows_a {
+                for j in 0..rows_b {
+                    let v = (na[i] + nb[j] - 2.0 * g[i * rows_b + j]).max(0.0);
+                    g[i * rows_b + j] = if sq { v } else { v.sqrt() };
+                }
+            }
+            g
+        }
+        "cosine" | "cosine_similarity" | "dot" => {
+            let mut g = gram(a, b, rows_a, rows_b, d);
+            if metric != "dot" {
+                let na: Vec<f64> = norms(a, rows_a).into_iter().map(f64::sqrt).collect();
+                let nb: Vec<f64> = norms(b, rows_b).into_iter().map(f64::sqrt).collect();
+                let dist = metric == "cosine";
+                for i in 0..rows_a {
+                    for j in 0..rows_b {
+                        let denom = na[i] * nb[j];
+                        let sim = if denom > 1e-300 { (g[i * rows_b + j] / denom).clamp(-1.0, 1.0) } else { 0.0 };
+                        g[i * rows_b + j] = if dist { 1.0 - sim } else { sim };
+                    }
+                }
+            }
+            g
+        }
+        "manhattan" | "cityblock" | "chebyshev" | "hamming" | "jaccard" => {
+            let kernel: fn(&[f64], &[f64]) -> f64 = match metric {
+                "manhattan" | "cityblock" => simd::l1_f64,
+                "chebyshev" => simd::linf_f64,
+                "hamming" => hamming,
+                _ => jaccard,
+            };
+            let mut out = vec![0.0; rows_a * rows_b];
+            for i in 0..rows_a {
+                if i % 64 == 63 && crate::jobs::cancelled() { break; }
+                let ai = &a[i * d..(i + 1) * d];
+                for j in 0..rows_b {
+                    if same && j < i { out[i * rows_b + j] = out[j * rows_b + i]; continue; }
+                    out[i * rows_b + j] = kernel(ai, &b[j * d..(j + 1) * d]);
+                }
+            }
+            out
+        }
+        _ => return None,
+    };
+
+    if same && !matches!(metric, "dot" | "cosine_similarity") {
+        for i in 0..rows_a { full[i * rows_b + i] = 0.0; }
+    }
+    if same && condensed {
+        let mut values = Vec::with_capacity(rows_a * rows_a.saturating_sub(1) / 2);
+        for i in 0..rows_a {
+            values.extend_from_slice(&full[i * rows_b + i + 1..(i + 1) * rows_b]);
+        }
+        return Some(Pairwise { values, rows_a, rows_b, condensed: true });
+    }
+    Some(Pairwise { values: full, rows_a, rows_b, condensed: false })
+}
+
+/// Fraction of positions that differ.
+fn hamming(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() { return 0.0; }
+    a.iter().zip(b).filter(|(x, y)| x != y).count() as f64 / a.len() as f64
+}
+
+/// Fraction of positions that differ among those where either is non-zero.
+fn jaccard(a: &[f64], b: &[f64]) -> f64 {
+    let (mut diff, mut union) = (0usize, 0usize);
+    for (x, y) in a.iter().zip(b) {
+        if *x != 0.0 || *y != 0.0 {
+            union += 1;
+            if x != y { diff += 1; }
+        }
+    }
+    if union == 0 { 0.0 } else { diff as f64 / union as f64 }
+}
@@ -0,0 +1,230 @@
+// ── Hardware performance counters ───────────────────────────
+// Linux perf_event counters around a benchmark run. Every counter is opened
+// on the calling thread with `inherit`, so threads the harness spawns
+// afterwards are counted too, and user space only, which an unprivileged
+// process may do at perf_event_paranoid <= 2. Counters that don't fit in the
+// PMU at once are multiplexed; their values are scaled by enabled / running
+// time the way `perf stat` does. When perf_event_open is refused (container
+// seccomp profiles, paranoid 3, no PMU in the VM) results simply carry no
+// counters and the response says why.
+use serde::Serialize;
+
+/// Per timed iteration, summed over threads.
+#[derive(Clone, Serialize)]
+pub struct Counts {
+    pub cycles: f64,
+    pub instructions: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_misses: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch_misses: Option<f64>,
+    /// Instructions per cycle.
+    pub ipc: f64,
+    pub cycles_per_element: f64,
+    pub instructions_per_element: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fp_arith: Option<FpArith>,
+}
+
+/// Floating-point arithmetic instructions retired, by vector width (Intel
+/// FP_ARITH_INST_RETIRED, which counts FMA twice).
+#[derive(Clone, Serialize)]
+pub struct FpArith {
+    pub scalar: f64,
+    pub packed_128: f64,
+    pub packed_256: f64,
+    pub packed_512: f64,
+    /// Packed over all FP arithmetic instructions.
+    pub vector_ratio: f64,
+}
+
+#[derive(Serialize)]
+pub struct Status {
+    pub available: bool,
+    /// Whether the FP-arithmetic-by-width events are counted.
+    pub fp_arith: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::{status, Counters};
+
+#[cfg(not(target_os = "linux"))]
+pub use other::{status, Counters};
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::{Counts, FpArith, Status};
+    use std::fs::File;
+    use std::io::Read;
+    use std::os::fd::{AsRawFd, FromRawFd};
+
+    const TYPE_HARDWARE: u32 = 0;
+    const TYPE_RAW: u32 = 4;
+    const HW_CPU_CYCLES: u64 = 0;
+    const HW_INSTRUCTIONS: u64 = 1;
+    const HW_CACHE_MISSES: u64 = 3;
+    const HW_BRANCH_MISSES: u64 = 5;
+    // attr.flags bits
+    const DISABLED: u64 = 1 << 0;
+    const INHERIT: u64 = 1 << 1;
+    const EXCLUDE_KERNEL: u64 = 1 << 5;
+    const EXCLUDE_HV: u64 = 1 << 6;
+    const FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+    const FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+    const FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+    const IOC_ENABLE: u64 = 0x2400;
+    const IOC_DISABLE: u64 = 0x2401;
+    const IOC_RESET: u64 = 0x2403;
+
+    /// FP_ARITH_INST_RETIRED (event 0xC7) umasks: scalar, 128-, 256- and
+    /// 512-bit packed, each covering both single and double precision.
+    const FP_ARITH: [u64; 4] = [0x03, 0x0c, 0x30, 0xc0];
+
+    /// `perf_event_attr` up to `config1` (PERF_ATTR_SIZE_VER1).
+    #[repr(C)]
+    #[derive(Default)]
+    struct Attr {
+        kind: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        wakeup_events: u32,
+        bp_type: u32,
+        config1: u64,
+    }
+
+    struct Counter(File);
+
+    impl Counter {
+        fn open(kind: u32, config: u64) -> std::io::Result<Counter> {
+            let attr = Attr {
+                kind, config,
+                size: std::mem::size_of::<Attr>() as u32,
+                read_format: FORMAT_TOTAL_TIME_ENABLED | FORMAT_TOTAL_TIME_RUNNING,
+                flags: DISABLED | INHERIT | EXCLUDE_KERNEL | EXCLUDE_HV,
+                ..Attr::default()
+            };
+            // SAFETY: `attr` is a valid perf_event_attr of the size it declares;
+            // pid 0 / cpu -1 measures the calling thread on any CPU.
+            let fd = unsafe { libc::syscall(libc::SYS_perf_event_open, &attr as *const Attr, 0, -1, -1, FLAG_FD_CLOEXEC) };
+            if fd < 0 { return Err(std::io::Error::last_os_error()); }
+            // SAFETY: the kernel just handed us this descriptor.
+            Ok(Counter(unsafe { File::from_raw_fd(fd as i32) }))
+        }
+
+        fn ioctl(&self, request: u64) {
+            // SAFETY: a perf event ioctl without an argument on a descriptor we own.
+            unsafe { libc::ioctl(self.0.as_raw_fd(), request as _, 0) };
+        }
+
+        /// The count scaled up for any time the event was multiplexed out.
+        fn read(&self) -> Option<f64> {
+            let mut buf = [0u8; 24];
+            (&self.0).read_exact(&mut buf).ok()?;
+            let word = |i: usize| u64::from_ne_bytes(buf[i * 8..i * 8 + 8].try_into().unwrap()) as f64;
+            let (value, enabled, running) = (word(0), word(1), word(2));
+            if running == 0.0 { return None; }
+            Some(value * enabled / running)
+        }
+    }
+
+    /// One run's counters: cycles and instructions are required, the rest
+    /// are best effort.
+    pub struct Counters {
+        cycles: Counter,
+        instructions: Counter,
+        cache_misses: Option<Counter>,
+        branch_misses: Option<Counter>,
+        fp_arith: Option<[Counter; 4]>,
+    }
+
+    impl Counters {
+        pub fn open() -> std::io::Result<Counters> {
+            let hw = |config| Counter::open(TYPE_HARDWARE, config);
+            let fp_arith = if crate::topology::get().vendor == "GenuineIntel" {
+                FP_ARITH.iter().map(|umask| Counter::open(TYPE_RAW, umask << 8 | 0xc7).ok()).collect::<Option<Vec<_>>>()
+                    .and_then(|v| v.try_into().ok())
+            } else { None };
+            Ok(Counters {
+                cycles: hw(HW_CPU_CYCLES)?,
+                instructions: hw(HW_INSTRUCTIONS)?,
+                cache_misses: hw(HW_CACHE_MISSES).ok(),
+                branch_misses: hw(HW_BRANCH_MISSES).ok(),
+                fp_arith,
+            })
+        }
+
+        fn each(&self, request: u64) {
+            let opt = self.cache_misses.iter().chain(&self.branch_misses).chain(self.fp_arith.iter().flatten());
+            for c in [&self.cycles, &self.instructions].into_iter().chain(opt) { c.ioctl(request); }
+        }
+
+        /// Zeroes and starts every counter, including in threads spawned since open.
+        pub fn start(&self) { self.each(IOC_RESET); self.each(IOC_ENABLE); }
+        pub fn stop(&self) { self.each(IOC_DISABLE); }
+
+        /// Totals since `start`, divided over `iterations` and `elements`.
+        pub fn read(&self, iterations: usize, elements: usize) -> Option<Counts> {
+            let per = |v: f64| v / iterations.max(1) as f64;
+            let (cycles, instructions) = (per(self.cycles.read()?), per(self.instructions.read()?));
+            let fp_arith = self.fp_arith.as_ref().and_then(|c| {
+                let [scalar, p128, p256, p512] = [c[0].read()?, c[1].read()?, c[2].read()?, c[3].read()?].map(per);
+                let total = scalar + p128 + p256 + p512;
+                Some(FpArith {
+                    scalar, packed_128: p128, packed_256: p256, packed_512: p512,
+                    vector_ratio: if total > 0.0 { (p128 + p256 + p512) / total } else { 0.0 },
+                })
+            });
+            let elements = elements.max(1) as f64;
+            Some(Counts {
+                cycles, instructions,
+                cache_misses: self.cache_misses.as_ref().and_then(Counter::read).map(per),
+                branch_misses: self.branch_misses.as_ref().and_then(Counter::read).map(per),
+                ipc: if cycles > 0.0 { instructions / cycles } else { 0.0 },
+                cycles_per_element: cycles / elements,
+                instructions_per_element: instructions / elements,
+                fp_arith,
+            })
+        }
+    }
+
+    /// Whether counters can be opened here, and why not.
+    pub fn status() -> Status {
+        match Counters::open() {
+            Ok(c) => Status { available: true, fp_arith: c.fp_arith.is_some(), reason: None },
+            Err(e) => {
+                let paranoid = std::fs::read_to_string("/proc/sys/kernel/perf_event_paranoid").ok();
+                let reason = match paranoid {
+                    Some(p) => format!("perf_event_open: {e} (perf_event_paranoid = {})", p.trim()),
+                    None => format!("perf_event_open: {e}"),
+                };
+                Status { available: false, fp_arith: false, reason: Some(reason) }
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod other {
+    use super::{Counts, Status};
+
+    pub struct Counters;
+
+    impl Counters {
+        pub fn open() -> std::io::Result<Counters> {
+            Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "perf counters need Linux"))
+        }
+        pub fn start(&self) {}
+        pub fn stop(&self) {}
+        pub fn read(&self, _iterations: usize, _elements: usize) -> Option<Counts> { None }
+    }
+
+    pub fn status() -> Status {
+        Status { available: false, fp_arith: false, reason: Some("perf counters need Linux".into()) }
+    }
+}
@@ -0,0 +1,69 @@
+// ── Compute pool ────────────────────────────────────────────
+// Endpoints that run kernels are handled on blocking threads instead of the
+// async workers, at most SIMD_COMPUTE_THREADS (default: CPU count) at a
+// time; further requests wait for a free slot. Each request has a deadline
+// of SIMD_REQUEST_TIMEOUT_MS (default 30 s), which a client can shorten but
+// not extend with `X-Timeout-Ms`, and is answered with 504 once it passes.
+// On timeout, or when the client disconnects, the computation is cancelled
+// through the same `jobs::cancelled` checkpoints that stop a cancelled job:
+// kernels bail out at their next check and hand their slot back.
+use crate::jobs::{self, Control};
+use crate::{api_err, ApiError, AppState};
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tracing::{Instrument, Span};
+
+pub struct Pool {
+    slots: Arc<Semaphore>,
+    pub threads: usize,
+    pub timeout: Duration,
+}
+
+impl Default for Pool {
+    fn default() -> Self {
+        let env = |k: &str| std::env::var(k).ok().and_then(|v| v.parse::<u64>().ok());
+        let threads = env("SIMD_COMPUTE_THREADS").map(|n| n as usize)
+            .unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(2))
+            .max(1);
+        let timeout = Duration::from_millis(env("SIMD_REQUEST_TIMEOUT_MS").unwrap_or(30_000).max(1));
+        Pool { slots: Arc::new(Semaphore::new(threads)), threads, timeout }
+    }
+}
+
+/// Cancels the request's computation when dropped: after it finished this is
+/// a no-op, otherwise the deadline passed or the client went away.
+struct CancelOnDrop(Arc<Control>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) { self.0.cancel(); }
+}
+
+/// Middleware running the rest of the request on the pool; see the module comment.
+pub async fn offload(State(s): State<Arc<AppState>>, req: Request, next: Next) -> Result<Response, ApiError> {
+    let requested = req.headers().get("x-timeout-ms").and_then(|v| v.to_str().ok()?.parse::<u64>().ok());
+    let timeout = requested.map_or(s.pool.timeout, |ms| Duration::from_millis(ms).min(s.pool.timeout));
+    let control = Arc::new(Control::default());
+    let _cancel = CancelOnDrop(control.clone());
+    let slots = s.pool.slots.clone();
+    let run = async move {
+        let slot = slots.acquire_owned().await.expect("compute pool closed");
+        let (handle, span) = (tokio::runtime::Handle::current(), Span::current());
+        tokio::task::spawn_blocking(move || {
+            let _slot = slot;
+            jobs::with_control(control, || handle.block_on(next.run(req).instrument(span)))
+        }).await
+    };
+    match tokio::time::timeout(timeout, run).await {
+        Ok(Ok(res)) => Ok(res),
+        Ok(Err(e)) => Err(api_err(StatusCode::INTERNAL_SERVER_ERROR, "Computation failed", Some(e.to_string()))),
+        Err(_) => Err(api_err(StatusCode::GATEWAY_TIMEOUT, "Computation timed out",
+            Some(format!("exceeded the {} ms deadline", timeout.as_millis())))),
+    }
+}
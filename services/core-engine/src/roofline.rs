@@ -0,0 +1,231 @@
+// ── Roofline ────────────────────────────────────────────────
+// Characterises the machine and places each benchmark operation on its
+// roofline. The memory roof is a STREAM-style probe (copy, scale, add,
+// triad over arrays far larger than the caches, best of N as STREAM
+// reports); the compute roof is a register-resident multiply-add probe per
+// instruction set. An operation with arithmetic intensity I (FLOPs/byte)
+// can reach at most min(peak, I × bandwidth).
+use crate::bench::{self, sample_parts, Suite, VECTOR_OPERATIONS};
+use crate::limits::Limits;
+use crate::{api_err, ApiError, AppState, BenchmarkRequest};
+use crate::telemetry::Json;
+use axum::{extract::State, http::StatusCode, routing::post, Router};
+use serde::{Deserialize, Serialize};
+use std::hint::black_box;
+use std::sync::Arc;
+
+#[derive(Deserialize, Default)]
+pub(crate) struct RooflineRequest {
+    /// Elements per STREAM array (default 4Mi, i.e. 32 MiB of f64 each).
+    stream_size: Option<usize>,
+    /// Timed repetitions of each probe (default 10).
+    iterations: Option<usize>,
+    /// Threads for every probe and operation (default 1).
+    threads: Option<usize>,
+    /// Operations to place on the roofline (default: vector ops and gemm).
+    operations: Option<Vec<String>>,
+    /// Elements for the vector operations (default `stream_size`).
+    size: Option<usize>,
+    /// Matrix dimension for gemm/transpose (default 256).
+    gemm_n: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct StreamResult { kernel: &'static str, bytes: usize, best_gbs: f64, median_gbs: f64 }
+
+#[derive(Serialize)]
+struct PeakResult { variant: &'static str, fused: bool, gflops_per_thread: f64, gflops: f64 }
+
+#[derive(Serialize)]
+struct Placement {
+    operation: String, size: usize, variant: String,
+    flops: f64, bytes: u64,
+    /// FLOPs per byte moved.
+    arithmetic_intensity: f64,
+    per_op_ns: u128, achieved_gflops: f64, achieved_gbs: f64,
+    /// min(peak, intensity × bandwidth).
+    attainable_gflops: f64,
+    /// "memory" left of the ridge point, "compute" right of it.
+    bound: &'static str,
+    /// Achieved over attainable, against the roof that binds.
+    efficiency: f64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct RooflineResponse {
+    threads: usize,
+    stream_size: usize,
+    stream: Vec<StreamResult>,
+    bandwidth_gbs: f64,
+    peak: Vec<PeakResult>,
+    peak_gflops: f64,
+    /// Arithmetic intensity where the memory and compute roofs meet.
+    ridge_point: f64,
+    operations: Vec<Placement>,
+    elapsed_ns: u128,
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/v1/simd/roofline", post(roofline))
+}
+
+// ── Memory roof ─────────────────────────────────────────────
+const STREAM: [(&str, usize); 4] = [("copy", 2), ("scale", 2), ("add", 3), ("triad", 3)];
+
+fn stream(size: usize, threads: usize, iterations: usize) -> Vec<StreamResult> {
+    let (mut a, mut b, mut c) = (vec![1.0f64; size], vec![2.0f64; size], vec![0.0f64; size]);
+    let chunk = size.div_ceil(threads).max(1);
+    let q = black_box(3.0);
+    let mut out = Vec::with_capacity(STREAM.len());
+    for (kernel, arrays) in STREAM {
+        let parts: Vec<_> = a.chunks_mut(chunk).zip(b.chunks_mut(chunk)).zip(c.chunks_mut(chunk)).collect();
+        let samples = sample_parts(1, iterations, None, parts, |((a, b), c)| {
+            match kernel {
+                "copy" => c.copy_from_slice(a),
+                "scale" => for (b, c) in b.iter_mut().zip(c.iter()) { *b = q * c },
+                "add" => for ((c, a), b) in c.iter_mut().zip(a.iter()).zip(b.iter()) { *c = a + b },
+                _ => for ((a, b), c) in a.iter_mut().zip(b.iter()).zip(c.iter()) { *a = b + q * c },
+            }
+            black_box((a, b, c));
+            0.0
+        });
+        let stats = bench::summarize(&samples);
+        let bytes = arrays * size * 8;
+        let gbs = |ns: f64| if ns > 0.0 { bytes as f64 / ns } else { 0.0 };
+        out.push(StreamResult { kernel, bytes, best_gbs: gbs(stats.min_ns), median_gbs: gbs(stats.median_ns) });
+    }
+    out
+}
+
+// ── Compute roof ────────────────────────────────────────────
+// `CHAINS` independent multiply-add chains per vector register width:
+// enough to cover FMA latency × issue width without spilling registers.
+const ROUNDS: usize = 1 << 16;
+const CHAINS: usize = 12;
+
+macro_rules! peak_probe {
+    ($name:ident, $feature:literal, $zero:expr, $splat:path, |$a:ident, $x:ident, $y:ident| $step:expr, $lanes:literal) => {
+        /// Returns the FLOPs performed.
+        #[target_feature(enable = $feature)]
+        pub unsafe fn $name(rounds: usize) -> f64 {
+            let ($x, $y) = ($splat(black_box(0.999_999)), $splat(black_box(1e-7)));
+            let mut acc = [$zero; CHAINS];
+            for _ in 0..rounds {
+                for $a in acc.iter_mut() { *$a = $step; }
+            }
+            black_box(acc);
+            (2 * $lanes * CHAINS * rounds) as f64
+        }
+    };
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::{black_box, CHAINS};
+    use std::arch::x86_64::*;
+    // SSE2 has no FMA, so its probe issues a separate multiply and add.
+    peak_probe!(sse2, "sse2", _mm_setzero_pd(), _mm_set1_pd, |a, x, y| _mm_add_pd(_mm_mul_pd(*a, x), y), 2);
+    peak_probe!(avx2, "avx2,fma", _mm256_setzero_pd(), _mm256_set1_pd, |a, x, y| _mm256_fmadd_pd(*a, x, y), 4);
+    peak_probe!(avx512, "avx512f", _mm512_setzero_pd(), _mm512_set1_pd, |a, x, y| _mm512_fmadd_pd(*a, x, y), 8);
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arm {
+    use super::{black_box, CHAINS};
+    use std::arch::aarch64::*;
+    peak_probe!(neon, "neon", vdupq_n_f64(0.0), vdupq_n_f64, |a, x, y| vfmaq_f64(y, *a, x), 2);
+}
+
+type Probe = unsafe fn(usize) -> f64;
+
+/// (probe, fused) for a variant from `bench::variants()`.
+fn probe(variant: &str) -> Option<(Probe, bool)> {
+    match variant {
+        #[cfg(target_arch = "x86_64")]
+        "sse2" => Some((x86::sse2, false)),
+        #[cfg(target_arch = "x86_64")]
+        "avx2" => Some((x86::avx2, true)),
+        #[cfg(target_arch = "x86_64")]
+        "avx512" => Some((x86::avx512, true)),
+        #[cfg(target_arch = "aarch64")]
+        "neon" => Some((arm::neon, true)),
+        _ => None,
+    }
+}
+
+fn peaks(threads: usize, iterations: usize) -> Vec<PeakResult> {
+    let mut out = Vec::new();
+    for v in bench::variants() {
+        let Some((f, fused)) = probe(v.name) else { continue };
+        // SAFETY: `variants()` only lists instruction sets this CPU has.
+        let flops = unsafe { f(ROUNDS) };
+        let samples = sample_parts(1, iterations, None, vec![(); threads], |_| unsafe { f(ROUNDS) });
+        let best = bench::summarize(&samples).min_ns;
+        let per_thread = if best > 0.0 { flops / best } else { 0.0 };
+        out.push(PeakResult { variant: v.name, fused, gflops_per_thread: per_thread, gflops: per_thread * threads as f64 });
+    }
+    out
+}
+
+// ── Placement ───────────────────────────────────────────────
+fn place(r: &bench::BenchmarkResult, peak: f64, bandwidth: f64) -> Placement {
+    let (flops, bytes) = (r.flops, r.bytes_processed);
+    let intensity = if bytes > 0 { flops / bytes as f64 } else { 0.0 };
+    let (achieved, gbs) = (r.throughput_gflops, r.gbytes_per_sec);
+    let memory_roof = intensity * bandwidth;
+    let (attainable, bound) = if memory_roof < peak { (memory_roof, "memory") } else { (peak, "compute") };
+    // Zero-FLOP operations (transpose) are judged on bandwidth alone.
+    let efficiency = if flops == 0.0 {
+        if bandwidth > 0.0 { gbs / bandwidth } else { 0.0 }
+    } else if attainable > 0.0 { achieved / attainable } else { 0.0 };
+    Placement {
+        operation: r.operation.clone(), size: r.size, variant: r.variant.clone(),
+        flops, bytes, arithmetic_intensity: intensity,
+        per_op_ns: r.per_op_ns, achieved_gflops: achieved, achieved_gbs: gbs,
+        attainable_gflops: attainable, bound, efficiency,
+    }
+}
+
+pub(crate) async fn roofline(State(s): State<Arc<AppState>>, limits: Limits, Json(req): Json<RooflineRequest>) -> Result<Json<RooflineResponse>, ApiError> {
+    let call = s.metrics.call("roofline", "roofline");
+    let t0 = std::time::Instant::now();
+    let bad = |e: String| api_err(StatusCode::BAD_REQUEST, "Invalid roofline request", Some(e));
+    let stream_size = req.stream_size.unwrap_or(1 << 22).max(1);
+    let iterations = req.iterations.unwrap_or(10).max(1);
+    let threads = req.threads.unwrap_or(1);
+    let operations = req.operations.unwrap_or_else(|| {
+        VECTOR_OPERATIONS.iter().map(|s| s.to_string()).chain(["gemm".to_string()]).collect()
+    });
+    let widest = bench::variants().pop().map(|v| v.name.to_string());
+    let suite = |ops: Vec<String>, size: usize| Suite::from_request(&BenchmarkRequest {
+        size: Some(size), iterations: Some(iterations), warmup: None,
+        operations: Some(ops), sizes: None, sweep: None, dtypes: None,
+        threads: Some(vec![threads]), variants: widest.clone().map(|w| vec![w]), counters: None,
+        save_baseline: None, compare_to: None, threshold: None, alpha: None,
+    });
+    let (matrix, vector): (Vec<String>, Vec<String>) = operations.into_iter().partition(|o| bench::MATRIX_OPERATIONS.contains(&o.as_str()));
+    let n = req.gemm_n.unwrap_or(256);
+    let suites = tracing::info_span!("validate").in_scope(|| {
+        let suites = [suite(vector, req.size.unwrap_or(stream_size)).map_err(bad)?, suite(matrix, n.saturating_mul(n)).map_err(bad)?];
+        limits.elements("stream_size", stream_size)?;
+        for s in &suites { limits.benchmark(s)?; }
+        let work = suites.iter().map(Suite::work).sum::<u128>() + STREAM.len() as u128 * stream_size as u128 * (iterations + 1) as u128;
+        limits.work("roofline work", work)?;
+        Ok::<_, ApiError>(suites)
+    })?;
+
+    let kernel = tracing::info_span!("kernel", threads, stream_size).entered();
+    let stream = stream(stream_size, threads, iterations);
+    let bandwidth = stream.iter().map(|s| s.best_gbs).fold(0.0, f64::max);
+    let peak = peaks(threads, iterations);
+    let peak_gflops = peak.iter().map(|p| p.gflops).fold(0.0, f64::max);
+    let operations = suites.iter().flat_map(|s| s.run()).map(|r| place(&r, peak_gflops, bandwidth)).collect();
+    drop(kernel);
+
+    call.finish(true, stream_size, Default::default());
+    Ok(Json(RooflineResponse {
+        threads, stream_size, stream, bandwidth_gbs: bandwidth, peak, peak_gflops,
+        ridge_point: if bandwidth > 0.0 { peak_gflops / bandwidth } else { 0.0 },
+        operations, elapsed_ns: t0.elapsed().as_nanos(),
+    }))
+}
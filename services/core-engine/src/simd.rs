@@ -0,0 +1,399 @@
+// ── SIMD kernels ────────────────────────────────────────────
+// Distance and reduction primitives shared by the index, matrix and
+// vector paths. On x86_64 the AVX2+FMA variants are selected at runtime;
+// everywhere else an 8-lane unrolled loop lets LLVM emit NEON/SSE.
+
+pub const LANES: usize = 8;
+
+#[inline]
+pub fn dot_f32(a: &[f32], b: &[f32]) -> f32 {
+    let n = a.len().min(b.len());
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_avx2_fma() {
+            // SAFETY: feature presence checked above, slices bounded by n.
+            return unsafe { x86::dot_f32_avx2(&a[..n], &b[..n]) };
+        }
+    }
+    dot_f32_portable(&a[..n], &b[..n])
+}
+
+#[inline]
+pub fn l2_sq_f32(a: &[f32], b: &[f32]) -> f32 {
+    let n = a.len().min(b.len());
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_avx2_fma() {
+            // SAFETY: feature presence checked above, slices bounded by n.
+            return unsafe { x86::l2_sq_f32_avx2(&a[..n], &b[..n]) };
+        }
+    }
+    l2_sq_f32_portable(&a[..n], &b[..n])
+}
+
+#[inline]
+pub fn norm_f32(a: &[f32]) -> f32 {
+    dot_f32(a, a).sqrt()
+}
+
+pub fn dot_f32_portable(a: &[f32], b: &[f32]) -> f32 {
+    let mut acc = [0.0f32; LANES];
+    let (ca, cb) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        for l in 0..LANES { acc[l] += x[l] * y[l]; }
+    }
+    let tail: f32 = ra.iter().zip(rb).map(|(x, y)| x * y).sum();
+    acc.iter().sum::<f32>() + tail
+}
+
+pub fn l2_sq_f32_portable(a: &[f32], b: &[f32]) -> f32 {
+    let mut acc = [0.0f32; LANES];
+    let (ca, cb) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        for l in 0..LANES { let d = x[l] - y[l]; acc[l] += d * d; }
+    }
+    let tail: f32 = ra.iter().zip(rb).map(|(x, y)| (x - y) * (x - y)).sum();
+    acc.iter().sum::<f32>() + tail
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline]
+pub fn has_avx2_fma() -> bool {
+    use std::sync::OnceLock;
+    static DETECTED: OnceLock<bool> = OnceLock::new();
+    *DETECTED.get_or_init(|| is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma"))
+}
+
+/// Name of the kernel path the dispatching functions take on this CPU.
+pub fn backend() -> &'static str {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_avx2_fma() { "avx2" } else { "sse2" }
+    }
+    #[cfg(target_arch = "aarch64")]
+    { "neon" }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    { "scalar" }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn hsum256(v: __m256) -> f32 {
+        let lo = _mm256_castps256_ps128(v);
+        let hi = _mm256_extractf128_ps(v, 1);
+        let s = _mm_add_ps(lo, hi);
+        let s = _mm_add_ps(s, _mm_movehl_ps(s, s));
+        let s = _mm_add_ss(s, _mm_shuffle_ps(s, s, 1));
+        _mm_cvtss_f32(s)
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn dot_f32_avx2(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len();
+        let (mut acc0, mut acc1) = (_mm256_setzero_ps(), _mm256_setzero_ps());
+        let mut i = 0;
+        while i + 16 <= n {
+            acc0 = _mm256_fmadd_ps(_mm256_loadu_ps(a.as_ptr().add(i)), _mm256_loadu_ps(b.as_ptr().add(i)), acc0);
+            acc1 = _mm256_fmadd_ps(_mm256_loadu_ps(a.as_ptr().add(i + 8)), _mm256_loadu_ps(b.as_ptr().add(i + 8)), acc1);
+            i += 16;
+        }
+        while i + 8 <= n {
+            acc0 = _mm256_fmadd_ps(_mm256_loadu_ps(a.as_ptr().add(i)), _mm256_loadu_ps(b.as_ptr().add(i)), acc0);
+            i += 8;
+        }
+        let mut sum = hsum256(_mm256_add_ps(acc0, acc1));
+        while i < n { sum += a[i] * b[i]; i += 1; }
+        sum
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn l2_sq_f32_avx2(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len();
+        let (mut acc0, mut acc1) = (_mm256_setzero_ps(), _mm256_setzero_ps());
+        let mut i = 0;
+        while i + 16 <= n {
+            let d0 = _mm256_sub_ps(_mm256_loadu_ps(a.as_ptr().add(i)), _mm256_loadu_ps(b.as_ptr().add(i)));
+            let d1 = _mm256_sub_ps(_mm256_loadu_ps(a.as_ptr().add(i + 8)), _mm256_loadu_ps(b.as_ptr().add(i + 8)));
+            acc0 = _mm256_fmadd_ps(d0, d0, acc0);
+            acc1 = _mm256_fmadd_ps(d1, d1, acc1);
+            i += 16;
+        }
+        while i + 8 <= n {
+            let d = _mm256_sub_ps(_mm256_loadu_ps(a.as_ptr().add(i)), _mm256_loadu_ps(b.as_ptr().add(i)));
+            acc0 = _mm256_fmadd_ps(d, d, acc0);
+            i += 8;
+        }
+        let mut sum = hsum256(_mm256_add_ps(acc0, acc1));
+        while i < n { let d = a[i] - b[i]; sum += d * d; i += 1; }
+        sum
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn dot_f64_avx2(a: &[f64], b: &[f64]) -> f64 {
+        let n = a.len();
+        let (mut acc0, mut acc1) = (_mm256_setzero_pd(), _mm256_setzero_pd());
+        let mut i = 0;
+        while i + 8 <= n {
+            acc0 = _mm256_fmadd_pd(_mm256_loadu_pd(a.as_ptr().add(i)), _mm256_loadu_pd(b.as_ptr().add(i)), acc0);
+            acc1 = _mm256_fmadd_pd(_mm256_loadu_pd(a.as_ptr().add(i + 4)), _mm256_loadu_pd(b.as_ptr().add(i + 4)), acc1);
+            i += 8;
+        }
+        let s = _mm256_add_pd(acc0, acc1);
+        let s = _mm_add_pd(_mm256_castpd256_pd128(s), _mm256_extractf128_pd(s, 1));
+        let mut sum = _mm_cvtsd_f64(_mm_add_sd(s, _mm_unpackhi_pd(s, s)));
+        while i < n { sum += a[i] * b[i]; i += 1; }
+        sum
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn axpy_f64_avx2(alpha: f64, x: &[f64], y: &mut [f64]) {
+        let n = x.len();
+        let va = _mm256_set1_pd(alpha);
+        let mut i = 0;
+        while i + 4 <= n {
+            let py = y.as_mut_ptr().add(i);
+            _mm256_storeu_pd(py, _mm256_fmadd_pd(va, _mm256_loadu_pd(x.as_ptr().add(i)), _mm256_loadu_pd(py)));
+            i += 4;
+        }
+        while i < n { y[i] = alpha.mul_add(x[i], y[i]); i += 1; }
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn gather_dot_f64_avx2(values: &[f64], indices: &[usize], x: &[f64]) -> f64 {
+        let n = values.len();
+        let mut acc = _mm256_setzero_pd();
+        let mut i = 0;
+        while i + 4 <= n {
+            let idx = _mm256_loadu_si256(indices.as_ptr().add(i) as *const __m256i);
+            let g = _mm256_i64gather_pd::<8>(x.as_ptr(), idx);
+            acc = _mm256_fmadd_pd(_mm256_loadu_pd(values.as_ptr().add(i)), g, acc);
+            i += 4;
+        }
+        let s = _mm_add_pd(_mm256_castpd256_pd128(acc), _mm256_extractf128_pd(acc, 1));
+        let mut sum = _mm_cvtsd_f64(_mm_add_sd(s, _mm_unpackhi_pd(s, s)));
+        while i < n { sum += values[i] * x[indices[i]]; i += 1; }
+        sum
+    }
+
+    /// Codegen trampoline: whatever `f` inlines is compiled for AVX2+FMA.
+    #[target_feature(enable = "avx2,fma")]
+    pub unsafe fn wide<R>(f: impl FnOnce() -> R) -> R { f() }
+}
+
+/// Runs `f` with AVX2+FMA code generation when the CPU has it, so the
+/// auto-vectorised loops inside (e.g. fixed-width lane arrays) use 256-bit
+/// registers instead of the SSE2 baseline.
+#[inline]
+pub fn with_wide_lanes<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_avx2_fma() {
+            // SAFETY: feature presence checked above.
+            return unsafe { x86::wide(f) };
+        }
+    }
+    f()
+}
+
+// ── f64 kernels ─────────────────────────────────────────────
+#[inline]
+pub fn dot_f64(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len().min(b.len());
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_avx2_fma() {
+            // SAFETY: feature presence checked above, slices bounded by n.
+            return unsafe { x86::dot_f64_avx2(&a[..n], &b[..n]) };
+        }
+    }
+    dot_f64_portable(&a[..n], &b[..n])
+}
+
+/// `y += alpha * x`, the GEMM inner loop.
+#[inline]
+pub fn axpy_f64(alpha: f64, x: &[f64], y: &mut [f64]) {
+    let n = x.len().min(y.len());
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_avx2_fma() {
+            // SAFETY: feature presence checked above, slices bounded by n.
+            return unsafe { x86::axpy_f64_avx2(alpha, &x[..n], &mut y[..n]) };
+        }
+    }
+    for (yi, xi) in y[..n].iter_mut().zip(&x[..n]) { *yi += alpha * xi; }
+}
+
+#[inline]
+pub fn norm_sq_f64(a: &[f64]) -> f64 {
+    dot_f64(a, a)
+}
+
+pub fn dot_f64_portable(a: &[f64], b: &[f64]) -> f64 {
+    let mut acc = [0.0f64; LANES];
+    let (ca, cb) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        for l in 0..LANES { acc[l] += x[l] * y[l]; }
+    }
+    let tail: f64 = ra.iter().zip(rb).map(|(x, y)| x * y).sum();
+    acc.iter().sum::<f64>() + tail
+}
+
+pub fn sum_f64(a: &[f64]) -> f64 {
+    let mut acc = [0.0f64; LANES];
+    let c = a.chunks_exact(LANES);
+    let tail: f64 = c.remainder().iter().sum();
+    for x in c {
+        for l in 0..LANES { acc[l] += x[l]; }
+    }
+    acc.iter().sum::<f64>() + tail
+}
+
+pub fn l1_f64(a: &[f64], b: &[f64]) -> f64 {
+    let mut acc = [0.0f64; LANES];
+    let (ca, cb) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        for l in 0..LANES { acc[l] += (x[l] - y[l]).abs(); }
+    }
+    let tail: f64 = ra.iter().zip(rb).map(|(x, y)| (x - y).abs()).sum();
+    acc.iter().sum::<f64>() + tail
+}
+
+pub fn linf_f64(a: &[f64], b: &[f64]) -> f64 {
+    let mut acc = [0.0f64; LANES];
+    let (ca, cb) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let (ra, rb) = (ca.remainder(), cb.remainder());
+    for (x, y) in ca.zip(cb) {
+        for l in 0..LANES { acc[l] = acc[l].max((x[l] - y[l]).abs()); }
+    }
+    let tail = ra.iter().zip(rb).map(|(x, y)| (x - y).abs()).fold(0.0, f64::max);
+    acc.iter().copied().fold(tail, f64::max)
+}
+
+/// `Σ values[j] · x[indices[j]]`, the sparse row × dense vector kernel.
+/// Every index must be in bounds for `x`.
+#[inline]
+pub fn gather_dot_f64(values: &[f64], indices: &[usize], x: &[f64]) -> f64 {
+    let n = values.len().min(indices.len());
+    assert!(indices[..n].iter().all(|&j| j < x.len()), "gather index out of bounds");
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_avx2_fma() {
+            // SAFETY: feature presence and index bounds checked above.
+            return unsafe { x86::gather_dot_f64_avx2(&values[..n], &indices[..n], x) };
+        }
+    }
+    let mut acc = [0.0f64; 4];
+    let (cv, ci) = (values[..n].chunks_exact(4), indices[..n].chunks_exact(4));
+    let tail: f64 = cv.remainder().iter().zip(ci.remainder()).map(|(v, &j)| v * x[j]).sum();
+    for (v, j) in cv.zip(ci) {
+        for l in 0..4 { acc[l] += v[l] * x[j[l]]; }
+    }
+    acc.iter().sum::<f64>() + tail
+}
+
+// ── Blocked GEMM ────────────────────────────────────────────
+/// Cache tile sizes: an `mc × kc` panel of A and a `kc × nc` panel of B
+/// are reused across the inner loops.
+#[derive(Clone, Copy, serde::Serialize)]
+pub struct GemmBlocking { pub mc: usize, pub kc: usize, pub nc: usize }
+
+/// Used for any cache level the topology doesn't report.
+const DEFAULT_BLOCKING: GemmBlocking = GemmBlocking { mc: 64, kc: 256, nc: 1024 };
+
+impl GemmBlocking {
+    /// Tiles sized to the data caches (bytes; L3 is this core's share).
+    /// The inner loop streams a B row segment into a C row segment, so both
+    /// (`nc` doubles each) fill half of L1; the `kc × nc` B panel, reused
+    /// for every row of the block, takes half of L2; and the `mc × nc` C
+    /// block revisited on each `kc` step takes half of L3.
+    pub fn for_caches(l1: Option<usize>, l2: Option<usize>, l3: Option<usize>) -> GemmBlocking {
+        let nc = l1.map(|l1| (l1 / 32).clamp(64, 4096) & !7).unwrap_or(DEFAULT_BLOCKING.nc);
+        let kc = l2.map(|l2| (l2 / (16 * nc)).clamp(32, 1024)).unwrap_or(DEFAULT_BLOCKING.kc);
+        let mc = l3.map(|l3| (l3 / (16 * nc)).clamp(16, 512)).unwrap_or(DEFAULT_BLOCKING.mc);
+        GemmBlocking { mc, kc, nc }
+    }
+}
+
+/// The blocking for this machine's caches, derived once.
+pub fn gemm_blocking() -> GemmBlocking {
+    static BLOCKING: std::sync::OnceLock<GemmBlocking> = std::sync::OnceLock::new();
+    *BLOCKING.get_or_init(|| {
+        let t = crate::topology::get();
+        let size = |level| t.data_cache(level).map(|c| c.size_bytes);
+        let l3 = t.data_cache(3).map(|c| c.size_bytes / c.shared_by.min(t.logical_cpus).max(1));
+        GemmBlocking::for_caches(size(1), size(2), l3)
+    })
+}
+
+/// Row-major `C[m×n] += A[m×k] · B[k×n]`. Returns early, with `C` partly
+/// updated, once the surrounding job or request is cancelled.
+pub fn gemm_f64(m: usize, n: usize, k: usize, a: &[f64], b: &[f64], c: &mut [f64]) {
+    let GemmBlocking { mc, kc, nc } = gemm_blocking();
+    for jc in (0..n).step_by(nc) {
+        let jn = (jc + nc).min(n);
+        for pc in (0..k).step_by(kc) {
+            let pk = (pc + kc).min(k);
+            for ic in (0..m).step_by(mc) {
+                if crate::jobs::cancelled() { return; }
+                for i in ic..(ic + mc).min(m) {
+                    let crow = &mut c[i * n + jc..i * n + jn];
+                    for p in pc..pk {
+                        axpy_f64(a[i * k + p], &b[p * n + jc..p * n + jn], crow);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Row-major transpose of an `rows × cols` buffer.
+pub fn transpose_f64(rows: usize, cols: usize, a: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.0; rows * cols];
+    for i in 0..rows {
+        for j in 0..cols { out[j * rows + i] = a[i * cols + j]; }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(m: usize, n: usize, k: usize, a: &[f64], b: &[f64]) -> Vec<f64> {
+        let mut c = vec![0.0; m * n];
+        for i in 0..m {
+            for j in 0..n {
+                c[i * n + j] = (0..k).map(|p| a[i * k + p] * b[p * n + j]).sum();
+            }
+        }
+        c
+    }
+
+    #[test]
+    fn gemm_matches_naive_product() {
+        let (m, n, k) = (37, 29, 41);
+        let a: Vec<f64> = (0..m * k).map(|i| ((i * 7) % 13) as f64 - 6.0).collect();
+        let b: Vec<f64> = (0..k * n).map(|i| ((i * 5) % 11) as f64 * 0.5).collect();
+        let mut c = vec![0.0; m * n];
+        gemm_f64(m, n, k, &a, &b, &mut c);
+        assert_eq!(c, naive(m, n, k, &a, &b));
+    }
+
+    #[test]
+    fn gemm_propagates_nan_and_inf_through_zeros() {
+        // 0 · Inf and 0 · NaN are NaN, as in the naive product.
+        // [0 1] · [[Inf NaN] [1 1]]
+        let a = [0.0, 1.0];
+        let b = [f64::INFINITY, f64::NAN, 1.0, 1.0];
+        let mut c = vec![0.0; 2];
+        gemm_f64(1, 2, 2, &a, &b, &mut c);
+        assert!(c[0].is_nan() && c[1].is_nan(), "{c:?}");
+    }
+}
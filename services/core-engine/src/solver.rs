@@ -0,0 +1,347 @@
+// ── Iterative solvers ───────────────────────────────────────
+// Krylov methods for `A·x = b` on CSR matrices: CG (SPD), BiCGSTAB and
+// restarted GMRES, each with optional Jacobi or ILU(0) preconditioning.
+// Convergence is judged on the relative residual ‖b − A·x‖ / ‖b‖.
+use crate::{jobs, simd};
+use crate::sparse::Csr;
+use serde::Serialize;
+
+// ── Preconditioners ─────────────────────────────────────────
+pub enum Preconditioner {
+    Identity,
+    Jacobi(Vec<f64>),
+    /// Incomplete LU with A's sparsity: unit-lower L and U share one CSR;
+    /// `diag[i]` is the position of row i's diagonal entry.
+    Ilu0 { lu: Csr, diag: Vec<usize> },
+}
+
+impl Preconditioner {
+    pub fn build(kind: &str, a: &Csr) -> Result<Self, String> {
+        match kind {
+            "none" => Ok(Preconditioner::Identity),
+            "jacobi" => {
+                let inv = diagonal_positions(a)?.iter().map(|&k| 1.0 / a.data[k]).collect();
+                Ok(Preconditioner::Jacobi(inv))
+            }
+            "ilu0" => ilu0(a),
+            other => Err(format!("unknown preconditioner '{other}' (expected none, jacobi or ilu0)")),
+        }
+    }
+
+    /// `z = M⁻¹·r`.
+    pub fn apply(&self, r: &[f64], z: &mut [f64]) {
+        match self {
+            Preconditioner::Identity => z.copy_from_slice(r),
+            Preconditioner::Jacobi(inv) => {
+                for ((zi, ri), di) in z.iter_mut().zip(r).zip(inv) { *zi = ri * di; }
+            }
+            Preconditioner::Ilu0 { lu, diag } => {
+                for i in 0..lu.rows {
+                    let lo = lu.indptr[i];
+                    z[i] = r[i] - simd::gather_dot_f64(&lu.data[lo..diag[i]], &lu.indices[lo..diag[i]], z);
+                }
+                for i in (0..lu.rows).rev() {
+                    let (d, hi) = (diag[i], lu.indptr[i + 1]);
+                    z[i] = (z[i] - simd::gather_dot_f64(&lu.data[d + 1..hi], &lu.indices[d + 1..hi], z)) / lu.data[d];
+                }
+            }
+        }
+    }
+}
+
+/// Position of each row's (nonzero) diagonal entry.
+fn diagonal_positions(a: &Csr) -> Result<Vec<usize>, String> {
+    (0..a.rows).map(|i| {
+        let (idx, vals) = a.row(i);
+        match idx.binary_search(&i) {
+            Ok(k) if vals[k] != 0.0 => Ok(a.indptr[i] + k),
+            _ => Err(format!("row {i} has a zero diagonal")),
+        }
+    }).collect()
+}
+
+fn ilu0(a: &Csr) -> Result<Preconditioner, String> {
+    let mut lu = a.clone();
+    let diag = diagonal_positions(a)?;
+    let mut pos = vec![usize::MAX; a.cols];
+    for i in 0..lu.rows {
+        let (lo, hi) = (lu.indptr[i], lu.indptr[i + 1]);
+        for p in lo..hi { pos[lu.indices[p]] = p; }
+        for p in lo..diag[i] {
+            let k = lu.indices[p];
+            let lik = lu.data[p] / lu.data[diag[k]];
+            lu.data[p] = lik;
+            for q in diag[k] + 1..lu.indptr[k + 1] {
+                let t = pos[lu.indices[q]];
+                if t != usize::MAX { lu.data[t] -= lik * lu.data[q]; }
+            }
+        }
+        for p in lo..hi { pos[lu.indices[p]] = usize::MAX; }
+        if lu.data[diag[i]] == 0.0 { return Err(format!("ILU(0) broke down at row {i} (zero pivot)")); }
+    }
+    Ok(Preconditioner::Ilu0 { lu, diag })
+}
+
+// ── Solvers ─────────────────────────────────────────────────
+pub struct Options { pub tolerance: f64, pub max_iterations: usize, pub restart: usize }
+
+/// Largest GMRES restart accepted; the Hessenberg matrix is restart².
+pub const MAX_RESTART: usize = 1000;
+pub const DEFAULT_RESTART: usize = 30;
+pub const DEFAULT_MAX_ITERATIONS: usize = 1000;
+
+/// The restart GMRES actually runs with: a cycle longer than n or the
+/// iteration budget is never used. The Krylov basis holds `restart + 1`
+/// vectors of length n.
+pub fn gmres_restart(restart: usize, n: usize, max_iterations: usize) -> usize {
+    restart.min(n).min(max_iterations).max(1)
+}
+
+#[derive(Serialize)]
+pub struct SolveInfo {
+    pub method: String, pub preconditioner: String, pub iterations: usize,
+    pub residual: f64, pub converged: bool,
+    /// `converged`, `max_iterations`, `breakdown` or `cancelled`.
+    pub status: String,
+}
+
+pub enum Stop { Converged, MaxIterations, Breakdown, Cancelled }
+
+/// Reports job progress every 64 iterations; true when the job was cancelled.
+fn checkpoint(it: usize, max_it: usize) -> bool {
+    if !it.is_multiple_of(64) { return false; }
+    jobs::report_progress(it as f64 / max_it.max(1) as f64);
+    jobs::cancelled()
+}
+
+fn norm(v: &[f64]) -> f64 { simd::norm_sq_f64(v).sqrt() }
+
+fn residual(a: &Csr, b: &[f64], x: &[f64], r: &mut [f64]) {
+    a.spmv(x, r);
+    for (ri, bi) in r.iter_mut().zip(b) { *ri = bi - *ri; }
+}
+
+/// Solves in place, starting from the contents of `x`.
+pub fn solve(method: &str, a: &Csr, b: &[f64], x: &mut [f64], m: &Preconditioner, opts: &Options) -> Result<(usize, Stop), String> {
+    let bnorm = norm(b);
+    if bnorm == 0.0 {
+        x.iter_mut().for_each(|v| *v = 0.0);
+        return Ok((0, Stop::Converged));
+    }
+    let tol = opts.tolerance * bnorm;
+    match method {
+        "cg" => Ok(cg(a, b, x, m, tol, opts.max_iterations)),
+        "bicgstab" => Ok(bicgstab(a, b, x, m, tol, opts.max_iterations)),
+        "gmres" => Ok(gmres(a, b, x, m, tol, opts.max_iterations, gmres_restart(opts.restart, b.len(), opts.max_iterations))),
+        other => Err(format!("unknown method '{other}' (expected cg, bicgstab or gmres)")),
+    }
+}
+
+fn cg(a: &Csr, b: &[f64], x: &mut [f64], m: &Preconditioner, tol: f64, max_it: usize) -> (usize, Stop) {
+    let n = b.len();
+    let (mut r, mut z, mut ap) = (vec![0.0; n], vec![0.0; n], vec![0.0; n]);
+    residual(a, b, x, &mut r);
+    if norm(&r) <= tol { return (0, Stop::Converged); }
+    m.apply(&r, &mut z);
+    let mut p = z.clone();
+    let mut rz = simd::dot_f64(&r, &z);
+    for it in 1..=max_it {
+        if checkpoint(it, max_it) { return (it, Stop::Cancelled); }
+        a.spmv(&p, &mut ap);
+        let pap = simd::dot_f64(&p, &ap);
+        if pap == 0.0 || !pap.is_finite() { return (it, Stop::Breakdown); }
+        let alpha = rz / pap;
+        simd::axpy_f64(alpha, &p, x);
+        simd::axpy_f64(-alpha, &ap, &mut r);
+        if norm(&r) <= tol { return (it, Stop::Converged); }
+        m.apply(&r, &mut z);
+        let rz_new = simd::dot_f64(&r, &z);
+        let beta = rz_new / rz;
+        rz = rz_new;
+        for (pi, zi) in p.iter_mut().zip(&z) { *pi = zi + beta * *pi; }
+    }
+    (max_it, Stop::MaxIterations)
+}
+
+fn bicgstab(a: &Csr, b: &[f64], x: &mut [f64], m: &Preconditioner, tol: f64, max_it: usize) -> (usize, Stop) {
+    let n = b.len();
+    let mut r = vec![0.0; n];
+    residual(a, b, x, &mut r);
+    if norm(&r) <= tol { return (0, Stop::Converged); }
+    let r_hat = r.clone();
+    let (mut p, mut v) = (vec![0.0; n], vec![0.0; n]);
+    let (mut phat, mut shat, mut s, mut t) = (vec![0.0; n], vec![0.0; n], vec![0.0; n], vec![0.0; n]);
+    let (mut rho, mut alpha, mut omega) = (1.0, 1.0, 1.0);
+    for it in 1..=max_it {
+        if checkpoint(it, max_it) { return (it, Stop::Cancelled); }
+        let rho_new = simd::dot_f64(&r_hat, &r);
+        if rho_new == 0.0 || omega == 0.0 { return (it, Stop::Breakdown); }
+        let beta = (rho_new / rho) * (alpha / omega);
+        for ((pi, ri), vi) in p.iter_mut().zip(&r).zip(&v) { *pi = ri + beta * (*pi - omega * vi); }
+        m.apply(&p, &mut phat);
+        a.spmv(&phat, &mut v);
+        let rv = simd::dot_f64(&r_hat, &v);
+        if rv == 0.0 { return (it, Stop::Breakdown); }
+        alpha = rho_new / rv;
+        for ((si, ri), vi) in s.iter_mut().zip(&r).zip(&v) { *si = ri - alpha * vi; }
+        if norm(&s) <= tol {
+            simd::axpy_f64(alpha, &phat, x);
+            return (it, Stop::Converged);
+        }
+        m.apply(&s, &mut shat);
+        a.spmv(&shat, &mut t);
+        let tt = simd::dot_f64(&t, &t);
+        omega = if tt > 0.0 { simd::dot_f64(&t, &s) / tt } else { 0.0 };
+        simd::axpy_f64(alpha, &phat, x);
+        simd::axpy_f64(omega, &shat, x);
+        for ((ri, si), ti) in r.iter_mut().zip(&s).zip(&t) { *ri = si - omega * ti; }
+        if norm(&r) <= tol { return (it, Stop::Converged); }
+        rho = rho_new;
+    }
+    (max_it, Stop::MaxIterations)
+}
+
+/// Right-preconditioned GMRES(`restart`) with Givens rotations.
+fn gmres(a: &Csr, b: &[f64], x: &mut [f64], m: &Preconditioner, tol: f64, max_it: usize, restart: usize) -> (usize, Stop) {
+    let n = b.len();
+    let (mut r, mut z, mut w) = (vec![0.0; n], vec![0.0; n], vec![0.0; n]);
+    let mut it = 0;
+    loop {
+        residual(a, b, x, &mut r);
+        let beta = norm(&r);
+        if beta <= tol { return (it, Stop::Converged); }
+        if it >= max_it { return (it, Stop::MaxIterations); }
+        let mut basis = vec![r.iter().map(|v| v / beta).collect::<Vec<f64>>()];
+        let mut h = vec![vec![0.0; restart]; restart + 1];
+        let (mut cs, mut sn) = (vec![0.0; restart], vec![0.0; restart]);
+        let mut g = vec![0.0; restart + 1];
+        g[0] = beta;
+        let mut k = 0;
+        while k < restart && it < max_it {
+            if checkpoint(it, max_it) { return (it, Stop::Cancelled); }
+            m.apply(&basis[k], &mut z);
+            a.spmv(&z, &mut w);
+            for (i, vi) in basis.iter().enumerate() {
+                h[i][k] = simd::dot_f64(&w, vi);
+                simd::axpy_f64(-h[i][k], vi, &mut w);
+            }
+            let wnorm = norm(&w);
+            h[k + 1][k] = wnorm;
+            let lucky = wnorm <= f64::EPSILON * beta;
+            for i in 0..k {
+                let t = cs[i] * h[i][k] + sn[i] * h[i + 1][k];
+                h[i + 1][k] = -sn[i] * h[i][k] + cs[i] * h[i + 1][k];
+                h[i][k] = t;
+            }
+            let d = h[k][k].hypot(h[k + 1][k]);
+            (cs[k], sn[k]) = if d == 0.0 { (1.0, 0.0) } else { (h[k][k] / d, h[k + 1][k] / d) };
+            h[k][k] = d;
+            h[k + 1][k] = 0.0;
+            g[k + 1] = -sn[k] * g[k];
+            g[k] *= cs[k];
+            if !lucky { basis.push(w.iter().map(|v| v / wnorm).collect()); }
+            k += 1;
+            it += 1;
+            if lucky || g[k].abs() <= tol { break; }
+        }
+        // Back-substitute H·y = g and update x += M⁻¹·(V·y).
+        let mut y = vec![0.0; k];
+        for i in (0..k).rev() {
+            let s: f64 = (i + 1..k).map(|j| h[i][j] * y[j]).sum();
+            y[i] = if h[i][i] != 0.0 { (g[i] - s) / h[i][i] } else { 0.0 };
+        }
+        let mut update = vec![0.0; n];
+        for (yi, vi) in y.iter().zip(&basis) { simd::axpy_f64(*yi, vi, &mut update); }
+        m.apply(&update, &mut z);
+        simd::axpy_f64(1.0, &z, x);
+    }
+}
+
+pub fn describe(method: &str, preconditioner: &str, a: &Csr, b: &[f64], x: &[f64], (iterations, stop): (usize, Stop)) -> SolveInfo {
+    let mut r = vec![0.0; b.len()];
+    residual(a, b, x, &mut r);
+    let bnorm = norm(b);
+    let residual = if bnorm > 0.0 { norm(&r) / bnorm } else { norm(&r) };
+    let status = match stop { Stop::Converged => "converged", Stop::MaxIterations => "max_iterations", Stop::Breakdown => "breakdown", Stop::Cancelled => "cancelled" };
+    SolveInfo {
+        method: method.into(), preconditioner: preconditioner.into(), iterations, residual,
+        converged: matches!(stop, Stop::Converged), status: status.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tridiagonal `n × n` matrix with `lower`, `diag`, `upper` bands.
+    fn tridiagonal(n: usize, lower: f64, diag: f64, upper: f64) -> Csr {
+        let mut trips = Vec::new();
+        for i in 0..n {
+            if i > 0 { trips.push((i, i - 1, lower)); }
+            trips.push((i, i, diag));
+            if i + 1 < n { trips.push((i, i + 1, upper)); }
+        }
+        Csr::from_triplets(n, n, trips).unwrap()
+    }
+
+    fn check(method: &str, pc: &str, a: &Csr, restart: usize) {
+        let b: Vec<f64> = (0..a.rows).map(|i| 1.0 + (i % 7) as f64).collect();
+        let mut x = vec![0.0; a.rows];
+        let m = Preconditioner::build(pc, a).unwrap();
+        let opts = Options { tolerance: 1e-10, max_iterations: 2000, restart };
+        let (iterations, stop) = solve(method, a, &b, &mut x, &m, &opts).unwrap();
+        assert!(matches!(stop, Stop::Converged), "{method}/{pc} did not converge in {iterations} iterations");
+        let mut r = vec![0.0; a.rows];
+        residual(a, &b, &x, &mut r);
+        assert!(norm(&r) <= 1e-9 * norm(&b), "{method}/{pc}: residual {}", norm(&r) / norm(&b));
+    }
+
+    #[test]
+    fn converges_on_spd_system() {
+        // 1-D Poisson: symmetric positive definite.
+        let a = tridiagonal(100, -1.0, 2.0, -1.0);
+        for method in ["cg", "bicgstab", "gmres"] {
+            for pc in ["none", "jacobi", "ilu0"] {
+                check(method, pc, &a, 30);
+            }
+        }
+    }
+
+    #[test]
+    fn converges_on_nonsymmetric_system() {
+        // Convection-diffusion: diagonally dominant, not symmetric.
+        let a = tridiagonal(100, -1.5, 3.0, -0.5);
+        for method in ["bicgstab", "gmres"] {
+            for pc in ["none", "jacobi", "ilu0"] {
+                check(method, pc, &a, 20);
+            }
+        }
+        // A restart of 1 still converges, just slowly.
+        check("gmres", "jacobi", &a, 1);
+    }
+
+    #[test]
+    fn ilu0_is_exact_on_tridiagonal() {
+        // ILU(0) of a tridiagonal matrix has no fill to drop, so one
+        // preconditioned step solves the system.
+        let a = tridiagonal(50, -1.5, 3.0, -0.5);
+        let b = vec![1.0; 50];
+        let mut x = vec![0.0; 50];
+        let m = Preconditioner::build("ilu0", &a).unwrap();
+        let opts = Options { tolerance: 1e-10, max_iterations: 100, restart: 30 };
+        let (iterations, stop) = solve("gmres", &a, &b, &mut x, &m, &opts).unwrap();
+        assert!(matches!(stop, Stop::Converged));
+        assert!(iterations <= 2, "took {iterations} iterations");
+    }
+
+    #[test]
+    fn zero_rhs_and_unknown_method() {
+        let a = tridiagonal(5, -1.0, 2.0, -1.0);
+        let mut x = vec![1.0; 5];
+        let opts = Options { tolerance: 1e-8, max_iterations: 10, restart: 5 };
+        let (iterations, stop) = solve("cg", &a, &[0.0; 5], &mut x, &Preconditioner::Identity, &opts).unwrap();
+        assert_eq!(iterations, 0);
+        assert!(matches!(stop, Stop::Converged) && x.iter().all(|v| *v == 0.0));
+        assert!(solve("qmr", &a, &[1.0; 5], &mut x, &Preconditioner::Identity, &opts).is_err());
+    }
+}
@@ -0,0 +1,362 @@
+// ── Sparse matrices ─────────────────────────────────────────
+// COO, CSR and CSC on the wire (SciPy field names). Everything is
+// canonicalised to CSR with sorted column indices and summed duplicates;
+// CSC is the CSR of the transpose. SpMV rows use the gather-dot kernel and
+// SpMM rows accumulate dense rows of B with axpy.
+use crate::solver::{self, Options, Preconditioner, SolveInfo};
+use crate::{mat_to_json, pairwise, simd, MatrixRequest};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum SparseInput {
+    Coo { shape: [usize; 2], row: Vec<usize>, col: Vec<usize>, data: Vec<f64> },
+    Csr { shape: [usize; 2], indptr: Vec<usize>, indices: Vec<usize>, data: Vec<f64> },
+    Csc { shape: [usize; 2], indptr: Vec<usize>, indices: Vec<usize>, data: Vec<f64> },
+}
+
+impl SparseInput {
+    pub fn nnz(&self) -> usize {
+        match self {
+            SparseInput::Coo { data, .. } | SparseInput::Csr { data, .. } | SparseInput::Csc { data, .. } => data.len(),
+        }
+    }
+
+    pub fn shape(&self) -> [usize; 2] {
+        match self {
+            SparseInput::Coo { shape, .. } | SparseInput::Csr { shape, .. } | SparseInput::Csc { shape, .. } => *shape,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct SparseVector { pub size: usize, pub indices: Vec<usize>, pub data: Vec<f64> }
+
+#[derive(Clone)]
+pub struct Csr { pub rows: usize, pub cols: usize, pub indptr: Vec<usize>, pub indices: Vec<usize>, pub data: Vec<f64> }
+
+impl Csr {
+    pub fn from_input(s: &SparseInput) -> Result<Csr, String> {
+        match s {
+            SparseInput::Coo { shape: [r, c], row, col, data } => {
+                if row.len() != data.len() || col.len() != data.len() {
+                    return Err(format!("coo row/col/data lengths differ ({}, {}, {})", row.len(), col.len(), data.len()));
+                }
+                let trips = row.iter().zip(col).zip(data).map(|((&i, &j), &v)| (i, j, v)).collect();
+                Csr::from_triplets(*r, *c, trips)
+            }
+            SparseInput::Csr { shape: [r, c], indptr, indices, data } => Csr::compressed(*r, *c, indptr, indices, data),
+            SparseInput::Csc { shape: [r, c], indptr, indices, data } => Ok(Csr::compressed(*c, *r, indptr, indices, data)?.transpose()),
+        }
+    }
+
+    /// Validates a compressed-row buffer; unsorted or duplicate rows are
+    /// rebuilt through the triplet path.
+    fn compressed(rows: usize, cols: usize, indptr: &[usize], indices: &[usize], data: &[f64]) -> Result<Csr, String> {
+        if indptr.len() != rows + 1 { return Err(format!("indptr has {} entries, expected {}", indptr.len(), rows + 1)); }
+        if indices.len() != data.len() { return Err(format!("indices/data lengths differ ({}, {})", indices.len(), data.len())); }
+        if indptr[0] != 0 || indptr[rows] != data.len() || indptr.windows(2).any(|w| w[0] > w[1]) {
+            return Err("indptr must start at 0, be non-decreasing and end at nnz".into());
+        }
+        if let Some(j) = indices.iter().find(|&&j| j >= cols) { return Err(format!("index {j} out of bounds for {cols} columns")); }
+        let canonical = indptr.windows(2).all(|w| indices[w[0]..w[1]].windows(2).all(|p| p[0] < p[1]));
+        if canonical {
+            return Ok(Csr { rows, cols, indptr: indptr.to_vec(), indices: indices.to_vec(), data: data.to_vec() });
+        }
+        let trips = (0..rows).flat_map(|i| (indptr[i]..indptr[i + 1]).map(move |k| (i, indices[k], data[k]))).collect();
+        Csr::from_triplets(rows, cols, trips)
+    }
+
+    pub fn from_triplets(rows: usize, cols: usize, mut trips: Vec<(usize, usize, f64)>) -> Result<Csr, String> {
+        if let Some((i, j, _)) = trips.iter().find(|(i, j, _)| *i >= rows || *j >= cols) {
+            return Err(format!("entry ({i}, {j}) out of bounds for {rows}x{cols}"));
+        }
+        trips.sort_unstable_by_key(|&(i, j, _)| (i, j));
+        let mut indptr = vec![0; rows + 1];
+        let (mut indices, mut data) = (Vec::with_capacity(trips.len()), Vec::with_capacity(trips.len()));
+        let mut last = None;
+        for (i, j, v) in trips {
+            if last == Some((i, j)) {
+                *data.last_mut().unwrap() += v;
+                continue;
+            }
+            last = Some((i, j));
+            indptr[i + 1] += 1;
+            indices.push(j);
+            data.push(v);
+        }
+        for i in 0..rows { indptr[i + 1] += indptr[i]; }
+        Ok(Csr { rows, cols, indptr, indices, data })
+    }
+
+    /// Drops exact zeros.
+    pub fn from_dense(m: &[Vec<f64>], rows: usize, cols: usize) -> Csr {
+        let mut indptr = Vec::with_capacity(rows + 1);
+        let (mut indices, mut data) = (Vec::new(), Vec::new());
+        indptr.push(0);
+        for i in 0..rows {
+            for (j, &v) in m.get(i).map(|r| r.as_slice()).unwrap_or(&[]).iter().take(cols).enumerate() {
+                if v != 0.0 { indices.push(j); data.push(v); }
+            }
+            indptr.push(data.len());
+        }
+        Csr { rows, cols, indptr, indices, data }
+    }
+
+    pub fn nnz(&self) -> usize { self.data.len() }
+
+    pub fn row(&self, i: usize) -> (&[usize], &[f64]) {
+        let r = self.indptr[i]..self.indptr[i + 1];
+        (&self.indices[r.clone()], &self.data[r])
+    }
+
+    /// Counting-sort transpose; rows of the result come out sorted.
+    pub fn transpose(&self) -> Csr {
+        let mut indptr = vec![0; self.cols + 1];
+        for &j in &self.indices { indptr[j + 1] += 1; }
+        for j in 0..self.cols { indptr[j + 1] += indptr[j]; }
+        let mut next = indptr.clone();
+        let (mut indices, mut data) = (vec![0; self.nnz()], vec![0.0; self.nnz()]);
+        for i in 0..self.rows {
+            let (idx, vals) = self.row(i);
+            for (&j, &v) in idx.iter().zip(vals) {
+                indices[next[j]] = i;
+                data[next[j]] = v;
+                next[j] += 1;
+            }
+        }
+        Csr { rows: self.cols, cols: self.rows, indptr, indices, data }
+    }
+
+    pub fn to_dense(&self) -> Vec<Vec<f64>> {
+        (0..self.rows).map(|i| {
+            let mut row = vec![0.0; self.cols];
+            let (idx, vals) = self.row(i);
+            for (&j, &v) in idx.iter().zip(vals) { row[j] = v; }
+            row
+        }).collect()
+    }
+
+    /// `y = A·x`.
+    pub fn spmv(&self, x: &[f64], y: &mut [f64]) {
+        for (i, yi) in y.iter_mut().enumerate().take(self.rows) {
+            let (idx, vals) = self.row(i);
+            *yi = simd::gather_dot_f64(vals, idx, x);
+        }
+    }
+
+    /// Row-major `A · B` with `B` dense `cols × n`.
+    pub fn spmm(&self, b: &[f64], n: usize) -> Vec<f64> {
+        let mut c = vec![0.0; self.rows * n];
+        for i in 0..self.rows {
+            let (idx, vals) = self.row(i);
+            let crow = &mut c[i * n..(i + 1) * n];
+            for (&j, &v) in idx.iter().zip(vals) { simd::axpy_f64(v, &b[j * n..(j + 1) * n], crow); }
+        }
+        c
+    }
+
+    pub fn to_output(&self, format: &str) -> Result<SparseInput, String> {
+        let shape = [self.rows, self.cols];
+        match format {
+            "csr" => Ok(SparseInput::Csr { shape, indptr: self.indptr.clone(), indices: self.indices.clone(), data: self.data.clone() }),
+            "csc" => {
+                let t = self.transpose();
+                Ok(SparseInput::Csc { shape, indptr: t.indptr, indices: t.indices, data: t.data })
+            }
+            "coo" => Ok(SparseInput::Coo {
+                shape,
+                row: (0..self.rows).flat_map(|i| std::iter::repeat_n(i, self.indptr[i + 1] - self.indptr[i])).collect(),
+                col: self.indices.clone(),
+                data: self.data.clone(),
+            }),
+            other => Err(format!("unknown sparse format '{other}' (expected coo, csr or csc)")),
+        }
+    }
+}
+
+impl SparseVector {
+    pub fn dot(&self, x: &[f64]) -> Result<f64, String> {
+        if x.len() != self.size { return Err(format!("dense vector has {} entries, sparse vector size is {}", x.len(), self.size)); }
+        if self.indices.len() != self.data.len() { return Err("sparse vector indices/data lengths differ".into()); }
+        if let Some(j) = self.indices.iter().find(|&&j| j >= self.size) { return Err(format!("index {j} out of bounds for size {}", self.size)); }
+        Ok(simd::gather_dot_f64(&self.data, &self.indices, x))
+    }
+}
+
+type OpResult = (serde_json::Value, Option<f64>, String);
+
+fn sparse_json(s: SparseInput) -> serde_json::Value {
+    serde_json::to_value(s).unwrap_or(serde_json::Value::Null)
+}
+
+/// The sparse arms of the `matrix` endpoint.
+pub fn matrix_op(req: &MatrixRequest) -> Result<OpResult, String> {
+    let sparse_a = || -> Result<Csr, String> {
+        let s = req.sparse_a.as_ref().ok_or("requires `sparse_a`")?;
+        Csr::from_input(s).map_err(|e| format!("invalid sparse_a: {e}"))
+    };
+    let format = req.format.as_deref().unwrap_or("csr");
+    let describe = |a: &Csr| format!("{}x{} (nnz {})", a.rows, a.cols, a.nnz());
+    match req.operation.as_str() {
+        "spmv" => {
+            let a = sparse_a()?;
+            let x = req.vector.as_deref().ok_or("requires `vector`")?;
+            if x.len() != a.cols { return Err(format!("vector has {} entries, matrix has {} columns", x.len(), a.cols)); }
+            let mut y = vec![0.0; a.rows];
+            a.spmv(x, &mut y);
+            Ok((serde_json::Value::from(y), None, format!("{} * {}", describe(&a), x.len())))
+        }
+        "spmm" => {
+            let a = sparse_a()?;
+            let b = req.matrix_b.as_deref().ok_or("requires `matrix_b`")?;
+            let n = pairwise::row_width(b, "matrix_b")?;
+            if b.len() != a.cols { return Err(format!("matrix_b has {} rows, sparse_a has {} columns", b.len(), a.cols)); }
+            let c = a.spmm(&pairwise::flatten(b, a.cols, n), n);
+            let result: Vec<Vec<f64>> = c.chunks(n.max(1)).take(a.rows).map(|r| r.to_vec()).collect();
+            Ok((mat_to_json(&result), None, format!("{} * {}x{n}", describe(&a), b.len())))
+        }
+        "sparse_dot" => {
+            let v = req.sparse_vector.as_ref().ok_or("requires `sparse_vector`")?;
+            let x = req.vector.as_deref().ok_or("requires `vector`")?;
+            Ok((serde_json::Value::Null, Some(v.dot(x)?), format!("sparse {} (nnz {}) . {}", v.size, v.data.len(), x.len())))
+        }
+        "to_dense" => {
+            let a = sparse_a()?;
+            Ok((mat_to_json(&a.to_dense()), None, describe(&a)))
+        }
+        "to_sparse" => {
+            let rows = req.matrix_a.len();
+            let cols = pairwise::row_width(&req.matrix_a, "matrix_a")?;
+            let a = Csr::from_dense(&req.matrix_a, rows, cols);
+            let dims = describe(&a);
+            Ok((sparse_json(a.to_output(format)?), None, dims))
+        }
+        "convert" => {
+            let a = sparse_a()?;
+            let dims = describe(&a);
+            Ok((sparse_json(a.to_output(format)?), None, dims))
+        }
+        other => Err(format!("not a sparse operation: {other}")),
+    }
+}
+
+/// `solve`: `sparse_a` (or dense `matrix_a`) · x = `vector`.
+pub fn solve(req: &MatrixRequest) -> Result<(Vec<f64>, SolveInfo, String), String> {
+    let a = match &req.sparse_a {
+        Some(s) => Csr::from_input(s).map_err(|e| format!("invalid sparse_a: {e}"))?,
+        None => {
+            let cols = req.matrix_a.first().map(|r| r.len()).unwrap_or(0);
+            Csr::from_dense(&req.matrix_a, req.matrix_a.len(), cols)
+        }
+    };
+    if a.rows != a.cols || a.rows == 0 { return Err(format!("solve needs a non-empty square matrix, got {}x{}", a.rows, a.cols)); }
+    let b = req.vector.as_deref().ok_or("requires right-hand side `vector`")?;
+    if b.len() != a.rows { return Err(format!("vector has {} entries, matrix has {} rows", b.len(), a.rows)); }
+    let mut x = match &req.x0 {
+        Some(x0) if x0.len() != a.rows => return Err(format!("x0 has {} entries, matrix has {} rows", x0.len(), a.rows)),
+        Some(x0) => x0.clone(),
+        None => vec![0.0; a.rows],
+    };
+    let method = req.method.as_deref().unwrap_or("cg");
+    let pc = req.preconditioner.as_deref().unwrap_or("none");
+    let m = Preconditioner::build(pc, &a)?;
+    let opts = Options {
+        tolerance: req.tolerance.unwrap_or(1e-8),
+        max_iterations: req.max_iterations.unwrap_or(solver::DEFAULT_MAX_ITERATIONS),
+        restart: req.restart.unwrap_or(solver::DEFAULT_RESTART),
+    };
+    if !(1..=solver::MAX_RESTART).contains(&opts.restart) {
+        return Err(format!("restart is {}; expected 1..={}", opts.restart, solver::MAX_RESTART));
+    }
+    let outcome = solver::solve(method, &a, b, &mut x, &m, &opts)?;
+    let info = solver::describe(method, pc, &a, b, &x, outcome);
+    let dims = format!("{}x{} (nnz {})", a.rows, a.cols, a.nnz());
+    Ok((x, info, dims))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 4×5 with an empty row, an empty column and distinct values.
+    fn dense() -> Vec<Vec<f64>> {
+        vec![
+            vec![0.0, 1.5, 0.0, 0.0, -2.0],
+            vec![0.0, 0.0, 0.0, 0.0, 0.0],
+            vec![3.0, 0.0, 0.0, 4.25, 0.0],
+            vec![0.0, -1.0, 0.0, 7.0, 0.5],
+        ]
+    }
+
+    #[test]
+    fn round_trips_through_every_format() {
+        let a = Csr::from_dense(&dense(), 4, 5);
+        assert_eq!(a.nnz(), 7);
+        for format in ["csr", "csc", "coo"] {
+            let wire = a.to_output(format).unwrap();
+            assert_eq!(wire.shape(), [4, 5]);
+            assert_eq!(wire.nnz(), 7);
+            let json = serde_json::to_value(&wire).unwrap();
+            assert_eq!(json["format"], format);
+            let back: SparseInput = serde_json::from_value(json).unwrap();
+            let b = Csr::from_input(&back).unwrap();
+            assert_eq!(b.to_dense(), dense(), "{format}");
+            assert_eq!((b.indptr, b.indices), (a.indptr.clone(), a.indices.clone()), "{format}");
+        }
+    }
+
+    #[test]
+    fn canonicalises_unsorted_and_duplicate_entries() {
+        // COO with duplicates summed and entries out of order.
+        let coo = SparseInput::Coo { shape: [2, 3], row: vec![1, 0, 1, 0], col: vec![2, 1, 2, 0], data: vec![1.0, 2.0, 3.0, 4.0] };
+        let a = Csr::from_input(&coo).unwrap();
+        assert_eq!((a.indptr.as_slice(), a.indices.as_slice(), a.data.as_slice()), (&[0, 2, 3][..], &[0, 1, 2][..], &[4.0, 2.0, 4.0][..]));
+        // CSR with an unsorted row.
+        let csr = SparseInput::Csr { shape: [2, 3], indptr: vec![0, 2, 3], indices: vec![1, 0, 2], data: vec![2.0, 4.0, 4.0] };
+        assert_eq!(Csr::from_input(&csr).unwrap().data, a.data);
+        // CSC of the same matrix.
+        let t = a.transpose();
+        let csc = SparseInput::Csc { shape: [2, 3], indptr: t.indptr, indices: t.indices, data: t.data };
+        assert_eq!(Csr::from_input(&csc).unwrap().to_dense(), a.to_dense());
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let bad = [
+            SparseInput::Coo { shape: [2, 2], row: vec![0, 2], col: vec![0, 0], data: vec![1.0, 1.0] },
+            SparseInput::Coo { shape: [2, 2], row: vec![0], col: vec![0, 1], data: vec![1.0] },
+            SparseInput::Csr { shape: [2, 2], indptr: vec![0, 1], indices: vec![0], data: vec![1.0] },
+            SparseInput::Csr { shape: [2, 2], indptr: vec![0, 2, 1], indices: vec![0, 1], data: vec![1.0, 1.0] },
+            SparseInput::Csc { shape: [2, 2], indptr: vec![0, 1, 1], indices: vec![5], data: vec![1.0] },
+        ];
+        for s in &bad { assert!(Csr::from_input(s).is_err()); }
+    }
+
+    #[test]
+    fn spmv_and_spmm_match_dense() {
+        let m = dense();
+        let a = Csr::from_dense(&m, 4, 5);
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let mut y = vec![0.0; 4];
+        a.spmv(&x, &mut y);
+        let want: Vec<f64> = m.iter().map(|r| r.iter().zip(&x).map(|(a, b)| a * b).sum()).collect();
+        assert_eq!(y, want);
+        // B = [x, 2x] as a 5×2 row-major matrix.
+        let b: Vec<f64> = x.iter().flat_map(|v| [*v, 2.0 * v]).collect();
+        let c = a.spmm(&b, 2);
+        let want2: Vec<f64> = want.iter().flat_map(|v| [*v, 2.0 * v]).collect();
+        assert_eq!(c, want2);
+    }
+
+    #[test]
+    fn rejects_ragged_dense_operands() {
+        let req = |v: serde_json::Value| -> MatrixRequest { serde_json::from_value(v).unwrap() };
+        let a = serde_json::to_value(Csr::from_dense(&dense(), 4, 5).to_output("csr").unwrap()).unwrap();
+        let ragged = vec![vec![1.0, 2.0], vec![3.0], vec![4.0, 5.0], vec![6.0, 7.0], vec![8.0, 9.0]];
+        let err = matrix_op(&req(serde_json::json!({"operation": "spmm", "sparse_a": a, "matrix_b": ragged}))).err().unwrap();
+        assert_eq!(err, "matrix_b row 1 has 1 columns, expected 2");
+        let err = matrix_op(&req(serde_json::json!({"operation": "to_sparse", "matrix_a": [[1.0], [2.0, 3.0]]}))).err().unwrap();
+        assert_eq!(err, "matrix_a row 1 has 2 columns, expected 1");
+    }
+}
@@ -0,0 +1,217 @@
+// ── Operation statistics ────────────────────────────────────
+// Per-operation calls, errors, elements, compute time and latency
+// percentiles behind GET /api/v1/simd/stats, either since the last reset
+// or over a rolling window of the last 1m, 5m or 1h. Windows are built from
+// 10-second slots in a ring covering an hour, allocated the first time an
+// operation is called. Like the Prometheus series everything is an atomic:
+// the first writer to find a slot stale claims it for the new interval and
+// zeroes it, so a call landing exactly on a slot boundary may be lost.
+// Percentiles are interpolated within √2-spaced latency buckets (1 µs to
+// about 47 s) and so carry up to ~20% error.
+use crate::{api_err, ApiError, AppState};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+const SLOT_SECS: u64 = 10;
+/// One hour of slots plus the one being filled.
+const SLOTS: usize = 361;
+const BUCKETS: usize = 52;
+const WINDOWS: &[(&str, u64)] = &[("1m", 60), ("5m", 300), ("1h", 3600)];
+
+fn bucket(ns: u64) -> usize {
+    if ns <= 1_000 { return 0; }
+    ((2.0 * (ns as f64 / 1_000.0).log2()).ceil() as usize).min(BUCKETS - 1)
+}
+
+fn bucket_bound(i: usize) -> f64 { 1_000.0 * 2f64.powf(i as f64 / 2.0) }
+
+/// Slot index since the engine started.
+fn now_slot() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs() / SLOT_SECS
+}
+
+struct Counts {
+    calls: AtomicU64,
+    errors: AtomicU64,
+    elements: AtomicU64,
+    compute_ns: AtomicU64,
+    latency: [AtomicU64; BUCKETS],
+}
+
+impl Default for Counts {
+    fn default() -> Self {
+        Counts {
+            calls: AtomicU64::new(0), errors: AtomicU64::new(0), elements: AtomicU64::new(0), compute_ns: AtomicU64::new(0),
+            latency: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+}
+
+impl Counts {
+    fn record(&self, ok: bool, elements: u64, ns: u64) {
+        self.calls.fetch_add(1, Relaxed);
+        if !ok { self.errors.fetch_add(1, Relaxed); }
+        self.elements.fetch_add(elements, Relaxed);
+        self.compute_ns.fetch_add(ns, Relaxed);
+        self.latency[bucket(ns)].fetch_add(1, Relaxed);
+    }
+
+    fn clear(&self) {
+        for c in [&self.calls, &self.errors, &self.elements, &self.compute_ns].into_iter().chain(&self.latency) { c.store(0, Relaxed); }
+    }
+
+    fn add_to(&self, t: &mut Totals) {
+        t.calls += self.calls.load(Relaxed);
+        t.errors += self.errors.load(Relaxed);
+        t.elements += self.elements.load(Relaxed);
+        t.compute_ns += self.compute_ns.load(Relaxed);
+        for (h, c) in t.latency.iter_mut().zip(&self.latency) { *h += c.load(Relaxed); }
+    }
+}
+
+#[derive(Default)]
+struct Slot { epoch: AtomicU64, counts: Counts }
+
+#[derive(Default)]
+pub struct OpStats {
+    since_reset: Counts,
+    ring: OnceLock<Box<[Slot]>>,
+}
+
+impl OpStats {
+    pub fn record(&self, ok: bool, elements: u64, ns: u64) {
+        self.since_reset.record(ok, elements, ns);
+        let now = now_slot();
+        let slot = &self.ring.get_or_init(|| (0..SLOTS).map(|_| Slot::default()).collect())[now as usize % SLOTS];
+        // Epochs are stored +1 so that 0 means never used.
+        let seen = slot.epoch.load(Relaxed);
+        if seen != now + 1 && slot.epoch.compare_exchange(seen, now + 1, Relaxed, Relaxed).is_ok() { slot.counts.clear(); }
+        slot.counts.record(ok, elements, ns);
+    }
+
+    pub fn reset(&self) {
+        self.since_reset.clear();
+        for slot in self.ring.get().into_iter().flat_map(|r| r.iter()) { slot.epoch.store(0, Relaxed); slot.counts.clear(); }
+    }
+
+    /// Totals since reset, or over the last `secs` (to slot granularity).
+    fn totals(&self, secs: Option<u64>) -> Totals {
+        let mut t = Totals::default();
+        match secs {
+            None => self.since_reset.add_to(&mut t),
+            Some(secs) => {
+                let oldest = now_slot().saturating_sub(secs / SLOT_SECS - 1) + 1;
+                for slot in self.ring.get().into_iter().flat_map(|r| r.iter()) {
+                    if slot.epoch.load(Relaxed) >= oldest { slot.counts.add_to(&mut t); }
+                }
+            }
+        }
+        t
+    }
+}
+
+struct Totals { calls: u64, errors: u64, elements: u64, compute_ns: u64, latency: [u64; BUCKETS] }
+
+impl Default for Totals {
+    fn default() -> Self { Totals { calls: 0, errors: 0, elements: 0, compute_ns: 0, latency: [0; BUCKETS] } }
+}
+
+impl Totals {
+    /// Interpolated within the bucket holding rank `q · calls`.
+    fn percentile(&self, q: f64) -> f64 {
+        let total: u64 = self.latency.iter().sum();
+        if total == 0 { return 0.0; }
+        let rank = q * total as f64;
+        let mut below = 0.0;
+        for (i, &n) in self.latency.iter().enumerate() {
+            let n = n as f64;
+            if n > 0.0 && below + n >= rank {
+                let lo = if i == 0 { 0.0 } else { bucket_bound(i - 1) };
+                return lo + (bucket_bound(i) - lo) * ((rank - below) / n).clamp(0.0, 1.0);
+            }
+            below += n;
+        }
+        bucket_bound(BUCKETS - 1)
+    }
+}
+
+// ── API ─────────────────────────────────────────────────────
+#[derive(Deserialize)]
+struct StatsQuery { window: Option<String> }
+
+#[derive(Serialize)]
+struct Latency { p50_ns: f64, p95_ns: f64, p99_ns: f64, mean_ns: f64 }
+
+#[derive(Serialize)]
+struct OperationStats {
+    endpoint: &'static str, operation: &'static str,
+    calls: u64, errors: u64, elements: u64,
+    /// Summed handler time.
+    compute_time_ns: u64,
+    latency: Latency,
+}
+
+#[derive(Serialize)]
+struct StatsResponse {
+    total_computes: u64, total_matrix_ops: u64, total_benchmarks: u64,
+    /// "all" (since start or the last reset), "1m", "5m" or "1h".
+    window: String,
+    calls: u64, errors: u64, elements: u64, compute_time_ns: u64,
+    /// Operations called in the window, busiest first.
+    operations: Vec<OperationStats>,
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/api/v1/simd/stats", get(stats))
+        .route("/api/v1/simd/stats/reset", post(reset))
+}
+
+async fn stats(State(s): State<Arc<AppState>>, Query(q): Query<StatsQuery>) -> Result<Json<StatsResponse>, ApiError> {
+    let window = q.window.unwrap_or_else(|| "all".into());
+    let secs = match window.as_str() {
+        "all" => None,
+        w => Some(WINDOWS.iter().find(|(name, _)| *name == w).map(|&(_, secs)| secs).ok_or_else(|| {
+            api_err(StatusCode::BAD_REQUEST, "Unknown stats window", Some(format!("'{w}' (expected all, 1m, 5m or 1h)")))
+        })?),
+    };
+    let mut operations: Vec<OperationStats> = s.metrics.operations().filter_map(|(endpoint, operation, st)| {
+        let t = st.totals(secs);
+        (t.calls > 0).then(|| OperationStats {
+            endpoint, operation, calls: t.calls, errors: t.errors, elements: t.elements, compute_time_ns: t.compute_ns,
+            latency: Latency {
+                p50_ns: t.percentile(0.50), p95_ns: t.percentile(0.95), p99_ns: t.percentile(0.99),
+                mean_ns: t.compute_ns as f64 / t.calls as f64,
+            },
+        })
+    }).collect();
+    operations.sort_by_key(|o| std::cmp::Reverse(o.calls));
+    let sum = |f: fn(&OperationStats) -> u64| operations.iter().map(f).sum();
+    let st = &s.stats;
+    Ok(Json(StatsResponse {
+        total_computes: st.total_computes.load(Relaxed),
+        total_matrix_ops: st.total_matrix_ops.load(Relaxed),
+        total_benchmarks: st.total_benchmarks.load(Relaxed),
+        calls: sum(|o| o.calls), errors: sum(|o| o.errors), elements: sum(|o| o.elements), compute_time_ns: sum(|o| o.compute_time_ns),
+        window, operations,
+    }))
+}
+
+/// Zeroes the stats endpoint's figures. Prometheus counters are left alone,
+/// since scrapers rely on them never going backwards.
+async fn reset(State(s): State<Arc<AppState>>) -> StatusCode {
+    let st = &s.stats;
+    for c in [&st.total_computes, &st.total_matrix_ops, &st.total_benchmarks] { c.store(0, Relaxed); }
+    for (_, _, op) in s.metrics.operations() { op.reset(); }
+    StatusCode::NO_CONTENT
+}
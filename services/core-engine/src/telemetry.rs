@@ -0,0 +1,99 @@
+// ── Telemetry ───────────────────────────────────────────────
+// Logging plus optional OpenTelemetry tracing. Spans are exported over
+// OTLP/HTTP (protobuf) when OTEL_EXPORTER_OTLP_ENDPOINT or
+// OTEL_EXPORTER_OTLP_TRACES_ENDPOINT is set; the exporter reads those and the
+// other standard OTEL_EXPORTER_OTLP_* variables itself. Each request span
+// continues the W3C `traceparent` the gateway sends. The compute endpoints
+// take and return this module's `Json`, a drop-in for axum's that traces
+// body deserialization as `parse` and response serialization as `serialize`;
+// the handlers add `validate` and `kernel` spans between the two.
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    response::{IntoResponse, Response},
+};
+use opentelemetry::{global, trace::TracerProvider as _};
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource};
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+use tracing::{Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+const SERVICE: &str = "simd-engine";
+
+/// Installs the log subscriber and, if a collector is configured, the OTLP
+/// exporter. Keep the provider and `shutdown` it on exit to flush spans.
+pub fn init() -> Option<SdkTracerProvider> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let configured = ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"].iter().any(|k| std::env::var(k).is_ok_and(|v| !v.is_empty()));
+    let exporter = configured.then(|| opentelemetry_otlp::SpanExporter::builder().with_http().build());
+    let (provider, error) = match exporter {
+        Some(Ok(exporter)) => {
+            let service = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| SERVICE.into());
+            let provider = SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(Resource::builder().with_service_name(service).build())
+                .build();
+            (Some(provider), None)
+        }
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| "simd_engine=info".into()))
+        .with(tracing_subscriber::fmt::layer())
+        .with(provider.as_ref().map(|p| tracing_opentelemetry::layer().with_tracer(p.tracer(SERVICE))))
+        .init();
+    if let Some(e) = error { tracing::warn!("OTLP exporter disabled: {e}"); }
+    provider
+}
+
+/// Resolves on Ctrl-C or SIGTERM.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    let term = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut s) => { s.recv().await; }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let term = std::future::pending::<()>();
+    tokio::select! { _ = tokio::signal::ctrl_c() => {}, _ = term => {} }
+}
+
+/// Server span for one request, parented on its `traceparent` if any.
+pub fn request_span<B>(req: &axum::http::Request<B>) -> Span {
+    let (method, path) = (req.method(), req.uri().path());
+    let span = tracing::info_span!("request",
+        otel.name = %format!("{method} {path}"), otel.kind = "server",
+        http.request.method = %method, url.path = %path, http.response.status_code = tracing::field::Empty);
+    let _ = span.set_parent(global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(req.headers()))));
+    span
+}
+
+pub fn record_status<B>(res: &axum::http::Response<B>, _latency: Duration, span: &Span) {
+    span.record("http.response.status_code", res.status().as_u16());
+}
+
+/// `axum::Json` with `parse` and `serialize` spans; see the module comment.
+pub struct Json<T>(pub T);
+
+#[axum::async_trait]
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for Json<T> {
+    type Rejection = JsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let span = tracing::info_span!("parse", bytes = tracing::field::Empty);
+        if let Some(n) = req.headers().get(axum::http::header::CONTENT_LENGTH).and_then(|v| v.to_str().ok()) { span.record("bytes", n); }
+        let axum::Json(v) = axum::Json::<T>::from_request(req, state).instrument(span).await?;
+        Ok(Json(v))
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        tracing::info_span!("serialize").in_scope(|| axum::Json(self.0).into_response())
+    }
+}
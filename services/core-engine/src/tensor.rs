@@ -0,0 +1,648 @@
+// ── N-dimensional tensors ───────────────────────────────────
+// Strided views over shared row-major buffers. Broadcasting follows
+// NumPy: shapes are right-aligned and size-1 (or missing) dims stretch via
+// a zero stride, so `64x128 + 128` never copies the vector.
+use crate::layout::{self, SliceSpec};
+use crate::limits::Limits;
+use crate::{api_err, ApiError, AppState};
+use crate::telemetry::Json;
+use axum::{extract::State, http::StatusCode, routing::post, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
+
+#[derive(Clone, Debug)]
+pub struct Tensor {
+    pub shape: Vec<usize>,
+    pub strides: Vec<isize>,
+    pub offset: usize,
+    pub data: Arc<Vec<f64>>,
+}
+
+pub fn contiguous_strides(shape: &[usize]) -> Vec<isize> {
+    let mut strides = vec![0isize; shape.len()];
+    let mut acc = 1isize;
+    for (s, d) in strides.iter_mut().zip(shape).rev() {
+        *s = acc;
+        acc *= (*d).max(1) as isize;
+    }
+    strides
+}
+
+pub fn numel(shape: &[usize]) -> usize {
+    shape.iter().product()
+}
+
+/// `numel` for shapes that come from a request; `None` on overflow.
+pub fn checked_numel(shape: &[usize]) -> Option<usize> {
+    shape.iter().try_fold(1usize, |acc, d| acc.checked_mul(*d))
+}
+
+impl Tensor {
+    pub fn new(shape: Vec<usize>, data: Vec<f64>) -> Result<Self, String> {
+        let n = checked_numel(&shape).ok_or("shape overflows")?;
+        if n != data.len() {
+            return Err(format!("shape {shape:?} needs {n} elements, got {}", data.len()));
+        }
+        Ok(Self { strides: contiguous_strides(&shape), shape, offset: 0, data: Arc::new(data) })
+    }
+
+    pub fn scalar(v: f64) -> Self {
+        Self { shape: Vec::new(), strides: Vec::new(), offset: 0, data: Arc::new(vec![v]) }
+    }
+
+    pub fn ndim(&self) -> usize { self.shape.len() }
+    pub fn numel(&self) -> usize { numel(&self.shape) }
+
+    pub fn is_contiguous(&self) -> bool {
+        self.strides == contiguous_strides(&self.shape)
+    }
+
+    /// Materialises the view in row-major order.
+    pub fn to_vec(&self) -> Vec<f64> {
+        if self.is_contiguous() {
+            return self.data[self.offset..self.offset + self.numel()].to_vec();
+        }
+        map1(self, |x| x).data.as_ref().clone()
+    }
+
+    /// A contiguous tensor with the same logical contents.
+    pub fn contiguous(&self) -> Tensor {
+        if self.is_contiguous() && self.offset == 0 && self.data.len() == self.numel() { return self.clone(); }
+        Tensor { shape: self.shape.clone(), strides: contiguous_strides(&self.shape), offset: 0, data: Arc::new(self.to_vec()) }
+    }
+
+    /// Zero-copy view stretched to `shape` (which must be broadcast-compatible).
+    pub fn broadcast_to(&self, shape: &[usize]) -> Result<Tensor, String> {
+        if shape.len() < self.ndim() {
+            return Err(format!("cannot broadcast {:?} to {:?}", self.shape, shape));
+        }
+        let pad = shape.len() - self.ndim();
+        let mut strides = vec![0isize; shape.len()];
+        for (i, &d) in shape.iter().enumerate() {
+            if i < pad { continue; }
+            let (sd, ss) = (self.shape[i - pad], self.strides[i - pad]);
+            strides[i] = if sd == d { ss } else if sd == 1 { 0 } else {
+                return Err(format!("cannot broadcast {:?} to {:?}", self.shape, shape));
+            };
+        }
+        Ok(Tensor { shape: shape.to_vec(), strides, offset: self.offset, data: self.data.clone() })
+    }
+}
+
+pub fn broadcast_shapes(shapes: &[&[usize]]) -> Result<Vec<usize>, String> {
+    let ndim = shapes.iter().map(|s| s.len()).max().unwrap_or(0);
+    let mut out = vec![1usize; ndim];
+    for s in shapes {
+        let pad = ndim - s.len();
+        for (i, &d) in s.iter().enumerate() {
+            let o = &mut out[pad + i];
+            if *o == 1 { *o = d; } else if d != 1 && d != *o {
+                return Err(format!("shapes {:?} are not broadcast-compatible", shapes));
+            }
+        }
+    }
+    Ok(out)
+}
+
+// ── Strided iteration ───────────────────────────────────────
+/// Calls `f(bases, len)` once per innermost row of `shape`, where `bases`
+/// holds each operand's starting offset and rows advance by the operand's
+/// last stride.
+fn for_each_row<const K: usize>(shape: &[usize], strides: [&[isize]; K], offsets: [usize; K], mut f: impl FnMut([isize; K], usize)) {
+    if shape.contains(&0) { return; }
+    let nd = shape.len();
+    let mut base = offsets.map(|o| o as isize);
+    if nd == 0 { f(base, 1); return; }
+    let inner = shape[nd - 1];
+    let mut idx = vec![0usize; nd - 1];
+    loop {
+        f(base, inner);
+        let mut d = nd - 1;
+        loop {
+            if d == 0 { return; }
+            d -= 1;
+            idx[d] += 1;
+            for k in 0..K { base[k] += strides[k][d]; }
+            if idx[d] < shape[d] { break; }
+            for k in 0..K { base[k] -= strides[k][d] * shape[d] as isize; }
+            idx[d] = 0;
+        }
+    }
+}
+
+#[inline]
+fn last_stride(t: &Tensor) -> isize { t.strides.last().copied().unwrap_or(1) }
+
+pub fn map1(a: &Tensor, f: impl Fn(f64) -> f64) -> Tensor {
+    let mut out = Vec::with_capacity(a.numel());
+    let sa = last_stride(a);
+    let da = &a.data;
+    for_each_row(&a.shape, [&a.strides], [a.offset], |[ba], len| {
+        if sa == 1 {
+            out.extend(da[ba as usize..ba as usize + len].iter().map(|x| f(*x)));
+        } else {
+            out.extend((0..len as isize).map(|i| f(da[(ba + i * sa) as usize])));
+        }
+    });
+    Tensor { strides: contiguous_strides(&a.shape), shape: a.shape.clone(), offset: 0, data: Arc::new(out) }
+}
+
+pub fn map2(a: &Tensor, b: &Tensor, f: impl Fn(f64, f64) -> f64) -> Result<Tensor, String> {
+    let shape = broadcast_shapes(&[&a.shape, &b.shape])?;
+    let (a, b) = (a.broadcast_to(&shape)?, b.broadcast_to(&shape)?);
+    let mut out = Vec::with_capacity(numel(&shape));
+    let (sa, sb) = (last_stride(&a), last_stride(&b));
+    let (da, db) = (&a.data, &b.data);
+    for_each_row(&shape, [&a.strides, &b.strides], [a.offset, b.offset], |[ba, bb], len| {
+        let (ba, bb) = (ba as usize, bb as usize);
+        match (sa, sb) {
+            (1, 1) => out.extend(da[ba..ba + len].iter().zip(&db[bb..bb + len]).map(|(x, y)| f(*x, *y))),
+            (1, 0) => { let y = db[bb]; out.extend(da[ba..ba + len].iter().map(|x| f(*x, y))) }
+            (0, 1) => { let x = da[ba]; out.extend(db[bb..bb + len].iter().map(|y| f(x, *y))) }
+            _ => out.extend((0..len as isize).map(|i| f(da[(ba as isize + i * sa) as usize], db[(bb as isize + i * sb) as usize]))),
+        }
+    });
+    Ok(Tensor { strides: contiguous_strides(&shape), shape, offset: 0, data: Arc::new(out) })
+}
+
+pub fn map3(a: &Tensor, b: &Tensor, c: &Tensor, f: impl Fn(f64, f64, f64) -> f64) -> Result<Tensor, String> {
+    let shape = broadcast_shapes(&[&a.shape, &b.shape, &c.shape])?;
+    let (a, b, c) = (a.broadcast_to(&shape)?, b.broadcast_to(&shape)?, c.broadcast_to(&shape)?);
+    let mut out = Vec::with_capacity(numel(&shape));
+    let (sa, sb, sc) = (last_stride(&a), last_stride(&b), last_stride(&c));
+    let (da, db, dc) = (&a.data, &b.data, &c.data);
+    for_each_row(&shape, [&a.strides, &b.strides, &c.strides], [a.offset, b.offset, c.offset], |[ba, bb, bc], len| {
+        if (sa, sb, sc) == (1, 1, 1) {
+            let (ba, bb, bc) = (ba as usize, bb as usize, bc as usize);
+            out.extend(da[ba..ba + len].iter().zip(&db[bb..bb + len]).zip(&dc[bc..bc + len]).map(|((x, y), z)| f(*x, *y, *z)));
+        } else {
+            out.extend((0..len as isize).map(|i| f(
+                da[(ba + i * sa) as usize], db[(bb + i * sb) as usize], dc[(bc + i * sc) as usize],
+            )));
+        }
+    });
+    Ok(Tensor { strides: contiguous_strides(&shape), shape, offset: 0, data: Arc::new(out) })
+}
+
+// ── Reductions ──────────────────────────────────────────────
+#[derive(Clone, Copy, PartialEq)]
+pub enum Reduce { Sum, Mean, Max, Min, Prod, Norm, Argmax, Argmin }
+
+impl Reduce {
+    pub fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "sum" => Reduce::Sum, "mean" => Reduce::Mean, "max" | "reduce_max" => Reduce::Max,
+            "min" | "reduce_min" => Reduce::Min, "prod" => Reduce::Prod, "norm" => Reduce::Norm,
+            "argmax" => Reduce::Argmax, "argmin" => Reduce::Argmin,
+            _ => return None,
+        })
+    }
+}
+
+/// Normalises (possibly negative) axes; `None` means every axis.
+pub fn resolve_axes(ndim: usize, axes: Option<&[isize]>) -> Result<Vec<usize>, String> {
+    let Some(axes) = axes else { return Ok((0..ndim).collect()) };
+    let mut out = Vec::with_capacity(axes.len());
+    for &a in axes {
+        let r = if a < 0 { a + ndim as isize } else { a };
+        if r < 0 || r >= ndim as isize { return Err(format!("axis {a} out of range for {ndim}-d tensor")); }
+        if out.contains(&(r as usize)) { return Err(format!("duplicate axis {a}")); }
+        out.push(r as usize);
+    }
+    out.sort_unstable();
+    Ok(out)
+}
+
+/// Reduces `t` over `axes`. Argmax/argmin return the flat index within
+/// the reduced sub-space (the axis index when reducing a single axis).
+pub fn reduce(t: &Tensor, axes: &[usize], keepdims: bool, op: Reduce) -> Tensor {
+    let kept: Vec<usize> = (0..t.ndim()).filter(|d| !axes.contains(d)).collect();
+    let red_shape: Vec<usize> = axes.iter().map(|&d| t.shape[d]).collect();
+    let red_strides: Vec<isize> = axes.iter().map(|&d| t.strides[d]).collect();
+    let kept_shape: Vec<usize> = kept.iter().map(|&d| t.shape[d]).collect();
+    let kept_strides: Vec<isize> = kept.iter().map(|&d| t.strides[d]).collect();
+    let count = numel(&red_shape);
+
+    let mut out = Vec::with_capacity(numel(&kept_shape));
+    let data = &t.data;
+    // Outer walk over kept positions; each "row" of length 1 is one output.
+    let mut outer_shape = kept_shape.clone();
+    outer_shape.push(1);
+    let mut outer_strides = kept_strides.clone();
+    outer_strides.push(0);
+    for_each_row(&outer_shape, [&outer_strides], [t.offset], |[base], _| {
+        let init = match op {
+            Reduce::Sum | Reduce::Mean | Reduce::Norm => 0.0,
+            Reduce::Prod => 1.0,
+            Reduce::Max | Reduce::Argmax => f64::NEG_INFINITY,
+            Reduce::Min | Reduce::Argmin => f64::INFINITY,
+        };
+        let (mut acc, mut arg, mut i) = (init, 0usize, 0usize);
+        let rs = red_strides.last().copied().unwrap_or(1);
+        for_each_row(&red_shape, [&red_strides], [base as usize], |[rb], len| {
+            if rs == 1 && matches!(op, Reduce::Sum | Reduce::Mean) {
+                acc += crate::simd::sum_f64(&data[rb as usize..rb as usize + len]);
+                i += len;
+                return;
+            }
+            for j in 0..len as isize {
+                let x = data[(rb + j * rs) as usize];
+                match op {
+                    Reduce::Sum | Reduce::Mean => acc += x,
+                    Reduce::Norm => acc += x * x,
+                    Reduce::Prod => acc *= x,
+                    Reduce::Max => acc = acc.max(x),
+                    Reduce::Min => acc = acc.min(x),
+                    Reduce::Argmax => if x > acc { acc = x; arg = i; },
+                    Reduce::Argmin => if x < acc { acc = x; arg = i; },
+                }
+                i += 1;
+            }
+        });
+        out.push(match op {
+            Reduce::Mean => if count > 0 { acc / count as f64 } else { f64::NAN },
+            Reduce::Norm => acc.sqrt(),
+            Reduce::Argmax | Reduce::Argmin => arg as f64,
+            _ => acc,
+        });
+    });
+
+    let shape: Vec<usize> = if keepdims {
+        (0..t.ndim()).map(|d| if axes.contains(&d) { 1 } else { t.shape[d] }).collect()
+    } else {
+        kept_shape
+    };
+    Tensor { strides: contiguous_strides(&shape), shape, offset: 0, data: Arc::new(out) }
+}
+
+// ── API ─────────────────────────────────────────────────────
+#[derive(Deserialize)]
+pub struct TensorInput { pub shape: Option<Vec<usize>>, pub data: Vec<f64> }
+
+impl TensorInput {
+    /// Shape defaults to 1-D.
+    pub fn into_tensor(self) -> Result<Tensor, String> {
+        let shape = self.shape.unwrap_or_else(|| vec![self.data.len()]);
+        Tensor::new(shape, self.data)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Axes { One(isize), Many(Vec<isize>) }
+
+impl Axes {
+    pub fn to_vec(&self) -> Vec<isize> {
+        match self { Axes::One(a) => vec![*a], Axes::Many(v) => v.clone() }
+    }
+}
+
+/// Operands and options shared by the top-level op and each pipeline step.
+#[derive(Deserialize, Default)]
+pub struct RawParams {
+    b: Option<TensorInput>,
+    c: Option<TensorInput>,
+    tensors: Option<Vec<TensorInput>>,
+    scalar: Option<f64>,
+    axis: Option<Axes>,
+    keepdims: Option<bool>,
+    shape: Option<Vec<isize>>,
+    perm: Option<Vec<usize>>,
+    slices: Option<Vec<SliceSpec>>,
+    reps: Option<Vec<usize>>,
+    repeats: Option<usize>,
+    pad_width: Option<Vec<[usize; 2]>>,
+    mode: Option<String>,
+    indices: Option<Vec<isize>>,
+}
+
+#[derive(Default)]
+pub struct Params {
+    pub b: Option<Tensor>,
+    pub c: Option<Tensor>,
+    pub tensors: Vec<Tensor>,
+    pub scalar: Option<f64>,
+    pub axis: Option<Vec<isize>>,
+    pub keepdims: bool,
+    pub shape: Option<Vec<isize>>,
+    pub perm: Option<Vec<usize>>,
+    pub slices: Vec<SliceSpec>,
+    pub reps: Option<Vec<usize>>,
+    pub repeats: Option<usize>,
+    pub pad_width: Option<Vec<[usize; 2]>>,
+    pub mode: Option<String>,
+    pub indices: Option<Vec<isize>>,
+}
+
+impl RawParams {
+    pub fn resolve(self) -> Result<Params, String> {
+        Ok(Params {
+            b: self.b.map(|b| b.into_tensor()).transpose()?,
+            c: self.c.map(|c| c.into_tensor()).transpose()?,
+            tensors: self.tensors.unwrap_or_default().into_iter().map(|t| t.into_tensor()).collect::<Result<_, _>>()?,
+            scalar: self.scalar,
+            axis: self.axis.map(|a| a.to_vec()),
+            keepdims: self.keepdims.unwrap_or(false),
+            shape: self.shape, perm: self.perm, slices: self.slices.unwrap_or_default(),
+            reps: self.reps, repeats: self.repeats, pad_width: self.pad_width, mode: self.mode,
+            indices: self.indices,
+        })
+    }
+}
+
+impl Params {
+    /// The single axis a layout op works on.
+    fn one_axis(&self, default: isize) -> Result<isize, String> {
+        match self.axis.as_deref() {
+            None => Ok(default),
+            Some([a]) => Ok(*a),
+            Some(_) => Err("this operation takes a single axis".into()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Step { operation: String, #[serde(flatten)] params: RawParams }
+
+#[derive(Deserialize)]
+pub(crate) struct TensorRequest {
+    operation: String,
+    a: TensorInput,
+    #[serde(flatten)]
+    params: RawParams,
+    /// Further ops applied in order, each taking the previous result as `a`.
+    steps: Option<Vec<Step>>,
+}
+
+#[derive(Serialize)]
+pub struct TensorResponse {
+    pub operation: String, pub shape: Vec<usize>, pub result: serde_json::Value, pub scalar_result: Option<f64>,
+    pub input_size: usize, pub elapsed_ns: u128, pub throughput_gflops: f64,
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new().route("/api/v1/simd/tensor", post(tensor))
+}
+
+/// Operand `b`/`c`, falling back to a scalar when absent.
+fn operand(t: &Option<Tensor>, default: f64) -> Tensor {
+    t.clone().unwrap_or_else(|| Tensor::scalar(default))
+}
+
+/// Applies one element-wise, reduction or layout op. Returns `Ok(None)`
+/// for an unknown operation name.
+pub fn apply(op: &str, a: &Tensor, p: &Params) -> Result<Option<Tensor>, String> {
+    let (b, c) = (&p.b, &p.c);
+    let s = p.scalar.unwrap_or(1.0);
+    let axes = p.axis.as_deref();
+    let keepdims = p.keepdims;
+    let need = |what: &str| format!("{op} requires `{what}`");
+    let out = match op {
+        "add" => map2(a, &operand(b, 0.0), |x, y| x + y)?,
+        "sub" => map2(a, &operand(b, 0.0), |x, y| x - y)?,
+        "mul" => map2(a, &operand(b, 1.0), |x, y| x * y)?,
+        "div" => map2(a, &operand(b, 1.0), |x, y| x / y)?,
+        // With `b` these are element-wise; without, they reduce (see below).
+        "min" if b.is_some() => map2(a, &operand(b, f64::MAX), f64::min)?,
+        "max" if b.is_some() => map2(a, &operand(b, f64::MIN), f64::max)?,
+        "pow" => map2(a, &operand(b, s), f64::powf)?,
+        // a * b + (c | scalar)
+        "fma" => map3(a, &operand(b, 0.0), &c.clone().unwrap_or_else(|| Tensor::scalar(s)), |x, y, z| x.mul_add(y, z))?,
+        // a + (b - a) * (c | scalar)
+        "lerp" => {
+            let t = c.clone().unwrap_or_else(|| Tensor::scalar(s.clamp(0.0, 1.0)));
+            map3(a, &operand(b, 0.0), &t, |x, y, t| x + (y - x) * t)?
+        }
+        "clamp" => map3(a, &operand(b, 0.0), &operand(c, 1.0), |x, lo, hi| x.max(lo).min(hi))?,
+        "scale" => map1(a, |x| x * s),
+        "neg" => map1(a, |x| -x),
+        "abs" => map1(a, f64::abs),
+        "sqrt" => map1(a, f64::sqrt),
+        "exp" => map1(a, f64::exp),
+        "log" => map1(a, f64::ln),
+        "dot_product" => {
+            let prod = map2(a, &operand(b, 0.0), |x, y| x * y)?;
+            let axes = resolve_axes(prod.ndim(), axes)?;
+            reduce(&prod, &axes, keepdims, Reduce::Sum)
+        }
+        "normalize" => {
+            let axes = resolve_axes(a.ndim(), axes)?;
+            let norm = reduce(a, &axes, true, Reduce::Norm);
+            map2(a, &norm, |x, n| if n > 1e-15 { x / n } else { 0.0 })?
+        }
+        "var" | "std" => {
+            let axes = resolve_axes(a.ndim(), axes)?;
+            let mean = reduce(a, &axes, true, Reduce::Mean);
+            let sq = map2(a, &mean, |x, m| (x - m) * (x - m))?;
+            let var = reduce(&sq, &axes, keepdims, Reduce::Mean);
+            if op == "std" { map1(&var, f64::sqrt) } else { var }
+        }
+        // Layout
+        "reshape" => layout::reshape(a, p.shape.as_deref().ok_or_else(|| need("shape"))?)?,
+        "flatten" => layout::reshape(a, &[-1])?,
+        "transpose" | "permute" => layout::permute(a, p.perm.as_deref())?,
+        "slice" => layout::slice(a, &p.slices)?,
+        "concat" | "stack" => {
+            let mut all = vec![a.clone()];
+            all.extend(p.tensors.iter().cloned());
+            if op == "concat" { layout::concat(&all, p.one_axis(0)?)? } else { layout::stack(&all, p.one_axis(0)?)? }
+        }
+        "tile" => layout::tile(a, p.reps.as_deref().ok_or_else(|| need("reps"))?)?,
+        "repeat" => layout::repeat(a, p.repeats.ok_or_else(|| need("repeats"))?, p.axis.as_ref().map(|_| p.one_axis(0)).transpose()?)?,
+        "pad" => layout::pad(a, p.pad_width.as_deref().ok_or_else(|| need("pad_width"))?, p.mode.as_deref().unwrap_or("constant"), p.scalar.unwrap_or(0.0))?,
+        "gather" => layout::gather(a, p.indices.as_deref().ok_or_else(|| need("indices"))?, p.one_axis(0)?)?,
+        "scatter_add" => layout::scatter_add(a, p.indices.as_deref().ok_or_else(|| need("indices"))?, b.as_ref().ok_or_else(|| need("b"))?, p.one_axis(0)?)?,
+        other => match Reduce::parse(other) {
+            Some(r) => reduce(a, &resolve_axes(a.ndim(), axes)?, keepdims, r),
+            None => return Ok(None),
+        },
+    };
+    Ok(Some(out))
+}
+
+/// Upper bound on what `apply` allocates for its result: the product of the
+/// output dims, each counted as at least 1, saturating. Checked against
+/// `max_elements` before every step, since layout ops and broadcasting can
+/// build outputs far larger than their inputs. Ops that would fail anyway
+/// fall back to the input's shape and report their error from `apply`.
+pub fn output_bound(op: &str, a: &Tensor, p: &Params) -> usize {
+    let bound = |dims: &[usize]| dims.iter().fold(1usize, |acc, d| acc.saturating_mul((*d).max(1)));
+    let axis = |default: isize| p.one_axis(default).ok().and_then(|ax| {
+        let ax = if ax < 0 { ax + a.ndim() as isize } else { ax };
+        usize::try_from(ax).ok().filter(|ax| *ax < a.ndim())
+    });
+    match op {
+        "tile" => {
+            let reps = p.reps.as_deref().unwrap_or(&[]);
+            let nd = a.ndim().max(reps.len());
+            let dim = |d: usize, dims: &[usize]| if d + dims.len() < nd { 1 } else { dims[d + dims.len() - nd] };
+            bound(&(0..nd).map(|d| dim(d, reps).saturating_mul(dim(d, &a.shape))).collect::<Vec<_>>())
+        }
+        "repeat" => bound(&a.shape).saturating_mul(p.repeats.unwrap_or(1)),
+        "pad" => {
+            let widths = p.pad_width.as_deref().unwrap_or(&[]);
+            bound(&a.shape.iter().zip(widths.iter().chain(std::iter::repeat(&[0, 0])))
+                .map(|(d, [before, after])| d.saturating_add(*before).saturating_add(*after)).collect::<Vec<_>>())
+        }
+        "concat" | "stack" => p.tensors.iter().fold(bound(&a.shape), |acc, t| acc.saturating_add(bound(&t.shape))),
+        "gather" => match axis(0) {
+            Some(ax) => {
+                let mut shape = a.shape.clone();
+                shape[ax] = p.indices.as_ref().map_or(0, Vec::len);
+                bound(&shape)
+            }
+            None => bound(&a.shape),
+        },
+        // A valid reshape keeps the element count; an invalid one fails in `apply`.
+        "reshape" | "flatten" | "scatter_add" => bound(&a.shape),
+        // Element-wise ops broadcast `a` against `b` and `c`.
+        _ => {
+            let shapes: Vec<&[usize]> = [Some(a), p.b.as_ref(), p.c.as_ref()].into_iter().flatten().map(|t| t.shape.as_slice()).collect();
+            broadcast_shapes(&shapes).map_or(bound(&a.shape), |s| bound(&s))
+        }
+    }
+}
+
+impl Params {
+    /// Elements in the operands a step brings with it.
+    fn input_elements(&self) -> usize {
+        [self.b.as_ref(), self.c.as_ref()].into_iter().flatten().chain(&self.tensors).map(Tensor::numel).sum()
+    }
+}
+
+pub fn respond(operation: String, out: &Tensor, input_size: usize, t: Instant) -> TensorResponse {
+    let (result, scalar_result) = if out.ndim() == 0 {
+        (serde_json::Value::Null, Some(out.data[out.offset]))
+    } else {
+        (serde_json::Value::Array(out.to_vec().into_iter().map(serde_json::Value::from).collect()), None)
+    };
+    let elapsed_ns = t.elapsed().as_nanos();
+    let flops = input_size.max(out.numel()).max(1) as f64;
+    TensorResponse {
+        operation, shape: out.shape.clone(), result, scalar_result, input_size, elapsed_ns,
+        throughput_gflops: if elapsed_ns > 0 { flops / elapsed_ns as f64 } else { 0.0 },
+    }
+}
+
+pub(crate) async fn tensor(State(s): State<Arc<AppState>>, limits: Limits, Json(req): Json<TensorRequest>) -> Result<Json<TensorResponse>, ApiError> {
+    let call = s.metrics.call("tensor", "tensor");
+    let t = Instant::now();
+    let bad = |e: String| api_err(StatusCode::BAD_REQUEST, "Invalid tensor operation", Some(e));
+    let validate = tracing::info_span!("validate").entered();
+    let a = req.a.into_tensor().map_err(bad)?;
+    let input_size = a.numel();
+    let mut ops = vec![(req.operation, req.params)];
+    ops.extend(req.steps.unwrap_or_default().into_iter().map(|st| (st.operation, st.params)));
+    let names: Vec<String> = ops.iter().map(|(o, _)| o.clone()).collect();
+    let ops: Vec<(String, Params)> = ops.into_iter().map(|(op, raw)| Ok((op, raw.resolve()?))).collect::<Result<_, String>>().map_err(bad)?;
+    limits.elements("tensor elements", ops.iter().map(|(_, p)| p.input_elements()).sum::<usize>().saturating_add(input_size))?;
+    drop(validate);
+    let mut cur = a;
+    let kernel = tracing::info_span!("kernel", operations = %names.join(" -> "), elements = input_size).entered();
+    for (op, p) in ops {
+        limits.elements(&format!("{op} output elements"), output_bound(&op, &cur, &p))?;
+        cur = apply(&op, &cur, &p)
+            .map_err(|e| bad(format!("{op}: {e}")))?
+            .ok_or_else(|| api_err(StatusCode::BAD_REQUEST, "Unknown tensor operation", Some(op.clone())))?;
+    }
+    drop(kernel);
+    s.stats.total_computes.fetch_add(1, Ordering::Relaxed);
+    call.finish(true, input_size, Default::default());
+    Ok(Json(respond(names.join(" -> "), &cur, input_size, t)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(shape: &[usize], data: &[f64]) -> Tensor {
+        Tensor::new(shape.to_vec(), data.to_vec()).unwrap()
+    }
+
+    /// 2×3 holding 1..=6.
+    fn m23() -> Tensor {
+        t(&[2, 3], &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0])
+    }
+
+    #[test]
+    fn strides_and_broadcasting_follow_numpy() {
+        assert_eq!(contiguous_strides(&[2, 3, 4]), [12, 4, 1]);
+        assert_eq!(contiguous_strides(&[2, 0, 3]), [3, 3, 1]);
+        assert!(Tensor::new(vec![usize::MAX, 2], vec![]).is_err());
+        assert!(Tensor::new(vec![2, 2], vec![1.0; 3]).is_err());
+
+        assert_eq!(broadcast_shapes(&[&[2, 1, 3], &[4, 1]]).unwrap(), [2, 4, 3]);
+        assert_eq!(broadcast_shapes(&[&[3], &[]]).unwrap(), [3]);
+        assert!(broadcast_shapes(&[&[2, 3], &[4]]).is_err());
+
+        let row = t(&[3], &[10.0, 20.0, 30.0]);
+        assert_eq!(row.broadcast_to(&[2, 3]).unwrap().strides, [0, 1]);
+        let col = t(&[2, 1], &[100.0, 200.0]);
+        assert_eq!(col.broadcast_to(&[2, 3]).unwrap().strides, [1, 0]);
+        assert!(row.broadcast_to(&[3, 2]).is_err());
+        assert!(m23().broadcast_to(&[3]).is_err());
+
+        let sum = map2(&m23(), &row, |x, y| x + y).unwrap();
+        assert_eq!((sum.shape.clone(), sum.to_vec()), (vec![2, 3], vec![11.0, 22.0, 33.0, 14.0, 25.0, 36.0]));
+        let outer = map2(&col, &row, |x, y| x + y).unwrap();
+        assert_eq!(outer.to_vec(), [110.0, 120.0, 130.0, 210.0, 220.0, 230.0]);
+        let fma = map3(&m23(), &Tensor::scalar(2.0), &col, |x, y, z| x * y + z).unwrap();
+        assert_eq!(fma.to_vec(), [102.0, 104.0, 106.0, 208.0, 210.0, 212.0]);
+    }
+
+    #[test]
+    fn reduces_over_axes() {
+        let a = m23();
+        let sum = |axes: &[usize], keepdims| reduce(&a, axes, keepdims, Reduce::Sum);
+        assert_eq!((sum(&[0], false).shape, sum(&[0], false).to_vec()), (vec![3], vec![5.0, 7.0, 9.0]));
+        assert_eq!(sum(&[1], false).to_vec(), [6.0, 15.0]);
+        assert_eq!(sum(&[1], true).shape, [2, 1]);
+        let all = sum(&[0, 1], false);
+        assert_eq!((all.ndim(), all.data[0]), (0, 21.0));
+
+        assert_eq!(reduce(&a, &[1], false, Reduce::Mean).to_vec(), [2.0, 5.0]);
+        assert_eq!(reduce(&a, &[0], false, Reduce::Prod).to_vec(), [4.0, 10.0, 18.0]);
+        assert_eq!(reduce(&a, &[0], false, Reduce::Min).to_vec(), [1.0, 2.0, 3.0]);
+        assert_eq!(reduce(&t(&[2], &[3.0, 4.0]), &[0], false, Reduce::Norm).to_vec(), [5.0]);
+        let b = t(&[2, 3], &[3.0, 1.0, 2.0, 0.0, 5.0, 4.0]);
+        assert_eq!(reduce(&b, &[1], false, Reduce::Argmax).to_vec(), [0.0, 1.0]);
+        assert_eq!(reduce(&b, &[0], false, Reduce::Argmin).to_vec(), [1.0, 0.0, 0.0]);
+
+        // A transposed (strided) view reduces like its materialised copy.
+        let at = Tensor { shape: vec![3, 2], strides: vec![1, 3], offset: 0, data: a.data.clone() };
+        assert!(!at.is_contiguous());
+        assert_eq!(reduce(&at, &[1], false, Reduce::Sum).to_vec(), [5.0, 7.0, 9.0]);
+        assert_eq!(reduce(&at, &[1], false, Reduce::Max).to_vec(), [4.0, 5.0, 6.0]);
+
+        assert_eq!(resolve_axes(3, Some(&[-1, 0])).unwrap(), [0, 2]);
+        assert_eq!(resolve_axes(2, None).unwrap(), [0, 1]);
+        assert!(resolve_axes(2, Some(&[2])).is_err());
+        assert!(resolve_axes(2, Some(&[1, -1])).is_err());
+
+        let p = Params { axis: Some(vec![1]), ..Default::default() };
+        assert_eq!(apply("var", &a, &p).unwrap().unwrap().to_vec(), [2.0 / 3.0, 2.0 / 3.0]);
+    }
+
+    #[test]
+    fn output_bound_caps_every_growing_op() {
+        let a = m23();
+        let with = |p: Params, op: &str| output_bound(op, &a, &p);
+        assert_eq!(with(Params { reps: Some(vec![2, 1, 2]), ..Default::default() }, "tile"), 2 * 2 * 6);
+        assert_eq!(with(Params { repeats: Some(4), ..Default::default() }, "repeat"), 24);
+        assert_eq!(with(Params { repeats: Some(usize::MAX), ..Default::default() }, "repeat"), usize::MAX);
+        assert_eq!(with(Params { pad_width: Some(vec![[1, 1], [0, 2]]), ..Default::default() }, "pad"), 4 * 5);
+        let tensors = vec![m23(), t(&[1, 3], &[0.0; 3])];
+        assert_eq!(with(Params { tensors, ..Default::default() }, "concat"), 6 + 6 + 3);
+        let gather = |axis| Params { axis: Some(vec![axis]), indices: Some(vec![0; 5]), ..Default::default() };
+        assert_eq!(with(gather(1), "gather"), 2 * 5);
+        assert_eq!(with(gather(-2), "gather"), 5 * 3);
+        assert_eq!(with(gather(7), "gather"), 6);
+        assert_eq!(with(Params { shape: Some(vec![-1]), ..Default::default() }, "reshape"), 6);
+        let b = Some(t(&[4, 1, 3], &[0.0; 12]));
+        assert_eq!(with(Params { b, ..Default::default() }, "add"), 24);
+        // Zero-length dims count as 1, so the bound never drops to 0.
+        assert_eq!(output_bound("neg", &t(&[0, 3], &[]), &Params::default()), 3);
+    }
+}
@@ -0,0 +1,218 @@
+// ── CPU topology ────────────────────────────────────────────
+// Model, core counts, NUMA nodes and the cache hierarchy, read once at
+// first use. On Linux the kernel's view in /sys/devices/system/cpu and
+// /sys/devices/system/node wins, because it accounts for the cgroup's
+// online CPUs and for how the hypervisor presents caches; CPUID (leaf 4 on
+// Intel, 0x8000001D on AMD) fills in the caches elsewhere. Anything that
+// cannot be determined is reported as zero or left out, never guessed.
+use serde::Serialize;
+use std::sync::OnceLock;
+
+#[derive(Clone, Serialize)]
+pub struct Cache {
+    pub level: u32,
+    /// "data", "instruction" or "unified".
+    pub kind: &'static str,
+    pub size_bytes: usize,
+    pub line_bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ways: Option<usize>,
+    /// Logical CPUs sharing one instance of this cache.
+    pub shared_by: usize,
+}
+
+#[derive(Serialize)]
+pub struct Topology {
+    pub vendor: String,
+    pub model: String,
+    pub logical_cpus: usize,
+    pub physical_cores: usize,
+    pub sockets: usize,
+    pub numa_nodes: usize,
+    pub cache_line_bytes: usize,
+    pub caches: Vec<Cache>,
+    /// Where `caches` came from: "sysfs", "cpuid" or "none".
+    pub cache_source: &'static str,
+}
+
+impl Topology {
+    /// The data (or unified) cache at `level`.
+    pub fn data_cache(&self, level: u32) -> Option<&Cache> {
+        self.caches.iter().find(|c| c.level == level && c.kind != "instruction")
+    }
+}
+
+pub fn get() -> &'static Topology {
+    static TOPOLOGY: OnceLock<Topology> = OnceLock::new();
+    TOPOLOGY.get_or_init(detect)
+}
+
+fn detect() -> Topology {
+    let (vendor, model) = identity();
+    let logical = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let (mut caches, mut source) = (sysfs::caches(), "sysfs");
+    if caches.is_empty() {
+        (caches, source) = (cpuid::caches(), "cpuid");
+    }
+    if caches.is_empty() { source = "none"; }
+    let (cores, sockets, nodes) = sysfs::cores().unwrap_or((0, 0, 0));
+    Topology {
+        vendor, model,
+        logical_cpus: logical,
+        physical_cores: cores,
+        sockets,
+        numa_nodes: nodes,
+        cache_line_bytes: caches.first().map(|c| c.line_bytes).or_else(cpuid::line_size).unwrap_or(0),
+        caches,
+        cache_source: source,
+    }
+}
+
+/// (vendor, model string): CPUID on x86_64, /proc/cpuinfo elsewhere.
+fn identity() -> (String, String) {
+    if let Some(id) = cpuid::identity() { return id; }
+    let info = std::fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    let field = |names: &[&str]| info.lines().find_map(|l| {
+        let (k, v) = l.split_once(':')?;
+        names.contains(&k.trim()).then(|| v.trim().to_string())
+    });
+    (field(&["vendor_id", "CPU implementer"]).unwrap_or_default(), field(&["model name", "Model", "Hardware"]).unwrap_or_default())
+}
+
+/// Number of CPUs in a kernel cpu list such as "0-3,8-11".
+fn count_cpu_list(list: &str) -> usize {
+    list.trim().split(',').filter(|r| !r.is_empty()).map(|r| match r.split_once('-') {
+        Some((a, b)) => b.parse::<usize>().unwrap_or(0).saturating_sub(a.parse().unwrap_or(0)) + 1,
+        None => 1,
+    }).sum()
+}
+
+mod sysfs {
+    use super::{count_cpu_list, Cache};
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    const CPU: &str = "/sys/devices/system/cpu";
+
+    fn read(path: impl AsRef<Path>) -> Option<String> {
+        std::fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+    }
+
+    /// "48K", "2048K", "32M" as bytes.
+    fn size(s: &str) -> Option<usize> {
+        let (digits, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+        let scale = match unit { "" => 1, "K" => 1 << 10, "M" => 1 << 20, "G" => 1 << 30, _ => return None };
+        Some(digits.parse::<usize>().ok()? * scale)
+    }
+
+    pub fn caches() -> Vec<Cache> {
+        let mut out = Vec::new();
+        for i in 0.. {
+            let dir = format!("{CPU}/cpu0/cache/index{i}");
+            let Some(level) = read(format!("{dir}/level")).and_then(|l| l.parse().ok()) else { break };
+            let kind = match read(format!("{dir}/type")).as_deref() {
+                Some("Data") => "data",
+                Some("Instruction") => "instruction",
+                _ => "unified",
+            };
+            let Some(size_bytes) = read(format!("{dir}/size")).and_then(|s| size(&s)) else { continue };
+            out.push(Cache {
+                level, kind, size_bytes,
+                line_bytes: read(format!("{dir}/coherency_line_size")).and_then(|s| s.parse().ok()).unwrap_or(64),
+                ways: read(format!("{dir}/ways_of_associativity")).and_then(|s| s.parse().ok()).filter(|&w| w > 0),
+                shared_by: read(format!("{dir}/shared_cpu_list")).map(|l| count_cpu_list(&l)).unwrap_or(1).max(1),
+            });
+        }
+        out
+    }
+
+    /// (physical cores, sockets, NUMA nodes) among the online CPUs.
+    pub fn cores() -> Option<(usize, usize, usize)> {
+        let online = read(format!("{CPU}/online"))?;
+        let (mut cores, mut sockets) = (HashSet::new(), HashSet::new());
+        for range in online.split(',') {
+            let (a, b) = range.split_once('-').unwrap_or((range, range));
+            for cpu in a.parse::<usize>().ok()?..=b.parse::<usize>().ok()? {
+                let topo = format!("{CPU}/cpu{cpu}/topology");
+                let package = read(format!("{topo}/physical_package_id")).unwrap_or_default();
+                let core = read(format!("{topo}/core_id")).unwrap_or_else(|| cpu.to_string());
+                cores.insert((package.clone(), core));
+                sockets.insert(package);
+            }
+        }
+        let nodes = std::fs::read_dir("/sys/devices/system/node").map(|d| d.flatten().filter(|e| {
+            e.file_name().to_str().and_then(|n| n.strip_prefix("node")).is_some_and(|n| n.parse::<usize>().is_ok())
+        }).count()).unwrap_or(0);
+        Some((cores.len(), sockets.len(), nodes.max(1)))
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod cpuid {
+    use super::Cache;
+    use std::arch::x86_64::{__cpuid, __cpuid_count};
+
+    /// ASCII packed little-endian into registers, NULs trimmed.
+    fn text(regs: &[u32]) -> String {
+        let bytes: Vec<u8> = regs.iter().flat_map(|r| r.to_le_bytes()).collect();
+        String::from_utf8_lossy(&bytes).trim_matches(char::from(0)).trim().to_string()
+    }
+
+    pub fn identity() -> Option<(String, String)> {
+        let v = __cpuid(0);
+        let vendor = text(&[v.ebx, v.edx, v.ecx]);
+        let mut brand = Vec::with_capacity(12);
+        if __cpuid(0x8000_0000).eax >= 0x8000_0004 {
+            for leaf in 0x8000_0002..=0x8000_0004 {
+                let r = __cpuid(leaf);
+                brand.extend([r.eax, r.ebx, r.ecx, r.edx]);
+            }
+        }
+        Some((vendor, text(&brand)))
+    }
+
+    /// Deterministic cache parameters: leaf 4 on Intel, 0x8000001D on AMD
+    /// (same register layout).
+    pub fn caches() -> Vec<Cache> {
+        let leaf = match identity().map(|(v, _)| v).as_deref() {
+            Some("GenuineIntel") if __cpuid(0).eax >= 4 => 4,
+            Some("AuthenticAMD") if __cpuid(0x8000_0000).eax >= 0x8000_001d => 0x8000_001d,
+            _ => return Vec::new(),
+        };
+        let mut out = Vec::new();
+        for sub in 0..16 {
+            let r = __cpuid_count(leaf, sub);
+            let kind = match r.eax & 0x1f {
+                0 => break,
+                1 => "data",
+                2 => "instruction",
+                _ => "unified",
+            };
+            let ways = (r.ebx >> 22) as usize + 1;
+            let partitions = ((r.ebx >> 12) & 0x3ff) as usize + 1;
+            let line = (r.ebx & 0xfff) as usize + 1;
+            let sets = r.ecx as usize + 1;
+            out.push(Cache {
+                level: (r.eax >> 5) & 7, kind,
+                size_bytes: ways * partitions * line * sets,
+                line_bytes: line,
+                ways: Some(ways),
+                shared_by: ((r.eax >> 14) & 0xfff) as usize + 1,
+            });
+        }
+        out
+    }
+
+    /// CLFLUSH line size from leaf 1.
+    pub fn line_size() -> Option<usize> {
+        Some(((__cpuid(1).ebx >> 8) & 0xff) as usize * 8).filter(|&l| l > 0)
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod cpuid {
+    use super::Cache;
+    pub fn identity() -> Option<(String, String)> { None }
+    pub fn caches() -> Vec<Cache> { Vec::new() }
+    pub fn line_size() -> Option<usize> { None }
+}